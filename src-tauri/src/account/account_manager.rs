@@ -1,34 +1,265 @@
 use anyhow::{anyhow, Result};
+use std::collections::HashMap;
 use std::fs;
+use std::future::Future;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard};
+use chrono_tz::Tz;
 
+use super::batch::{EntitlementFetch, EntitlementRequest};
+use super::events::{AccountEvent, AccountEvents};
 use super::types::*;
-use crate::api::{TraeApiClient, UsageSummary, UsageQueryResponse};
+use crate::alerts::{Alert, AlertCenter, AlertMetric};
+use crate::anomalies::{self, AnomalyDetector, UsageAnomaly};
+use crate::budgets::{self, BudgetStatus, BudgetTracker};
+use crate::analytics::{
+    self, ActivityHeatmap, BillingCycle, BurnRateForecast, CacheEfficiencyReport, CacheReportQuery, CostEstimateQuery,
+    CostEstimateReport, CycleComparison, CycleComparisonQuery, CycleRange, CycleUsage, EventSource, GroupBy, HeatmapFailure,
+    HeatmapQuery, PriceTable, PriceTableView, UsageAnalytics, UsageAnalyticsQuery,
+};
+use crate::webhooks::{WebhookDispatcher, WebhookEvent, WebhookEventKind};
+use crate::history::{SyncProgress, SyncReport, SyncState, UsageHistory};
+use crate::api::{
+    self, EntitlementListResponse, HttpTraeApi, PackageCatalog, SubscriptionInfo, TraeApi, TraeApiClient, UsageDetail,
+    UsageSession, UsageSummary, UsageQueryResponse, UserTokenResult,
+};
+
+/// 同步使用事件时的分页大小
+const SYNC_PAGE_SIZE: i32 = 100;
+
+/// 用户自定义礼包目录文件名（与 accounts.json 位于同一目录）
+const PACKAGE_CATALOG_FILE: &str = "package_catalog.json";
+
+/// 用户导入的模型价格表文件名（与 accounts.json 位于同一目录）
+const PRICE_TABLE_FILE: &str = "price_table.json";
 
 /// 账号管理器
+///
+/// 内部数据各自加锁，方法只需要 `&self`。网络请求前先复制所需的账号数据并释放锁，
+/// 请求返回后重新加锁写回结果，写回前检查账号是否已被删除或凭据是否已被修改，
+/// 因此慢请求不会阻塞账号列表等只读操作。
 pub struct AccountManager {
-    store: AccountStore,
+    /// 账号数据（只在读写内存数据时短暂加锁，不能跨越 await 持有）
+    store: Mutex<AccountStore>,
     data_path: PathBuf,
+    api: Arc<dyn TraeApi>,
+    /// 每个账号最近一次配额响应的诊断信息（仅保存在内存中）
+    diagnostics: Mutex<HashMap<String, EntitlementDiagnostics>>,
+    /// 礼包目录（内置 + 用户覆盖）
+    catalog: RwLock<PackageCatalog>,
+    /// 模型价格表（内置 + 用户导入）
+    prices: RwLock<PriceTable>,
+    /// 使用量历史（未设置时不记录）
+    history: Option<Arc<UsageHistory>>,
+    /// 每个账号最近一次额度消耗预测（仅保存在内存中）
+    forecasts: Mutex<HashMap<String, BurnRateForecast>>,
+    /// 每个账号当前的刷新失败类型（状态变化时才发送 Webhook，仅保存在内存中）
+    refresh_failures: Mutex<HashMap<String, WebhookEventKind>>,
+    /// 告警中心（未设置时不评估告警）
+    alerts: Option<Arc<AlertCenter>>,
+    /// Webhook 投递器（未设置时不发送）
+    webhooks: Option<Arc<WebhookDispatcher>>,
+    /// 预算管理（未设置时不计算预算）
+    budgets: Option<Arc<BudgetTracker>>,
+    /// 用量异常检测（未设置时不检测）
+    anomalies: Option<Arc<AnomalyDetector>>,
+    /// 领域事件总线
+    events: AccountEvents,
 }
 
 impl AccountManager {
     /// 创建账号管理器
     pub fn new() -> Result<Self> {
         let data_path = Self::get_data_path()?;
+        Self::with_api(data_path, Arc::new(HttpTraeApi))
+    }
+
+    /// 使用指定的存储路径和 API 实现创建账号管理器
+    pub fn with_api(data_path: PathBuf, api: Arc<dyn TraeApi>) -> Result<Self> {
         let store = Self::load_store(&data_path)?;
+        let catalog = PackageCatalog::load(&data_path.with_file_name(PACKAGE_CATALOG_FILE));
+        let prices = PriceTable::load(&data_path.with_file_name(PRICE_TABLE_FILE));
+
+        Ok(Self {
+            store: Mutex::new(store),
+            data_path,
+            api,
+            diagnostics: Mutex::new(HashMap::new()),
+            catalog: RwLock::new(catalog),
+            prices: RwLock::new(prices),
+            history: None,
+            forecasts: Mutex::new(HashMap::new()),
+            refresh_failures: Mutex::new(HashMap::new()),
+            alerts: None,
+            webhooks: None,
+            budgets: None,
+            anomalies: None,
+            events: AccountEvents::new(),
+        })
+    }
 
-        Ok(Self { store, data_path })
+    /// 设置使用量历史，之后每次刷新使用量都会记录快照
+    pub fn set_history(&mut self, history: Arc<UsageHistory>) {
+        self.history = Some(history);
     }
 
-    /// 获取数据存储路径
-    fn get_data_path() -> Result<PathBuf> {
+    /// 设置告警中心，之后每次刷新使用量都会评估告警规则
+    pub fn set_alerts(&mut self, alerts: Arc<AlertCenter>) {
+        self.alerts = Some(alerts);
+    }
+
+    /// 使用量刷新后记录快照并评估告警（失败只打印警告，不影响刷新结果）
+    fn on_usage_refreshed(&self, account: &Account, summary: &UsageSummary) {
+        if let Some(history) = &self.history {
+            if let Err(e) = history.record_snapshot(&account.id, summary) {
+                println!("[WARN] 记录使用量历史失败: {}", e);
+            }
+        }
+
+        if let Some(alerts) = &self.alerts {
+            match alerts.evaluate(account, summary) {
+                Ok(raised) => raised.iter().for_each(|alert| self.dispatch_alert(alert)),
+                Err(e) => println!("[WARN] 评估告警规则失败: {}", e),
+            }
+        }
+    }
+
+    /// 设置预算管理
+    pub fn set_budgets(&mut self, budgets: Arc<BudgetTracker>) {
+        self.budgets = Some(budgets);
+    }
+
+    /// 设置用量异常检测
+    pub fn set_anomalies(&mut self, anomalies: Arc<AnomalyDetector>) {
+        self.anomalies = Some(anomalies);
+    }
+
+    /// 订阅账号领域事件
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<AccountEvent> {
+        self.events.subscribe()
+    }
+
+    /// 生成账号简要信息（附带预测和预算）
+    fn account_brief(&self, store: &AccountStore, account: &Account) -> AccountBrief {
+        let is_current = store.current_account_id.as_deref() == Some(account.id.as_str());
+        let mut brief = AccountBrief::from_account(account, is_current);
+        brief.forecast = self.lock_forecasts().get(&account.id).map(BurnRateForecast::brief);
+        brief.budget = self.budgets.as_ref().and_then(|budgets| budgets.status(&account.id));
+        brief
+    }
+
+    /// 发布账号信息被修改的事件
+    fn publish_updated(&self, store: &AccountStore, account_id: &str) {
+        if let Some(account) = store.accounts.iter().find(|a| a.id == account_id) {
+            let account = self.account_brief(store, account);
+            self.events.publish(AccountEvent::AccountUpdated { account });
+        }
+    }
+
+    /// 设置 Webhook 投递器
+    pub fn set_webhooks(&mut self, webhooks: Arc<WebhookDispatcher>) {
+        self.webhooks = Some(webhooks);
+    }
+
+    /// 发送 Webhook 事件
+    fn dispatch_webhook(&self, event: WebhookEventKind, account_id: &str, message: String, data: serde_json::Value) {
+        let Some(webhooks) = &self.webhooks else {
+            return;
+        };
+        let account_name = self
+            .store()
+            .accounts
+            .iter()
+            .find(|a| a.id == account_id)
+            .map(|a| a.email.clone())
+            .unwrap_or_default();
+
+        webhooks.dispatch(WebhookEvent {
+            event,
+            account_id: account_id.to_string(),
+            account_name,
+            message,
+            occurred_at: chrono::Utc::now().timestamp(),
+            data,
+        });
+    }
+
+    /// 新触发的告警转为 Webhook 事件
+    fn dispatch_alert(&self, alert: &Alert) {
+        let event = if alert.metric == AlertMetric::TokenExpiresInHours {
+            WebhookEventKind::TokenExpiry
+        } else {
+            WebhookEventKind::QuotaThreshold
+        };
+        let data = serde_json::to_value(alert).unwrap_or_default();
+        self.dispatch_webhook(event, &alert.account_id, alert.message.clone(), data);
+    }
+
+    /// 刷新失败时发送 Webhook 事件（区分凭据失效和其它错误）
+    ///
+    /// 只在账号从正常变为失败、或失败类型改变时发送，持续失败不会重复发送。
+    fn report_refresh_failure(&self, account_id: &str, error: &anyhow::Error) {
+        let Some(has_credentials) = self
+            .store()
+            .accounts
+            .iter()
+            .find(|a| a.id == account_id)
+            .map(|a| a.jwt_token.is_some() || !a.cookies.is_empty())
+        else {
+            return;
+        };
+        let message = error.to_string();
+        let credentials_invalid = api::is_unauthorized(error) || !has_credentials;
+        let event = if credentials_invalid {
+            WebhookEventKind::CredentialsInvalid
+        } else {
+            WebhookEventKind::RefreshFailed
+        };
+
+        let previous = self
+            .refresh_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(account_id.to_string(), event);
+        if previous == Some(event) {
+            return;
+        }
+        self.dispatch_webhook(event, account_id, message.clone(), serde_json::json!({ "error": message }));
+    }
+
+    /// 刷新成功后清除账号的失败状态
+    fn clear_refresh_failure(&self, account_id: &str) {
+        self.refresh_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(account_id);
+    }
+
+    /// 获取应用数据目录
+    pub fn data_dir() -> Result<PathBuf> {
         let proj_dirs = directories::ProjectDirs::from("com", "sauce", "trae-account-manager")
             .ok_or_else(|| anyhow!("无法获取应用数据目录"))?;
 
         let data_dir = proj_dirs.data_dir();
         fs::create_dir_all(data_dir)?;
 
-        Ok(data_dir.join("accounts.json"))
+        Ok(data_dir.to_path_buf())
+    }
+
+    /// 获取数据存储路径
+    fn get_data_path() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("accounts.json"))
+    }
+
+    /// 从磁盘重新加载账号数据（其它进程修改了 accounts.json 后调用）
+    pub fn reload_store(&self) -> Result<usize> {
+        let reloaded = Self::load_store(&self.data_path)?;
+        let account_count = reloaded.accounts.len();
+        *self.store() = reloaded;
+
+        println!("[INFO] 已重新加载 {} 个账号", account_count);
+        self.events.publish(AccountEvent::StoreReloaded { account_count });
+        Ok(account_count)
     }
 
     /// 加载账号存储
@@ -42,30 +273,133 @@ impl AccountManager {
         }
     }
 
+    /// 锁定账号数据（持锁时发生 panic 不会破坏数据结构，直接继续使用）
+    fn store(&self) -> MutexGuard<'_, AccountStore> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn catalog(&self) -> RwLockReadGuard<'_, PackageCatalog> {
+        self.catalog.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn prices(&self) -> RwLockReadGuard<'_, PriceTable> {
+        self.prices.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// 保存账号存储
-    fn save_store(&self) -> Result<()> {
-        let content = serde_json::to_string_pretty(&self.store)?;
+    pub fn save_store(&self) -> Result<()> {
+        self.persist(&self.store())
+    }
+
+    /// 在已持有锁时写入账号存储（写文件也在锁内进行，保证文件内容与内存一致的先后顺序）
+    fn persist(&self, store: &AccountStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(store)?;
         fs::write(&self.data_path, content)?;
         Ok(())
     }
 
-    /// 添加账号（通过 cookies）
-    pub async fn add_account(&mut self, cookies: String) -> Result<Account> {
-        let mut client = TraeApiClient::new(&cookies)?;
+    /// 复制账号数据，供之后不持锁的网络请求使用
+    fn account_snapshot(&self, account_id: &str) -> Result<Account> {
+        self.store()
+            .accounts
+            .iter()
+            .find(|a| a.id == account_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("账号不存在"))
+    }
+
+    /// 网络请求返回后取回要写入结果的账号
+    fn refreshed_account<'a>(store: &'a mut AccountStore, account_id: &str) -> Result<&'a mut Account> {
+        store
+            .accounts
+            .iter_mut()
+            .find(|a| a.id == account_id)
+            .ok_or_else(|| anyhow!("账号在刷新期间已被删除"))
+    }
 
+    /// 写回用快照凭据刷新得到的 Token；凭据在请求期间被修改时丢弃，避免覆盖更新的凭据
+    fn merge_refreshed_token(&self, store: &mut AccountStore, snapshot: &Account, token_result: &UserTokenResult) -> Result<()> {
+        let acc = Self::refreshed_account(store, &snapshot.id)?;
+        if !acc.same_credentials(snapshot) {
+            println!("[WARN] 账号 {} 的凭据在刷新期间已被修改，丢弃刷新得到的 Token", acc.email);
+            return Ok(());
+        }
+        acc.jwt_token = Some(token_result.token.clone());
+        acc.token_expired_at = Some(token_result.expired_at.clone());
+        self.events.publish(AccountEvent::TokenRefreshed {
+            account_id: acc.id.clone(),
+            expired_at: acc.token_expired_at.clone(),
+        });
+        Ok(())
+    }
+
+    /// 使用 Token 调用接口，返回 401 且账号有 Cookies 时用 Cookies 换取新 Token 后重试一次
+    ///
+    /// 返回调用结果和换取到的新 Token（由调用方用 `merge_refreshed_token` 写回）。
+    pub(crate) async fn call_with_token_refresh<T, F, Fut>(
+        api: &dyn TraeApi,
+        account: &Account,
+        token: &str,
+        call: F,
+    ) -> (Result<T>, Option<UserTokenResult>)
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let error = match call(token.to_string()).await {
+            Ok(value) => return (Ok(value), None),
+            Err(e) => e,
+        };
+        if !api::is_unauthorized(&error) {
+            return (Err(error), None);
+        }
+        if account.cookies.is_empty() {
+            return (Err(error.context("Token 已过期，请更新 Token 或 Cookies")), None);
+        }
+
+        println!("[INFO] Token 已过期，尝试使用 Cookies 刷新...");
+        let token_result = match api.get_user_token(&account.cookies).await {
+            Ok(token_result) => token_result,
+            Err(e) => return (Err(e), None),
+        };
+        let result = call(token_result.token.clone()).await;
+        (result, Some(token_result))
+    }
+
+    /// 添加新账号（重新检查是否已存在，第一个账号设为活跃账号）
+    fn insert_account(&self, account: Account) -> Result<Account> {
+        let mut store = self.store();
+        if store.accounts.iter().any(|a| a.user_id == account.user_id) {
+            return Err(anyhow!("该账号已存在"));
+        }
+        store.accounts.push(account.clone());
+
+        // 如果是第一个账号，设为活跃账号
+        if store.active_account_id.is_none() {
+            store.active_account_id = Some(account.id.clone());
+        }
+
+        self.persist(&store)?;
+        let brief = self.account_brief(&store, &account);
+        self.events.publish(AccountEvent::AccountAdded { account: brief });
+        Ok(account)
+    }
+
+    /// 是否已添加了该用户的账号
+    fn has_user(&self, user_id: &str) -> bool {
+        self.store().accounts.iter().any(|a| a.user_id == user_id)
+    }
+
+    /// 添加账号（通过 cookies）
+    pub async fn add_account(&self, cookies: String) -> Result<Account> {
         // 获取 token
-        let token_result = client.get_user_token().await?;
+        let token_result = self.api.get_user_token(&cookies).await?;
 
         // 获取用户信息
-        let user_info = client.get_user_info().await?;
+        let user_info = self.api.get_user_info(&cookies).await?;
 
         // 检查是否已存在
-        if self
-            .store
-            .accounts
-            .iter()
-            .any(|a| a.user_id == token_result.user_id)
-        {
+        if self.has_user(&token_result.user_id) {
             return Err(anyhow!("该账号已存在"));
         }
 
@@ -82,37 +416,22 @@ impl AccountManager {
         account.jwt_token = Some(token_result.token);
         account.token_expired_at = Some(token_result.expired_at);
 
-        self.store.accounts.push(account.clone());
-
-        // 如果是第一个账号，设为活跃账号
-        if self.store.active_account_id.is_none() {
-            self.store.active_account_id = Some(account.id.clone());
-        }
-
-        self.save_store()?;
-        Ok(account)
+        self.insert_account(account)
     }
 
     /// 添加账号（通过 Token，可选 Cookies）
-    pub async fn add_account_by_token(&mut self, token: String, cookies: Option<String>) -> Result<Account> {
-        let client = TraeApiClient::new_with_token(&token)?;
-
+    pub async fn add_account_by_token(&self, token: String, cookies: Option<String>) -> Result<Account> {
         // 通过 Token 获取用户信息
-        let user_info = client.get_user_info_by_token().await?;
+        let user_info = self.api.get_user_info_by_token(&token).await?;
 
         // 检查是否已存在
-        if self
-            .store
-            .accounts
-            .iter()
-            .any(|a| a.user_id == user_info.user_id)
-        {
+        if self.has_user(&user_info.user_id) {
             return Err(anyhow!("该账号已存在"));
         }
 
         // 如果提供了 Cookies，尝试获取更详细的用户信息
         let (name, email, avatar_url) = if let Some(ref cookies_str) = cookies {
-            match self.get_user_info_with_cookies(cookies_str).await {
+            match self.api.get_user_info(cookies_str).await {
                 Ok(info) => (
                     info.screen_name,
                     info.non_plain_text_email.unwrap_or_default(),
@@ -144,65 +463,96 @@ impl AccountManager {
         account.jwt_token = Some(token);
         account.token_expired_at = None;
 
-        self.store.accounts.push(account.clone());
-
-        // 如果是第一个账号，设为活跃账号
-        if self.store.active_account_id.is_none() {
-            self.store.active_account_id = Some(account.id.clone());
-        }
-
-        self.save_store()?;
-        Ok(account)
-    }
-
-    /// 使用 Cookies 获取用户信息
-    async fn get_user_info_with_cookies(&self, cookies: &str) -> Result<crate::api::UserInfoResult> {
-        let client = TraeApiClient::new(cookies)?;
-        client.get_user_info().await
+        self.insert_account(account)
     }
 
     /// 删除账号
-    pub fn remove_account(&mut self, account_id: &str) -> Result<()> {
-        let index = self
-            .store
-            .accounts
-            .iter()
-            .position(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?;
-
-        self.store.accounts.remove(index);
+    pub fn remove_account(&self, account_id: &str) -> Result<()> {
+        {
+            let mut store = self.store();
+            let index = store
+                .accounts
+                .iter()
+                .position(|a| a.id == account_id)
+                .ok_or_else(|| anyhow!("账号不存在"))?;
+
+            store.accounts.remove(index);
+
+            // 如果删除的是活跃账号，重置活跃账号
+            if store.active_account_id.as_deref() == Some(account_id) {
+                store.active_account_id = store.accounts.first().map(|a| a.id.clone());
+            }
 
-        // 如果删除的是活跃账号，重置活跃账号
-        if self.store.active_account_id.as_deref() == Some(account_id) {
-            self.store.active_account_id = self.store.accounts.first().map(|a| a.id.clone());
+            self.persist(&store)?;
+        }
+        self.lock_forecasts().remove(account_id);
+        self.lock_diagnostics().remove(account_id);
+        self.clear_refresh_failure(account_id);
+        self.events.publish(AccountEvent::AccountRemoved { account_id: account_id.to_string() });
+
+        if let Some(history) = &self.history {
+            if let Err(e) = history.delete_account(account_id) {
+                println!("[WARN] 删除账号历史失败: {}", e);
+            }
+        }
+        if let Some(alerts) = &self.alerts {
+            if let Err(e) = alerts.delete_account(account_id) {
+                println!("[WARN] 删除账号告警失败: {}", e);
+            }
+        }
+        if let Some(budgets) = &self.budgets {
+            if let Err(e) = budgets.remove_budget(account_id) {
+                println!("[WARN] 删除账号预算失败: {}", e);
+            }
+        }
+        if let Some(anomalies) = &self.anomalies {
+            if let Err(e) = anomalies.delete_account(account_id) {
+                println!("[WARN] 删除账号异常记录失败: {}", e);
+            }
         }
 
-        self.save_store()?;
         Ok(())
     }
 
     /// 设置活跃账号
-    pub fn set_active_account(&mut self, account_id: &str) -> Result<()> {
-        if !self.store.accounts.iter().any(|a| a.id == account_id) {
+    pub fn set_active_account(&self, account_id: &str) -> Result<()> {
+        let mut store = self.store();
+        if !store.accounts.iter().any(|a| a.id == account_id) {
             return Err(anyhow!("账号不存在"));
         }
 
-        self.store.active_account_id = Some(account_id.to_string());
-        self.save_store()?;
+        store.active_account_id = Some(account_id.to_string());
+        self.persist(&store)?;
+        self.publish_updated(&store, account_id);
         Ok(())
     }
 
-    /// 切换账号（设置活跃账号并将登录信息写入 Trae IDE）
-    pub fn switch_account(&mut self, account_id: &str) -> Result<()> {
+    /// 切换账号（设置活跃账号并将登录信息写入 Trae IDE），切换过程通过事件发布
+    pub fn switch_account(&self, account_id: &str) -> Result<()> {
+        self.events.publish(AccountEvent::SwitchStarted { account_id: account_id.to_string() });
+        match self.write_switch(account_id) {
+            Ok(()) => {
+                self.events.publish(AccountEvent::SwitchCompleted { account_id: account_id.to_string() });
+                Ok(())
+            }
+            Err(e) => {
+                self.events.publish(AccountEvent::SwitchFailed {
+                    account_id: account_id.to_string(),
+                    error: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// 将账号登录信息写入 Trae IDE 并记录为当前使用的账号
+    fn write_switch(&self, account_id: &str) -> Result<()> {
         // 检查是否已经是当前使用的账号
-        if self.store.current_account_id.as_deref() == Some(account_id) {
+        if self.store().current_account_id.as_deref() == Some(account_id) {
             return Err(anyhow!("该账号已经是当前使用的账号"));
         }
 
-        let account = self.store.accounts.iter()
-            .find(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?
-            .clone();
+        let account = self.account_snapshot(account_id)?;
 
         // 检查账号是否有有效的 Token
         let token = account.jwt_token.as_ref()
@@ -232,21 +582,28 @@ impl AccountManager {
         }
 
         // 设置活跃账号和当前使用的账号
-        self.store.active_account_id = Some(account_id.to_string());
-        self.store.current_account_id = Some(account_id.to_string());
-        self.save_store()?;
+        let mut store = self.store();
+        if !store.accounts.iter().any(|a| a.id == account_id) {
+            return Err(anyhow!("账号在切换期间已被删除"));
+        }
+        store.active_account_id = Some(account_id.to_string());
+        store.current_account_id = Some(account_id.to_string());
+        self.persist(&store)?;
+        self.publish_updated(&store, account_id);
+        drop(store);
 
         println!("[INFO] 已切换到账号: {}", account.email);
         Ok(())
     }
 
     /// 绑定当前系统机器码到账号
-    pub fn bind_machine_id(&mut self, account_id: &str) -> Result<String> {
+    pub fn bind_machine_id(&self, account_id: &str) -> Result<String> {
         // 获取当前系统机器码
         let current_machine_id = crate::machine::get_machine_guid()?;
 
         // 更新账号的机器码
-        let account = self.store.accounts.iter_mut()
+        let mut store = self.store();
+        let account = store.accounts.iter_mut()
             .find(|a| a.id == account_id)
             .ok_or_else(|| anyhow!("账号不存在"))?;
 
@@ -254,178 +611,416 @@ impl AccountManager {
         account.updated_at = chrono::Utc::now().timestamp();
         let email = account.email.clone();
 
-        self.save_store()?;
+        self.persist(&store)?;
+        self.publish_updated(&store, account_id);
+        drop(store);
         println!("[INFO] 已绑定机器码 {} 到账号 {}", current_machine_id, email);
 
         Ok(current_machine_id)
     }
 
-    /// 获取所有账号列表
-    pub fn get_accounts(&self) -> Vec<AccountBrief> {
-        let current_id = self.store.current_account_id.as_deref();
-        self.store.accounts.iter().map(|account| {
-            let is_current = current_id == Some(account.id.as_str());
-            AccountBrief::from_account(account, is_current)
-        }).collect()
+    /// 获取账号列表（可按过滤条件筛选）
+    pub fn get_accounts(&self, filter: &AccountFilter) -> Vec<AccountBrief> {
+        let store = self.store();
+        store
+            .accounts
+            .iter()
+            .filter(|account| filter.matches(account))
+            .map(|account| self.account_brief(&store, account))
+            .collect()
     }
 
     /// 获取活跃账号
-    pub fn get_active_account(&self) -> Option<&Account> {
-        self.store
+    pub fn get_active_account(&self) -> Option<Account> {
+        let store = self.store();
+        store
             .active_account_id
             .as_ref()
-            .and_then(|id| self.store.accounts.iter().find(|a| &a.id == id))
+            .and_then(|id| store.accounts.iter().find(|a| &a.id == id))
+            .cloned()
     }
 
     /// 获取指定账号
     pub fn get_account(&self, account_id: &str) -> Result<Account> {
-        self.store
+        self.account_snapshot(account_id)
+    }
+
+    /// 获取账号使用量（网络不可用时返回缓存的使用量，并标记为过期）
+    pub async fn get_account_usage(&self, account_id: &str) -> Result<UsageSummary> {
+        let fetch = self.entitlement_request(account_id)?.send().await;
+        let result = self.merge_usage_fetch_or_cached(fetch);
+        self.save_store()?;
+        result
+    }
+
+    /// 获取账号最新的使用量（不返回缓存，供依赖准确数据的计算使用）
+    async fn fetch_account_usage(&self, account_id: &str) -> Result<UsageSummary> {
+        let fetch = self.entitlement_request(account_id)?.send().await;
+        self.apply_usage_fetch(fetch)
+    }
+
+    /// 返回最近一次成功刷新的使用量（标记为过期），没有缓存时返回原来的错误
+    fn cached_usage(&self, account_id: &str, error: anyhow::Error) -> Result<UsageSummary> {
+        let cached = self
+            .store()
             .accounts
             .iter()
             .find(|a| a.id == account_id)
-            .cloned()
-            .ok_or_else(|| anyhow!("账号不存在"))
+            .and_then(|a| a.last_usage.clone());
+        let Some(cached) = cached else {
+            return Err(error);
+        };
+
+        let summary = cached.to_stale(&error, chrono::Utc::now().timestamp());
+        if let Some(stale) = &summary.stale {
+            println!(
+                "[WARN] 刷新账号 {} 的使用量失败，返回 {} 秒前的缓存数据: {}",
+                account_id, stale.age_seconds, stale.error
+            );
+        }
+        Ok(summary)
     }
 
-    /// 获取账号使用量
-    pub async fn get_account_usage(&mut self, account_id: &str) -> Result<UsageSummary> {
-        let account = self
-            .store
+    /// 获取账号使用量明细（列出所有权益包）
+    pub async fn get_account_usage_detail(&self, account_id: &str) -> Result<UsageDetail> {
+        let entitlements = self.fetch_entitlements(account_id).await?;
+        let detail = TraeApiClient::parse_entitlements_to_detail(entitlements, &self.catalog())?;
+
+        self.apply_summary(account_id, &detail.summary)?;
+        Ok(detail)
+    }
+
+    /// 将最新使用量同步到账号（plan_type、订阅信息）
+    fn apply_summary(&self, account_id: &str, summary: &UsageSummary) -> Result<()> {
+        self.merge_summary(account_id, summary)?;
+        self.save_store()
+    }
+
+    /// 将最新使用量同步到内存中的账号数据（不保存）；账号已在刷新期间被删除时报错
+    fn merge_summary(&self, account_id: &str, summary: &UsageSummary) -> Result<()> {
+        let account = {
+            let mut store = self.store();
+            let acc = Self::refreshed_account(&mut store, account_id)?;
+            acc.plan_type = summary.plan_type.clone();
+            acc.subscription = summary.subscription.clone();
+            acc.features = summary.features;
+            acc.updated_at = chrono::Utc::now().timestamp();
+            acc.last_usage = Some(CachedUsage::new(summary, acc.updated_at));
+            acc.clone()
+        };
+        self.on_usage_refreshed(&account, summary);
+        self.events.publish(AccountEvent::UsageRefreshed {
+            account_id: account_id.to_string(),
+            summary: summary.clone(),
+        });
+        Ok(())
+    }
+
+    /// 获取账号的订阅信息（最近一次刷新的结果）
+    pub fn get_account_subscription(&self, account_id: &str) -> Result<Option<SubscriptionInfo>> {
+        self.store()
             .accounts
             .iter()
             .find(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?
-            .clone();
-
-        // 根据账号类型选择不同的方式获取使用量
-        let summary = if let Some(token) = &account.jwt_token {
-            // 优先使用 Token
-            let client = TraeApiClient::new_with_token(token)?;
-            match client.get_usage_summary_by_token().await {
-                Ok(summary) => summary,
-                Err(e) => {
-                    let error_msg = e.to_string();
-                    // 如果是 401 错误且有 Cookies，尝试刷新 Token
-                    if error_msg.contains("401") && !account.cookies.is_empty() {
-                        println!("[INFO] Token 已过期，尝试使用 Cookies 刷新...");
-                        // 使用 Cookies 刷新 Token
-                        let mut cookie_client = TraeApiClient::new(&account.cookies)?;
-                        let token_result = cookie_client.get_user_token().await?;
-
-                        // 更新存储的 Token
-                        if let Some(acc) = self.store.accounts.iter_mut().find(|a| a.id == account_id) {
-                            acc.jwt_token = Some(token_result.token.clone());
-                            acc.token_expired_at = Some(token_result.expired_at.clone());
-                        }
-                        self.save_store()?;
-
-                        // 使用新 Token 重新获取使用量
-                        let new_client = TraeApiClient::new_with_token(&token_result.token)?;
-                        new_client.get_usage_summary_by_token().await?
-                    } else if error_msg.contains("401") {
-                        return Err(anyhow!("Token 已过期，请更新 Token 或 Cookies"));
-                    } else {
-                        return Err(e);
-                    }
-                }
+            .map(|a| a.subscription.clone())
+            .ok_or_else(|| anyhow!("账号不存在"))
+    }
+
+    /// 获取所有付费账号的续费信息，按下次扣费时间升序排列（不再续费的排在最后）
+    pub fn get_upcoming_renewals(&self) -> Vec<AccountRenewal> {
+        let mut renewals: Vec<AccountRenewal> = self
+            .store()
+            .accounts
+            .iter()
+            .filter_map(|account| {
+                account.subscription.as_ref().map(|subscription| AccountRenewal {
+                    account_id: account.id.clone(),
+                    name: account.name.clone(),
+                    email: account.email.clone(),
+                    subscription: subscription.clone(),
+                })
+            })
+            .collect();
+
+        renewals.sort_by_key(|r| {
+            let subscription = &r.subscription;
+            if subscription.will_renew() {
+                (0, subscription.next_billing_time)
+            } else {
+                (1, subscription.expire_time)
             }
-        } else if !account.cookies.is_empty() {
-            // 使用 Cookies
-            let mut client = TraeApiClient::new(&account.cookies)?;
-            client.get_usage_summary().await?
-        } else {
-            return Err(anyhow!("账号没有有效的 Token 或 Cookies"));
-        };
+        });
+        renewals
+    }
 
-        // 更新账号的 plan_type
-        if let Some(acc) = self.store.accounts.iter_mut().find(|a| a.id == account_id) {
-            acc.plan_type = summary.plan_type.clone();
-            acc.updated_at = chrono::Utc::now().timestamp();
+    /// 获取账号配额信息（Token 过期时尝试使用 Cookies 刷新）
+    async fn fetch_entitlements(&self, account_id: &str) -> Result<EntitlementListResponse> {
+        let request = self.entitlement_request(account_id)?;
+        let fetch = request.send().await;
+        self.apply_entitlements(fetch)
+    }
+
+    /// 生成拉取配额信息的请求（快照账号数据，之后的网络请求不需要持有账号管理器）
+    pub fn entitlement_request(&self, account_id: &str) -> Result<EntitlementRequest> {
+        let account = self.account_snapshot(account_id)?;
+        Ok(EntitlementRequest::new(account, self.api.clone()))
+    }
+
+    /// 为多个账号生成拉取配额信息的请求（`account_ids` 为空时包括全部账号）
+    pub fn entitlement_requests(&self, account_ids: &[String], filter: &AccountFilter) -> Result<Vec<EntitlementRequest>> {
+        let store = self.store();
+        if let Some(missing) = account_ids.iter().find(|id| !store.accounts.iter().any(|a| &a.id == *id)) {
+            return Err(anyhow!("账号不存在: {}", missing));
+        }
+        Ok(store
+            .accounts
+            .iter()
+            .filter(|a| account_ids.is_empty() || account_ids.contains(&a.id))
+            .filter(|a| filter.matches(a))
+            .map(|a| EntitlementRequest::new(a.clone(), self.api.clone()))
+            .collect())
+    }
+
+    /// 合并配额信息拉取结果，刷新了 Token 时立即保存
+    fn apply_entitlements(&self, fetch: EntitlementFetch) -> Result<EntitlementListResponse> {
+        let token_refreshed = fetch.refreshed_token.is_some();
+        let result = self.merge_entitlements(fetch);
+        if token_refreshed {
+            self.save_store()?;
+        }
+        result
+    }
+
+    /// 合并配额信息拉取结果（不保存）：更新刷新得到的 Token、记录诊断信息，失败时上报
+    fn merge_entitlements(&self, fetch: EntitlementFetch) -> Result<EntitlementListResponse> {
+        let account_id = fetch.account.id.as_str();
+        if let Some(token_result) = &fetch.refreshed_token {
+            self.merge_refreshed_token(&mut self.store(), &fetch.account, token_result)?;
         }
+
+        match fetch.result {
+            Ok(entitlements) => {
+                self.clear_refresh_failure(account_id);
+                self.record_diagnostics(account_id, &entitlements);
+                Ok(entitlements)
+            }
+            Err(e) => {
+                self.report_refresh_failure(account_id, &e);
+                Err(e)
+            }
+        }
+    }
+
+    /// 合并配额信息拉取结果并更新账号使用量
+    pub fn apply_usage_fetch(&self, fetch: EntitlementFetch) -> Result<UsageSummary> {
+        let result = self.merge_usage_fetch(fetch);
         self.save_store()?;
+        result
+    }
 
+    /// 合并配额信息拉取结果并更新内存中的账号使用量（不保存，批量合并后统一调用 `save_store`）
+    pub fn merge_usage_fetch(&self, fetch: EntitlementFetch) -> Result<UsageSummary> {
+        let account_id = fetch.account.id.clone();
+        let entitlements = self.merge_entitlements(fetch)?;
+        let summary = TraeApiClient::parse_entitlements_to_summary(entitlements, &self.catalog())?;
+
+        self.merge_summary(&account_id, &summary)?;
         Ok(summary)
     }
 
+    /// 同 `merge_usage_fetch`，但请求因网络不可用而失败时返回缓存的使用量（标记为过期）
+    pub fn merge_usage_fetch_or_cached(&self, fetch: EntitlementFetch) -> Result<UsageSummary> {
+        let account_id = fetch.account.id.clone();
+        let offline = fetch.result.as_ref().err().is_some_and(api::is_transport_error);
+        match self.merge_usage_fetch(fetch) {
+            Err(e) if offline => self.cached_usage(&account_id, e),
+            result => result,
+        }
+    }
+
+    fn lock_diagnostics(&self) -> MutexGuard<'_, HashMap<String, EntitlementDiagnostics>> {
+        self.diagnostics.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_forecasts(&self) -> MutexGuard<'_, HashMap<String, BurnRateForecast>> {
+        self.forecasts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 记录最近一次配额响应（原始数据和解析失败的礼包）
+    fn record_diagnostics(&self, account_id: &str, entitlements: &EntitlementListResponse) {
+        for error in &entitlements.pack_errors {
+            println!("[WARN] 账号 {} 的配额数据部分解析失败: {}", account_id, error);
+        }
+
+        self.lock_diagnostics().insert(account_id.to_string(), EntitlementDiagnostics {
+            fetched_at: chrono::Utc::now().timestamp(),
+            pack_count: entitlements.user_entitlement_pack_list.len(),
+            pack_errors: entitlements.pack_errors.clone(),
+            raw: entitlements.raw.clone(),
+        });
+    }
+
+    /// 获取最近一次配额响应的诊断信息
+    pub fn get_entitlement_diagnostics(&self, account_id: &str) -> Result<Option<EntitlementDiagnostics>> {
+        if !self.store().accounts.iter().any(|a| a.id == account_id) {
+            return Err(anyhow!("账号不存在"));
+        }
+        Ok(self.lock_diagnostics().get(account_id).cloned())
+    }
+
+    /// 获取礼包目录
+    pub fn get_package_catalog(&self) -> PackageCatalogView {
+        PackageCatalogView {
+            override_path: self.catalog_path().to_string_lossy().to_string(),
+            catalog: self.catalog().clone(),
+        }
+    }
+
+    /// 重新加载礼包目录（用户修改覆盖文件后调用）
+    pub fn reload_package_catalog(&self) -> Result<PackageCatalogView> {
+        let path = self.catalog_path();
+        if path.exists() {
+            // 覆盖文件有误时直接报错，而不是静默回退到内置目录
+            PackageCatalog::read_file(&path)?;
+        }
+        *self.catalog.write().unwrap_or_else(PoisonError::into_inner) = PackageCatalog::load(&path);
+        Ok(self.get_package_catalog())
+    }
+
+    fn catalog_path(&self) -> PathBuf {
+        self.data_path.with_file_name(PACKAGE_CATALOG_FILE)
+    }
+
+    /// 获取模型价格表
+    pub fn get_price_table(&self) -> PriceTableView {
+        PriceTableView {
+            override_path: self.price_table_path().to_string_lossy().to_string(),
+            table: self.prices().clone(),
+        }
+    }
+
+    /// 从 JSON 文件导入模型价格表（替换之前导入的价格表）
+    pub fn import_price_table(&self, source: &str) -> Result<PriceTableView> {
+        // 先校验，只保存解析后的价格表，不直接复制源文件
+        let table = PriceTable::read_file(std::path::Path::new(source))?;
+
+        let path = self.price_table_path();
+        let content = serde_json::to_string_pretty(&table)?;
+        fs::write(&path, content).map_err(|e| anyhow!("导入价格表失败: {}", e))?;
+        *self.prices.write().unwrap_or_else(PoisonError::into_inner) = PriceTable::load(&path);
+        println!("[INFO] 已导入价格表: {}", source);
+        Ok(self.get_price_table())
+    }
+
+    /// 恢复内置价格表（删除导入的价格表）
+    pub fn reset_price_table(&self) -> Result<PriceTableView> {
+        let path = self.price_table_path();
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| anyhow!("删除价格表失败: {}", e))?;
+        }
+        *self.prices.write().unwrap_or_else(PoisonError::into_inner) = PriceTable::bundled().clone();
+        Ok(self.get_price_table())
+    }
+
+    fn price_table_path(&self) -> PathBuf {
+        self.data_path.with_file_name(PRICE_TABLE_FILE)
+    }
+
     /// 刷新账号 Token
-    pub async fn refresh_token(&mut self, account_id: &str) -> Result<()> {
-        let account = self
-            .store
-            .accounts
-            .iter()
-            .find(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?
-            .clone();
+    pub async fn refresh_token(&self, account_id: &str) -> Result<()> {
+        let account = self.account_snapshot(account_id)?;
 
-        let mut client = TraeApiClient::new(&account.cookies)?;
-        let token_result = client.get_user_token().await?;
+        let token_result = self.api.get_user_token(&account.cookies).await?;
 
-        if let Some(acc) = self.store.accounts.iter_mut().find(|a| a.id == account_id) {
-            acc.jwt_token = Some(token_result.token);
-            acc.token_expired_at = Some(token_result.expired_at);
-            acc.updated_at = chrono::Utc::now().timestamp();
+        let mut store = self.store();
+        let acc = Self::refreshed_account(&mut store, account_id)?;
+        if acc.cookies != account.cookies {
+            return Err(anyhow!("账号 Cookies 在刷新期间已被修改，请重试"));
         }
+        acc.jwt_token = Some(token_result.token);
+        acc.token_expired_at = Some(token_result.expired_at.clone());
+        acc.updated_at = chrono::Utc::now().timestamp();
 
-        self.save_store()?;
+        self.persist(&store)?;
+        self.events.publish(AccountEvent::TokenRefreshed {
+            account_id: account_id.to_string(),
+            expired_at: Some(token_result.expired_at),
+        });
         Ok(())
     }
 
     /// 更新账号 Token
-    pub async fn update_account_token(&mut self, account_id: &str, token: String) -> Result<UsageSummary> {
-        let client = TraeApiClient::new_with_token(&token)?;
+    pub async fn update_account_token(&self, account_id: &str, token: String) -> Result<UsageSummary> {
+        // 先确认账号存在，避免无谓的网络请求
+        self.account_snapshot(account_id)?;
 
         // 验证 Token 并获取用户信息
-        let user_info = client.get_user_info_by_token().await?;
+        let user_info = self.api.get_user_info_by_token(&token).await?;
 
-        // 查找账号
-        let acc = self.store.accounts.iter_mut()
-            .find(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?;
+        // 获取最新使用量
+        let entitlements = self.api.get_entitlement_list_by_token(&token).await?;
+        self.record_diagnostics(account_id, &entitlements);
+        let summary = TraeApiClient::parse_entitlements_to_summary(entitlements, &self.catalog())?;
 
-        // 确保是同一个用户
-        if acc.user_id != user_info.user_id {
-            return Err(anyhow!("Token 对应的用户与当前账号不匹配"));
-        }
+        let account = {
+            let mut store = self.store();
+            let acc = Self::refreshed_account(&mut store, account_id)?;
 
-        // 更新 Token
-        acc.jwt_token = Some(token.clone());
-        acc.updated_at = chrono::Utc::now().timestamp();
+            // 确保是同一个用户
+            if acc.user_id != user_info.user_id {
+                return Err(anyhow!("Token 对应的用户与当前账号不匹配"));
+            }
 
-        // 获取最新使用量
-        let summary = client.get_usage_summary_by_token().await?;
-        acc.plan_type = summary.plan_type.clone();
+            // 更新 Token
+            acc.jwt_token = Some(token);
+            acc.updated_at = chrono::Utc::now().timestamp();
+            acc.plan_type = summary.plan_type.clone();
+            acc.subscription = summary.subscription.clone();
+            acc.features = summary.features;
+            acc.last_usage = Some(CachedUsage::new(&summary, acc.updated_at));
+            let account = acc.clone();
+
+            self.persist(&store)?;
+            self.publish_updated(&store, account_id);
+            account
+        };
 
-        self.save_store()?;
+        self.on_usage_refreshed(&account, &summary);
+        self.events.publish(AccountEvent::UsageRefreshed {
+            account_id: account_id.to_string(),
+            summary: summary.clone(),
+        });
         Ok(summary)
     }
 
     /// 更新账号 Cookies
-    pub async fn update_cookies(&mut self, account_id: &str, cookies: String) -> Result<()> {
-        // 验证新 cookies 是否有效
-        let mut client = TraeApiClient::new(&cookies)?;
-        let token_result = client.get_user_token().await?;
+    pub async fn update_cookies(&self, account_id: &str, cookies: String) -> Result<()> {
+        // 先确认账号存在，避免无谓的网络请求
+        self.account_snapshot(account_id)?;
 
-        if let Some(acc) = self.store.accounts.iter_mut().find(|a| a.id == account_id) {
-            // 确保是同一个用户
-            if acc.user_id != token_result.user_id {
-                return Err(anyhow!("Cookies 对应的用户与当前账号不匹配"));
-            }
+        // 验证新 cookies 是否有效
+        let token_result = self.api.get_user_token(&cookies).await?;
 
-            acc.cookies = cookies;
-            acc.jwt_token = Some(token_result.token);
-            acc.token_expired_at = Some(token_result.expired_at);
-            acc.updated_at = chrono::Utc::now().timestamp();
-        } else {
-            return Err(anyhow!("账号不存在"));
+        let mut store = self.store();
+        let acc = Self::refreshed_account(&mut store, account_id)?;
+        // 确保是同一个用户
+        if acc.user_id != token_result.user_id {
+            return Err(anyhow!("Cookies 对应的用户与当前账号不匹配"));
         }
 
-        self.save_store()?;
+        acc.cookies = cookies;
+        acc.jwt_token = Some(token_result.token);
+        acc.token_expired_at = Some(token_result.expired_at);
+        acc.updated_at = chrono::Utc::now().timestamp();
+
+        self.persist(&store)?;
+        self.publish_updated(&store, account_id);
         Ok(())
     }
 
     /// 导出账号数据
     pub fn export_accounts(&self) -> Result<String> {
-        let export_data: Vec<serde_json::Value> = self.store.accounts.iter().map(|acc| {
+        let export_data: Vec<serde_json::Value> = self.store().accounts.iter().map(|acc| {
             serde_json::json!({
                 "name": acc.name,
                 "email": acc.email,
@@ -445,7 +1040,7 @@ impl AccountManager {
     }
 
     /// 导入账号数据
-    pub async fn import_accounts(&mut self, data: &str) -> Result<usize> {
+    pub async fn import_accounts(&self, data: &str) -> Result<usize> {
         let import_data: Vec<serde_json::Value> = serde_json::from_str(data)
             .map_err(|e| anyhow!("JSON 解析失败: {}", e))?;
 
@@ -480,66 +1075,438 @@ impl AccountManager {
 
     /// 获取使用事件
     pub async fn get_usage_events(
-        &mut self,
+        &self,
         account_id: &str,
         start_time: i64,
         end_time: i64,
         page_num: i32,
         page_size: i32,
     ) -> Result<UsageQueryResponse> {
-        let account = self
-            .store
-            .accounts
-            .iter()
-            .find(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?
-            .clone();
+        let account = self.account_snapshot(account_id)?;
 
         // 根据账号类型选择不同的方式调用 API
         if let Some(token) = &account.jwt_token {
-            // 优先使用 Token
-            let client = TraeApiClient::new_with_token(token)?;
-            match client.query_usage(start_time, end_time, page_size, page_num).await {
-                Ok(response) => Ok(response),
-                Err(e) => {
-                    let error_msg = e.to_string();
-                    // 如果是 401 错误且有 Cookies，尝试刷新 Token
-                    if error_msg.contains("401") && !account.cookies.is_empty() {
-                        println!("[INFO] Token 已过期，尝试使用 Cookies 刷新...");
-                        // 使用 Cookies 刷新 Token
-                        let mut cookie_client = TraeApiClient::new(&account.cookies)?;
-                        let token_result = cookie_client.get_user_token().await?;
-
-                        // 更新存储的 Token
-                        if let Some(acc) = self.store.accounts.iter_mut().find(|a| a.id == account_id) {
-                            acc.jwt_token = Some(token_result.token.clone());
-                            acc.token_expired_at = Some(token_result.expired_at.clone());
-                        }
-                        self.save_store()?;
-
-                        // 使用新 Token 重新查询
-                        let new_client = TraeApiClient::new_with_token(&token_result.token)?;
-                        new_client.query_usage(start_time, end_time, page_size, page_num).await
-                    } else if error_msg.contains("401") {
-                        Err(anyhow!("Token 已过期，请更新 Token 或 Cookies"))
-                    } else {
-                        Err(e)
-                    }
-                }
+            // 优先使用 Token，过期时用 Cookies 刷新后重试
+            let api = self.api.as_ref();
+            let (result, refreshed_token) = Self::call_with_token_refresh(api, &account, token, |token| async move {
+                api.query_usage_by_token(&token, start_time, end_time, page_size, page_num).await
+            })
+            .await;
+
+            // 更新存储的 Token
+            if let Some(token_result) = &refreshed_token {
+                let mut store = self.store();
+                self.merge_refreshed_token(&mut store, &account, token_result)?;
+                self.persist(&store)?;
             }
+            result
         } else if !account.cookies.is_empty() {
             // 使用 Cookies
-            let mut client = TraeApiClient::new(&account.cookies)?;
-            // 先获取 token
-            client.get_user_token().await?;
-            client.query_usage(start_time, end_time, page_size, page_num).await
+            self.api.query_usage(&account.cookies, start_time, end_time, page_size, page_num).await
         } else {
             Err(anyhow!("账号没有有效的 Token 或 Cookies"))
         }
     }
 
+    /// 同步时间范围内的全部使用事件到本地历史
+    ///
+    /// 自动翻页并按 session_id + usage_time 去重；请求范围与上次同步的范围衔接时，
+    /// 从上次的水位线开始增量同步。
+    pub async fn sync_usage_events<F>(
+        &self,
+        account_id: &str,
+        start_time: i64,
+        end_time: i64,
+        mut on_progress: F,
+    ) -> Result<SyncReport>
+    where
+        F: FnMut(&SyncProgress),
+    {
+        if start_time > end_time {
+            return Err(anyhow!("开始时间不能晚于结束时间"));
+        }
+        let history = self
+            .history
+            .clone()
+            .ok_or_else(|| anyhow!("使用量历史不可用"))?;
+
+        let end_time = end_time.min(chrono::Utc::now().timestamp());
+        let previous = history.sync_state(account_id)?;
+        // 与已同步范围衔接时只拉取水位线之后的事件
+        let covered = previous
+            .as_ref()
+            .filter(|state| start_time >= state.synced_from && start_time <= state.synced_to);
+        let sync_start = covered.map_or(start_time, |state| state.synced_to.min(end_time));
+
+        let mut progress = SyncProgress {
+            account_id: account_id.to_string(),
+            page_num: 0,
+            fetched: 0,
+            total: 0,
+            inserted: 0,
+        };
+        loop {
+            progress.page_num += 1;
+            let response = self
+                .get_usage_events(account_id, sync_start, end_time, progress.page_num, SYNC_PAGE_SIZE)
+                .await?;
+            let page = response.user_usage_group_by_sessions;
+
+            progress.total = response.total;
+            progress.fetched += page.len();
+            progress.inserted += history.insert_sessions(account_id, &page)?;
+            on_progress(&progress);
+
+            if page.is_empty() || progress.fetched as i64 >= response.total {
+                break;
+            }
+        }
+
+        let state = SyncState {
+            account_id: account_id.to_string(),
+            synced_from: covered.map_or(start_time, |state| state.synced_from),
+            synced_to: covered.map_or(end_time, |state| state.synced_to.max(end_time)),
+            last_synced_at: chrono::Utc::now().timestamp(),
+        };
+        history.save_sync_state(&state)?;
+
+        println!(
+            "[INFO] 账号 {} 使用事件同步完成: {} 页，拉取 {} 条，新增 {} 条",
+            account_id, progress.page_num, progress.fetched, progress.inserted
+        );
+
+        Ok(SyncReport {
+            account_id: account_id.to_string(),
+            start_time: sync_start,
+            end_time,
+            pages: progress.page_num,
+            fetched: progress.fetched,
+            inserted: progress.inserted,
+            state,
+        })
+    }
+
+    /// 查询本地已同步的使用事件
+    pub fn get_synced_usage_events(&self, account_id: &str, start_time: i64, end_time: i64) -> Result<Vec<UsageSession>> {
+        let history = self
+            .history
+            .as_ref()
+            .ok_or_else(|| anyhow!("使用量历史不可用"))?;
+        history.query_sessions(account_id, start_time, end_time)
+    }
+
+    /// 从接口拉取时间范围内的全部使用事件（不写入本地）
+    pub async fn fetch_usage_events(&self, account_id: &str, start_time: i64, end_time: i64) -> Result<Vec<UsageSession>> {
+        let mut sessions = Vec::new();
+        let mut page_num = 1;
+        loop {
+            let response = self
+                .get_usage_events(account_id, start_time, end_time, page_num, SYNC_PAGE_SIZE)
+                .await?;
+            let page = response.user_usage_group_by_sessions;
+            let done = page.is_empty();
+            sessions.extend(page);
+            if done || sessions.len() as i64 >= response.total {
+                break;
+            }
+            page_num += 1;
+        }
+        Ok(sessions)
+    }
+
+    /// 按来源读取使用事件
+    async fn load_usage_events(
+        &self,
+        account_id: &str,
+        start_time: i64,
+        end_time: i64,
+        source: EventSource,
+    ) -> Result<Vec<UsageSession>> {
+        match source {
+            EventSource::Stored => self.get_synced_usage_events(account_id, start_time, end_time),
+            EventSource::Fetched => self.fetch_usage_events(account_id, start_time, end_time).await,
+        }
+    }
+
+    /// 账号的重置时间，优先使用最近一次快照，没有时实时查询
+    async fn reset_time(&self, account_id: &str) -> Result<i64> {
+        if let Some(history) = &self.history {
+            if let Some(snapshot) = history.latest_snapshot(account_id)? {
+                if snapshot.reset_time > 0 {
+                    return Ok(snapshot.reset_time);
+                }
+            }
+        }
+        Ok(self.fetch_account_usage(account_id).await?.reset_time)
+    }
+
+    /// 按模型、模式、天、周或计费周期聚合使用事件
+    pub async fn get_usage_analytics(&self, query: &UsageAnalyticsQuery) -> Result<UsageAnalytics> {
+        if query.start_time > query.end_time {
+            return Err(anyhow!("开始时间不能晚于结束时间"));
+        }
+        let tz = analytics::time::resolve_timezone(query.timezone.as_deref())?;
+        let sessions = self
+            .load_usage_events(&query.account_id, query.start_time, query.end_time, query.source)
+            .await?;
+        let cycle_anchor = if query.group_by == GroupBy::BillingCycle {
+            self.reset_time(&query.account_id).await?
+        } else {
+            0
+        };
+
+        let (groups, total) = analytics::aggregate_sessions(&sessions, query.group_by, tz, cycle_anchor);
+        Ok(UsageAnalytics {
+            account_id: query.account_id.clone(),
+            group_by: query.group_by,
+            timezone: tz.name().to_string(),
+            start_time: query.start_time,
+            end_time: query.end_time,
+            groups,
+            total,
+        })
+    }
+
+    /// 生成缓存效率报告
+    pub async fn get_cache_efficiency_report(&self, query: &CacheReportQuery) -> Result<CacheEfficiencyReport> {
+        if query.start_time > query.end_time {
+            return Err(anyhow!("开始时间不能晚于结束时间"));
+        }
+        let sessions = self
+            .load_usage_events(&query.account_id, query.start_time, query.end_time, query.source)
+            .await?;
+        Ok(analytics::cache_report(&sessions, query))
+    }
+
+    /// 根据账号当前的权益周期推算计费周期（从新到旧）
+    pub async fn get_billing_cycles(
+        &self,
+        account_id: &str,
+        range: CycleRange,
+        timezone: Option<&str>,
+    ) -> Result<Vec<BillingCycle>> {
+        let tz = analytics::time::resolve_timezone(timezone)?;
+        let summary = self.fetch_account_usage(account_id).await?;
+        let now = chrono::Utc::now().timestamp();
+        Ok(analytics::cycles::resolve_cycles(tz, summary.cycle_start_time, summary.reset_time, now, range))
+    }
+
+    /// 获取某个计费周期内的使用事件（0 为当前周期，1 为上一个周期）
+    pub async fn get_cycle_usage_events(
+        &self,
+        account_id: &str,
+        cycle_index: u32,
+        page_num: i32,
+        page_size: i32,
+        timezone: Option<&str>,
+    ) -> Result<UsageQueryResponse> {
+        let range = CycleRange::Last { count: cycle_index + 1 };
+        let cycle = self
+            .get_billing_cycles(account_id, range, timezone)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("无法确定计费周期"))?;
+        self.get_usage_events(account_id, cycle.start_time, cycle.end_time, page_num, page_size)
+            .await
+    }
+
+    /// 按模型和模式对比最近几个计费周期的用量
+    pub async fn compare_billing_cycles(&self, query: &CycleComparisonQuery) -> Result<CycleComparison> {
+        if query.count < 2 {
+            return Err(anyhow!("至少需要对比 2 个计费周期"));
+        }
+        let tz = analytics::time::resolve_timezone(query.timezone.as_deref())?;
+        let range = CycleRange::Last { count: query.count };
+        let cycles = self
+            .get_billing_cycles(&query.account_id, range, query.timezone.as_deref())
+            .await?;
+
+        let mut usages = Vec::new();
+        for cycle in cycles {
+            let sessions = self
+                .load_usage_events(&query.account_id, cycle.start_time, cycle.end_time, query.source)
+                .await?;
+            usages.push(CycleUsage::new(cycle, &sessions, tz));
+        }
+        Ok(CycleComparison::new(&query.account_id, tz, usages))
+    }
+
+    /// 统计一个或多个账号按星期和小时分布的活跃度
+    pub async fn get_activity_heatmap(&self, query: &HeatmapQuery) -> Result<ActivityHeatmap> {
+        if query.start_time > query.end_time {
+            return Err(anyhow!("开始时间不能晚于结束时间"));
+        }
+        let tz = analytics::time::resolve_timezone(query.timezone.as_deref())?;
+        let account_ids: Vec<String> = if query.account_ids.is_empty() {
+            self.store().accounts.iter().map(|a| a.id.clone()).collect()
+        } else {
+            query.account_ids.clone()
+        };
+
+        let mut heatmap = ActivityHeatmap::new(account_ids.clone(), tz, query.start_time, query.end_time);
+        for account_id in &account_ids {
+            // 单个账号失败不影响其他账号的统计
+            match self.load_usage_events(account_id, query.start_time, query.end_time, query.source).await {
+                Ok(sessions) => heatmap.add(tz, &sessions),
+                Err(e) => heatmap.failures.push(HeatmapFailure {
+                    account_id: account_id.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Ok(heatmap)
+    }
+
+    /// 按模型价格表估算使用事件的费用
+    pub async fn get_cost_estimate(&self, query: &CostEstimateQuery) -> Result<CostEstimateReport> {
+        if query.start_time > query.end_time {
+            return Err(anyhow!("开始时间不能晚于结束时间"));
+        }
+        let sessions = self
+            .load_usage_events(&query.account_id, query.start_time, query.end_time, query.source)
+            .await?;
+        Ok(analytics::estimate_costs(&sessions, &self.prices(), query))
+    }
+
+    /// 预测账号当前周期内各类额度的耗尽时间
+    pub async fn get_account_forecast(&self, account_id: &str, timezone: Option<&str>) -> Result<BurnRateForecast> {
+        let tz = analytics::time::resolve_timezone(timezone)?;
+        let summary = self.fetch_account_usage(account_id).await?;
+        let now = chrono::Utc::now().timestamp();
+        let cycle_start = Self::forecast_cycle_start(tz, &summary, now);
+
+        let sessions = self.fetch_usage_events(account_id, cycle_start, now).await?;
+        Ok(self.store_forecast(account_id, &summary, &sessions, tz, cycle_start, now))
+    }
+
+    /// 根据已拉取的使用量和本地已同步的事件更新额度预测（不发起网络请求）
+    pub fn update_forecast(&self, account_id: &str, summary: &UsageSummary) -> Result<BurnRateForecast> {
+        let tz = analytics::time::resolve_timezone(None)?;
+        let now = chrono::Utc::now().timestamp();
+        let cycle_start = Self::forecast_cycle_start(tz, summary, now);
+
+        let sessions = match &self.history {
+            Some(history) => history.query_sessions(account_id, cycle_start, now)?,
+            None => Vec::new(),
+        };
+        Ok(self.store_forecast(account_id, summary, &sessions, tz, cycle_start, now))
+    }
+
+    /// 预测使用的当前周期开始时间
+    ///
+    /// 优先使用接口返回的周期开始时间（与计费周期视图一致），没有时按重置时间向前推一个月，
+    /// 都没有时取最近 30 天。
+    fn forecast_cycle_start(tz: Tz, summary: &UsageSummary, now: i64) -> i64 {
+        if summary.cycle_start_time > 0 && summary.cycle_start_time < summary.reset_time {
+            summary.cycle_start_time
+        } else if summary.reset_time > 0 {
+            analytics::time::billing_cycle(tz, summary.reset_time, now).1
+        } else {
+            now - 30 * 24 * 3600
+        }
+    }
+
+    fn store_forecast(
+        &self,
+        account_id: &str,
+        summary: &UsageSummary,
+        sessions: &[UsageSession],
+        tz: Tz,
+        cycle_start: i64,
+        now: i64,
+    ) -> BurnRateForecast {
+        let forecast = analytics::forecast_burn_rate(account_id, summary, sessions, tz, cycle_start, now);
+        self.lock_forecasts().insert(account_id.to_string(), forecast.clone());
+        forecast
+    }
+
+    /// 计算账号本月的预算使用情况（支出来自本月的使用事件），超过阈值时提醒
+    pub async fn get_budget_status(&self, account_id: &str, timezone: Option<&str>) -> Result<BudgetStatus> {
+        let tz = analytics::time::resolve_timezone(timezone)?;
+        self.budget_status(account_id, tz, EventSource::Fetched).await
+    }
+
+    async fn budget_status(&self, account_id: &str, tz: Tz, source: EventSource) -> Result<BudgetStatus> {
+        let tracker = self.budgets.clone().ok_or_else(|| anyhow!("预算功能不可用"))?;
+        let budget = tracker
+            .budget(account_id)?
+            .filter(|b| b.enabled)
+            .ok_or_else(|| anyhow!("账号未设置预算"))?;
+        let subscription = self.get_account_subscription(account_id)?;
+
+        let now = chrono::Utc::now().timestamp();
+        let (month, start, end) = analytics::time::month_bucket(tz, now);
+        let sessions = self.load_usage_events(account_id, start, now.min(end), source).await?;
+
+        let status = budgets::compute_status(&budget, &month, (start, end), &sessions, subscription.as_ref(), now);
+        tracker.record_status(&status)?;
+        Ok(status)
+    }
+
+    /// 以账号自身的历史用量为基线检测用量异常，返回检测时段内的全部异常
+    pub async fn detect_usage_anomalies(&self, account_id: &str, timezone: Option<&str>) -> Result<Vec<UsageAnomaly>> {
+        let tz = analytics::time::resolve_timezone(timezone)?;
+        self.check_anomalies(account_id, tz, EventSource::Fetched).await
+    }
+
+    async fn check_anomalies(&self, account_id: &str, tz: Tz, source: EventSource) -> Result<Vec<UsageAnomaly>> {
+        let detector = self.anomalies.clone().ok_or_else(|| anyhow!("异常检测功能不可用"))?;
+        let settings = detector.settings()?;
+        if !settings.enabled {
+            return Err(anyhow!("用量异常检测已关闭"));
+        }
+        let account = self.account_snapshot(account_id)?;
+
+        let now = chrono::Utc::now().timestamp();
+        // 多取一天，保证第一个基线时段完整
+        let start = now - (settings.baseline_days as i64 + 1) * 24 * 3600;
+        let sessions = self.load_usage_events(account_id, start, now, source).await?;
+
+        let anomalies = anomalies::detect_anomalies(&account, &sessions, &settings, tz, now);
+        let raised = detector.record(anomalies.clone())?;
+        if !raised.is_empty() {
+            println!("[INFO] 账号 {} 检测到 {} 条新的用量异常", account.name, raised.len());
+        }
+        Ok(anomalies)
+    }
+
+    /// 后台刷新成功后更新额度预测，并检查预算和用量异常
+    ///
+    /// 只使用本地已同步的使用事件，不发起额外的网络请求；失败只打印警告。
+    pub async fn update_after_refresh(&self, account_id: &str, summary: &UsageSummary) {
+        if let Err(e) = self.update_forecast(account_id, summary) {
+            println!("[WARN] 更新额度预测失败 ({}): {}", account_id, e);
+        }
+
+        let tz = match analytics::time::resolve_timezone(None) {
+            Ok(tz) => tz,
+            Err(e) => {
+                println!("[WARN] 无法确定时区: {}", e);
+                return;
+            }
+        };
+        let budget_enabled = self
+            .budgets
+            .as_ref()
+            .is_some_and(|tracker| matches!(tracker.budget(account_id), Ok(Some(budget)) if budget.enabled));
+        if budget_enabled {
+            if let Err(e) = self.budget_status(account_id, tz, EventSource::Stored).await {
+                println!("[WARN] 计算预算失败 ({}): {}", account_id, e);
+            }
+        }
+        let anomalies_enabled = self
+            .anomalies
+            .as_ref()
+            .is_some_and(|detector| detector.settings().is_ok_and(|s| s.enabled));
+        if anomalies_enabled {
+            if let Err(e) = self.check_anomalies(account_id, tz, EventSource::Stored).await {
+                println!("[WARN] 检测用量异常失败 ({}): {}", account_id, e);
+            }
+        }
+    }
+
     /// 从 Trae IDE 读取当前登录账号
-    pub async fn read_trae_ide_account(&mut self) -> Result<Option<Account>> {
+    pub async fn read_trae_ide_account(&self) -> Result<Option<Account>> {
         let trae_data_path = crate::machine::get_trae_data_path()?;
 
         let storage_path = trae_data_path
@@ -584,7 +1551,7 @@ impl AccountManager {
             .to_string();
 
         // 检查账号是否已存在
-        if self.store.accounts.iter().any(|a| a.user_id == user_id) {
+        if self.has_user(&user_id) {
             return Err(anyhow!("Trae IDE account already exists"));
         }
 
@@ -610,8 +1577,7 @@ impl AccountManager {
             .to_string();
 
         // 使用 Token 获取完整的用户信息
-        let client = TraeApiClient::new_with_token(&token)?;
-        let user_info = client.get_user_info_by_token().await?;
+        let user_info = self.api.get_user_info_by_token(&token).await?;
 
         // 创建账号对象
         let mut account = Account::new(
@@ -638,40 +1604,263 @@ impl AccountManager {
         account.jwt_token = Some(token);
 
         // 添加到账号列表
-        self.store.accounts.push(account.clone());
-
-        // 如果是第一个账号，设为活跃账号
-        if self.store.active_account_id.is_none() {
-            self.store.active_account_id = Some(account.id.clone());
-        }
-
-        self.save_store()?;
+        let account = self.insert_account(account)?;
 
         println!("[INFO] 成功从 Trae IDE 读取并添加账号: {}", account.email);
         Ok(Some(account))
     }
 
     /// 领取生日礼包
-    pub async fn claim_birthday_bonus(&mut self, account_id: &str) -> Result<()> {
-        let account = self.store.accounts.iter()
-            .find(|a| a.id == account_id)
-            .ok_or_else(|| anyhow!("账号不存在"))?;
+    pub async fn claim_birthday_bonus(&self, account_id: &str) -> Result<()> {
+        let account = self.account_snapshot(account_id)?;
 
         let token = account.jwt_token.as_ref()
             .ok_or_else(|| anyhow!("账号没有 Token"))?;
 
-        let client = TraeApiClient::new_with_token(token)?;
-
         // 先查询是否已领取
-        let claimed = client.query_birthday_bonus().await?;
+        let claimed = self.api.query_birthday_bonus(token).await?;
         if claimed {
             return Err(anyhow!("该账号已领取过礼包"));
         }
 
         // 领取礼包
-        client.claim_birthday_bonus().await?;
+        self.api.claim_birthday_bonus(token).await?;
 
         println!("[INFO] 成功领取礼包: {}", account.email);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{refresh_usage, BatchLimits, CancelToken};
+    use crate::api::fake::{FakeAccount, FakeTraeApi};
+
+    /// 使用临时目录和内存 API 创建账号管理器
+    fn manager(fake: &Arc<FakeTraeApi>) -> (AccountManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("trae-account-manager-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let manager = AccountManager::with_api(dir.join("accounts.json"), fake.clone()).unwrap();
+        (manager, dir)
+    }
+
+    fn fake_with(accounts: &[(&str, &str, &str)]) -> Arc<FakeTraeApi> {
+        let fake = Arc::new(FakeTraeApi::new());
+        for (user_id, cookies, token) in accounts {
+            fake.add_account(FakeAccount::new(user_id, cookies, token));
+        }
+        fake
+    }
+
+    /// 最近一次刷新失败的类型
+    fn refresh_failure(manager: &AccountManager, account_id: &str) -> Option<WebhookEventKind> {
+        manager.refresh_failures.lock().unwrap().get(account_id).copied()
+    }
+
+    #[tokio::test]
+    async fn refresh_retries_with_cookies_after_401() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        let account = manager.add_account_by_token("t1".to_string(), Some("c1".to_string())).await.unwrap();
+
+        fake.expire_token("t1");
+        manager.get_account_usage(&account.id).await.unwrap();
+
+        let calls = fake.calls();
+        assert!(calls.contains(&"get_user_token".to_string()));
+        assert_eq!(calls.last().map(String::as_str), Some("get_entitlement_list_by_token"));
+        // 刷新得到的 Token 已保存，重新打开后仍然有效
+        let reopened = AccountManager::with_api(dir.join("accounts.json"), fake.clone()).unwrap();
+        assert_eq!(reopened.get_account(&account.id).unwrap().jwt_token, fake.current_token("u1"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn usage_events_retry_with_cookies_after_401() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        let account = manager.add_account_by_token("t1".to_string(), Some("c1".to_string())).await.unwrap();
+
+        fake.expire_token("t1");
+        manager.get_usage_events(&account.id, 0, 100, 1, 20).await.unwrap();
+
+        let calls = fake.calls();
+        assert!(calls.contains(&"get_user_token".to_string()));
+        assert_eq!(calls.last().map(String::as_str), Some("query_usage_by_token"));
+        assert_eq!(manager.get_account(&account.id).unwrap().jwt_token, fake.current_token("u1"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refresh_without_cookies_reports_expired_token() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        let account = manager.add_account_by_token("t1".to_string(), None).await.unwrap();
+
+        fake.expire_token("t1");
+        let error = manager.get_account_usage(&account.id).await.unwrap_err();
+        assert!(error.to_string().contains("Token 已过期"), "{}", error);
+        assert!(api::is_unauthorized(&error));
+        assert_eq!(refresh_failure(&manager, &account.id), Some(WebhookEventKind::CredentialsInvalid));
+        assert_eq!(manager.get_account(&account.id).unwrap().jwt_token.as_deref(), Some("t1"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refresh_while_offline_keeps_credentials() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        let account = manager.add_account("c1".to_string()).await.unwrap();
+        let token = manager.get_account(&account.id).unwrap().jwt_token;
+
+        fake.set_offline(true);
+        assert!(manager.get_account_usage(&account.id).await.is_err());
+        assert_eq!(refresh_failure(&manager, &account.id), Some(WebhookEventKind::RefreshFailed));
+        assert_eq!(manager.get_account(&account.id).unwrap().jwt_token, token);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn panicked_refresh_is_reported_as_failed() {
+        let fake = fake_with(&[("u1", "c1", "t1"), ("u2", "c2", "t2")]);
+        let (manager, dir) = manager(&fake);
+        let first = manager.add_account_by_token("t1".to_string(), None).await.unwrap();
+        let second = manager.add_account_by_token("t2".to_string(), None).await.unwrap();
+
+        fake.panic_on_token("t2");
+        let requests = manager
+            .entitlement_requests(&[first.id.clone(), second.id.clone()], &AccountFilter::default())
+            .unwrap();
+        let mut completed = Vec::new();
+        let report = refresh_usage(&manager, requests, BatchLimits::default(), CancelToken::new(), |progress| {
+            completed.push((progress.completed, progress.total))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!((report.total, report.succeeded, report.failed), (2, 1, 1));
+        assert_eq!(completed, vec![(1, 2), (2, 2)]);
+        let failed = report.outcomes.iter().find(|o| o.account_id == second.id).unwrap();
+        assert!(failed.error.as_deref().unwrap_or_default().contains("异常退出"), "{:?}", failed.error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_after_refresh_uses_stored_events_only() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (mut manager, dir) = manager(&fake);
+        let history = Arc::new(UsageHistory::open_in_memory(&dir).unwrap());
+        let budgets = Arc::new(BudgetTracker::open(&dir));
+        manager.set_history(history.clone());
+        manager.set_budgets(budgets.clone());
+
+        let account = manager.add_account_by_token("t1".to_string(), None).await.unwrap();
+        let budget = serde_json::from_value(serde_json::json!({ "account_id": account.id, "monthly_limit": 100.0 })).unwrap();
+        budgets.set_budget(budget).unwrap();
+        let session: UsageSession = serde_json::from_value(serde_json::json!({
+            "session_id": "s1", "usage_time": chrono::Utc::now().timestamp() - 1, "mode": "", "model_name": "m",
+            "amount_float": 1.0, "cost_money_float": 12.5, "use_max_mode": false, "product_type_list": [],
+            "extra_info": { "cache_read_token": 0, "cache_write_token": 0, "input_token": 0, "output_token": 0 },
+        }))
+        .unwrap();
+        history.insert_sessions(&account.id, &[session]).unwrap();
+
+        let summary = manager.get_account_usage(&account.id).await.unwrap();
+        let calls = fake.calls().len();
+        manager.update_after_refresh(&account.id, &summary).await;
+
+        assert_eq!(fake.calls().len(), calls, "不应发起网络请求");
+        assert_eq!(budgets.status(&account.id).unwrap().event_spend, 12.5);
+        assert!(manager.lock_forecasts().contains_key(&account.id));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn only_transport_errors_fall_back_to_cache() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        let account = manager.add_account_by_token("t1".to_string(), None).await.unwrap();
+        manager.get_account_usage(&account.id).await.unwrap();
+
+        fake.set_offline(true);
+        let cached = manager.get_account_usage(&account.id).await.unwrap();
+        assert!(cached.stale.is_some());
+        // 内部计算不使用缓存
+        assert!(manager.fetch_account_usage(&account.id).await.is_err());
+
+        fake.set_offline(false);
+        fake.expire_token("t1");
+        let error = manager.get_account_usage(&account.id).await.unwrap_err();
+        assert!(error.to_string().contains("Token 已过期"), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn batch_refresh_offline_reports_cached_usage_as_failed() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        let account = manager.add_account_by_token("t1".to_string(), None).await.unwrap();
+        manager.get_account_usage(&account.id).await.unwrap();
+
+        fake.set_offline(true);
+        let requests = manager.entitlement_requests(std::slice::from_ref(&account.id), &AccountFilter::default()).unwrap();
+        let report = refresh_usage(&manager, requests, BatchLimits::default(), CancelToken::new(), |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!((report.succeeded, report.failed), (0, 1));
+        let outcome = &report.outcomes[0];
+        assert!(outcome.summary.as_ref().is_some_and(|s| s.stale.is_some()));
+        assert!(outcome.error.as_deref().unwrap_or_default().contains("network unreachable"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn forecast_starts_at_the_reported_cycle_start() {
+        let utc = |m, d| chrono::TimeZone::with_ymd_and_hms(&Tz::UTC, 2026, m, d, 0, 0, 0).unwrap().timestamp();
+        let now = utc(10, 18);
+        let mut summary = UsageSummary { cycle_start_time: utc(10, 3), reset_time: utc(11, 1), ..Default::default() };
+        assert_eq!(AccountManager::forecast_cycle_start(Tz::UTC, &summary, now), utc(10, 3));
+
+        // 没有周期开始时间时按重置时间向前推一个月
+        summary.cycle_start_time = 0;
+        assert_eq!(AccountManager::forecast_cycle_start(Tz::UTC, &summary, now), utc(10, 1));
+    }
+
+    #[tokio::test]
+    async fn adding_the_same_user_twice_fails() {
+        let fake = fake_with(&[("u1", "c1", "t1")]);
+        let (manager, dir) = manager(&fake);
+        manager.add_account("c1".to_string()).await.unwrap();
+
+        let token = fake.current_token("u1").unwrap();
+        let error = manager.add_account_by_token(token, None).await.unwrap_err();
+        assert_eq!(error.to_string(), "该账号已存在");
+        assert!(manager.add_account("c1".to_string()).await.is_err());
+        assert_eq!(manager.get_accounts(&AccountFilter::default()).len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn import_skips_duplicates_and_invalid_cookies() {
+        let fake = fake_with(&[("u1", "c1", "t1"), ("u2", "c2", "t2")]);
+        let (manager, dir) = manager(&fake);
+        manager.add_account("c1".to_string()).await.unwrap();
+
+        let data = serde_json::json!([
+            { "cookies": "c1" },
+            { "cookies": "c2" },
+            { "cookies": "c2" },
+            { "cookies": "" },
+            { "cookies": "unknown" },
+            { "name": "no cookies" },
+        ]);
+        let imported = manager.import_accounts(&data.to_string()).await.unwrap();
+        assert_eq!(imported, 1);
+        assert_eq!(manager.get_accounts(&AccountFilter::default()).len(), 2);
+
+        assert!(manager.import_accounts("not json").await.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
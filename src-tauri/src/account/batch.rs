@@ -0,0 +1,332 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex, Semaphore};
+use tokio::task::{Id, JoinSet};
+use tokio::time::Instant;
+
+use super::account_manager::AccountManager;
+use super::types::{Account, AccountFilter};
+use crate::api::{EntitlementListResponse, TraeApi, UsageSummary, UserTokenResult};
+
+/// 拉取配额信息所需的账号快照（不依赖账号管理器，可以并发执行）
+pub struct EntitlementRequest {
+    pub account: Account,
+    api: Arc<dyn TraeApi>,
+}
+
+/// 配额信息拉取结果，由账号管理器合并回账号数据
+pub struct EntitlementFetch {
+    /// 发起请求时的账号快照
+    pub account: Account,
+    pub result: Result<EntitlementListResponse>,
+    /// Token 过期后使用 Cookies 换取的新 Token
+    pub refreshed_token: Option<UserTokenResult>,
+}
+
+impl EntitlementRequest {
+    pub fn new(account: Account, api: Arc<dyn TraeApi>) -> Self {
+        Self { account, api }
+    }
+
+    /// 拉取配额信息
+    pub async fn send(self) -> EntitlementFetch {
+        let mut refreshed_token = None;
+        let result = self.fetch(&mut refreshed_token).await;
+        EntitlementFetch {
+            account: self.account,
+            result,
+            refreshed_token,
+        }
+    }
+
+    async fn fetch(&self, refreshed_token: &mut Option<UserTokenResult>) -> Result<EntitlementListResponse> {
+        let account = &self.account;
+
+        // 根据账号类型选择不同的方式获取配额信息
+        if let Some(token) = &account.jwt_token {
+            // 优先使用 Token，过期时用 Cookies 刷新后重试
+            let api = self.api.as_ref();
+            let (result, token_result) = AccountManager::call_with_token_refresh(api, account, token, |token| async move {
+                api.get_entitlement_list_by_token(&token).await
+            })
+            .await;
+            *refreshed_token = token_result;
+            result
+        } else if !account.cookies.is_empty() {
+            // 使用 Cookies
+            self.api.get_entitlement_list(&account.cookies).await
+        } else {
+            Err(anyhow!("账号没有有效的 Token 或 Cookies"))
+        }
+    }
+}
+
+/// 批量操作的取消标记（可跨命令共享）
+#[derive(Clone)]
+pub struct CancelToken(Arc<watch::Sender<bool>>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// 等待取消
+    pub async fn cancelled(&self) {
+        let mut receiver = self.0.subscribe();
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 按固定间隔放行请求
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64) -> Option<Self> {
+        (requests_per_second > 0.0).then(|| Self {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            next: Mutex::new(Instant::now()),
+        })
+    }
+
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// 批量请求的并发与频率限制
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchLimits {
+    /// 同时请求的账号数
+    pub concurrency: usize,
+    /// 每秒最多发起的请求数，0 表示不限制
+    pub requests_per_second: f64,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            requests_per_second: 2.0,
+        }
+    }
+}
+
+/// 批量拉取中单个账号的结果
+pub enum BatchItem {
+    Fetched(Box<EntitlementFetch>),
+    /// 请求发出前或进行中被取消
+    Cancelled(Box<Account>),
+}
+
+/// 以有限并发和频率拉取多个账号的配额信息，按完成顺序返回结果
+pub struct EntitlementBatch {
+    tasks: JoinSet<BatchItem>,
+    /// 任务对应的账号，任务异常退出时据此报告失败
+    accounts: HashMap<Id, Account>,
+}
+
+impl EntitlementBatch {
+    pub fn spawn(requests: Vec<EntitlementRequest>, limits: BatchLimits, cancel: CancelToken) -> Self {
+        let semaphore = Arc::new(Semaphore::new(limits.concurrency.max(1)));
+        let limiter = RateLimiter::new(limits.requests_per_second).map(Arc::new);
+        let mut tasks = JoinSet::new();
+        let mut accounts = HashMap::new();
+        for request in requests {
+            let (semaphore, limiter, cancel) = (semaphore.clone(), limiter.clone(), cancel.clone());
+            let account = request.account.clone();
+            let handle = tasks.spawn(async move {
+                let account = request.account.clone();
+                let fetch = async {
+                    let _permit = semaphore.acquire_owned().await;
+                    if let Some(limiter) = &limiter {
+                        limiter.wait().await;
+                    }
+                    request.send().await
+                };
+                tokio::select! {
+                    _ = cancel.cancelled() => BatchItem::Cancelled(Box::new(account)),
+                    fetch = fetch => BatchItem::Fetched(Box::new(fetch)),
+                }
+            });
+            accounts.insert(handle.id(), account);
+        }
+        Self { tasks, accounts }
+    }
+
+    /// 下一个完成的结果，全部完成后返回 None
+    pub async fn next(&mut self) -> Option<BatchItem> {
+        loop {
+            match self.tasks.join_next_with_id().await? {
+                Ok((id, item)) => {
+                    self.accounts.remove(&id);
+                    return Some(item);
+                }
+                Err(e) => {
+                    println!("[WARN] 拉取配额任务异常退出: {}", e);
+                    // 异常退出的任务计为失败，保证完成数与总数一致
+                    if let Some(account) = self.accounts.remove(&e.id()) {
+                        return Some(BatchItem::Fetched(Box::new(EntitlementFetch {
+                            account,
+                            result: Err(anyhow!("拉取配额任务异常退出: {}", e)),
+                            refreshed_token: None,
+                        })));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 批量刷新选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchRefreshOptions {
+    /// 为空时刷新全部账号
+    pub account_ids: Vec<String>,
+    pub filter: AccountFilter,
+    #[serde(flatten)]
+    pub limits: BatchLimits,
+}
+
+/// 单个账号的刷新状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStatus {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// 单个账号的刷新结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshOutcome {
+    pub account_id: String,
+    pub account_name: String,
+    pub account_email: String,
+    pub status: RefreshStatus,
+    /// 网络不可用时为缓存的使用量（`stale` 不为空）
+    pub summary: Option<UsageSummary>,
+    pub error: Option<String>,
+}
+
+impl RefreshOutcome {
+    fn new(account: &Account, status: RefreshStatus, result: Result<UsageSummary>) -> Self {
+        let (summary, error) = match result {
+            Ok(summary) => {
+                let error = summary.stale.as_ref().map(|stale| stale.error.clone());
+                (Some(summary), error)
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            account_id: account.id.clone(),
+            account_name: account.name.clone(),
+            account_email: account.email.clone(),
+            status,
+            summary,
+            error,
+        }
+    }
+}
+
+/// 批量刷新进度（每完成一个账号推送一次）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRefreshProgress {
+    pub outcome: RefreshOutcome,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// 批量刷新结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRefreshReport {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// 是否被取消（部分账号可能已经刷新）
+    pub was_cancelled: bool,
+    pub outcomes: Vec<RefreshOutcome>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+/// 批量刷新使用量
+///
+/// 网络请求并发执行，期间不持有账号数据的锁；每个结果到达时短暂加锁合并到内存中的
+/// 账号数据，全部完成（或取消）后统一保存一次。
+pub async fn refresh_usage(
+    manager: &AccountManager,
+    requests: Vec<EntitlementRequest>,
+    limits: BatchLimits,
+    cancel: CancelToken,
+    mut on_progress: impl FnMut(&BatchRefreshProgress),
+) -> Result<BatchRefreshReport> {
+    let started_at = chrono::Utc::now().timestamp();
+    let total = requests.len();
+    let mut batch = EntitlementBatch::spawn(requests, limits, cancel.clone());
+
+    let mut outcomes: Vec<RefreshOutcome> = Vec::with_capacity(total);
+    while let Some(item) = batch.next().await {
+        let outcome = match item {
+            BatchItem::Fetched(fetch) => {
+                let account = fetch.account.clone();
+                let result = manager.merge_usage_fetch_or_cached(*fetch);
+                // 返回缓存数据时仍计为失败（后台刷新据此退避）
+                let succeeded = result.as_ref().is_ok_and(|summary| summary.stale.is_none());
+                let status = if succeeded { RefreshStatus::Succeeded } else { RefreshStatus::Failed };
+                RefreshOutcome::new(&account, status, result)
+            }
+            BatchItem::Cancelled(account) => {
+                RefreshOutcome::new(&account, RefreshStatus::Cancelled, Err(anyhow!("刷新已取消")))
+            }
+        };
+
+        on_progress(&BatchRefreshProgress {
+            outcome: outcome.clone(),
+            completed: outcomes.len() + 1,
+            total,
+        });
+        outcomes.push(outcome);
+    }
+    manager.save_store()?;
+
+    let count = |status: RefreshStatus| outcomes.iter().filter(|o| o.status == status).count();
+    Ok(BatchRefreshReport {
+        total,
+        succeeded: count(RefreshStatus::Succeeded),
+        failed: count(RefreshStatus::Failed),
+        cancelled: count(RefreshStatus::Cancelled),
+        was_cancelled: cancel.is_cancelled(),
+        outcomes,
+        started_at,
+        finished_at: chrono::Utc::now().timestamp(),
+    })
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::types::AccountBrief;
+use crate::api::UsageSummary;
+
+/// 事件通道容量，订阅方处理过慢时会丢弃最旧的事件
+const EVENT_CAPACITY: usize = 256;
+
+/// 账号管理器发布的领域事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccountEvent {
+    /// 添加了新账号
+    AccountAdded { account: AccountBrief },
+    /// 删除了账号
+    AccountRemoved { account_id: String },
+    /// 账号信息被修改（凭据、机器码、活跃账号等）
+    AccountUpdated { account: AccountBrief },
+    /// 刷新得到了最新使用量
+    UsageRefreshed { account_id: String, summary: UsageSummary },
+    /// Token 被刷新（自动刷新或手动刷新）
+    TokenRefreshed { account_id: String, expired_at: Option<String> },
+    /// 开始切换 Trae IDE 账号
+    SwitchStarted { account_id: String },
+    /// 切换 Trae IDE 账号完成
+    SwitchCompleted { account_id: String },
+    /// 切换 Trae IDE 账号失败
+    SwitchFailed { account_id: String, error: String },
+    /// 从磁盘重新加载了账号数据
+    StoreReloaded { account_count: usize },
+}
+
+/// 领域事件总线（可以有多个订阅方，例如桌面窗口和命令行前端）
+pub struct AccountEvents {
+    sender: broadcast::Sender<AccountEvent>,
+}
+
+impl AccountEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self { sender }
+    }
+
+    /// 订阅之后发布的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<AccountEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 发布事件（没有订阅方时直接丢弃）
+    pub fn publish(&self, event: AccountEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for AccountEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
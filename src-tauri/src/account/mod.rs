@@ -1,5 +1,11 @@
 pub mod account_manager;
+pub mod batch;
+pub mod events;
+pub mod team;
 pub mod types;
 
 pub use account_manager::AccountManager;
+pub use events::AccountEvent;
+pub use batch::{refresh_usage, BatchLimits, BatchRefreshOptions, BatchRefreshReport, CancelToken};
+pub use team::{TeamAccountRow, TeamDashboard, TeamDashboardProgress, TeamDashboardQuery};
 pub use types::*;
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+use super::batch::{BatchLimits, RefreshOutcome};
+use super::types::AccountFilter;
+use crate::analytics::forecast::QuotaCategory;
+use crate::api::{UsageStaleness, UsageSummary};
+
+/// 团队看板查询
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamDashboardQuery {
+    /// 为空时包括全部账号
+    pub account_ids: Vec<String>,
+    pub filter: AccountFilter,
+    #[serde(flatten)]
+    pub limits: BatchLimits,
+}
+
+/// 单个额度类别的使用情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryUsage {
+    pub category: QuotaCategory,
+    pub used: f64,
+    pub limit: i64,
+    pub left: f64,
+}
+
+impl CategoryUsage {
+    /// 使用量汇总中已开放的额度类别
+    pub fn from_summary(summary: &UsageSummary) -> Vec<Self> {
+        QuotaCategory::quotas(summary)
+            .into_iter()
+            .filter(|quota| quota.limit > 0)
+            .map(|quota| Self {
+                category: quota.category,
+                used: quota.used,
+                limit: quota.limit,
+                left: quota.left,
+            })
+            .collect()
+    }
+}
+
+/// 剩余额度最少的类别
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowestRemaining {
+    pub account_id: String,
+    pub account_name: String,
+    pub category: QuotaCategory,
+    pub left: f64,
+    pub limit: i64,
+}
+
+/// 最早重置的账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoonestReset {
+    pub account_id: String,
+    pub account_name: String,
+    pub reset_time: i64,
+}
+
+/// 团队看板中的单个账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamAccountRow {
+    pub account_id: String,
+    pub name: String,
+    pub email: String,
+    pub plan_type: String,
+    pub categories: Vec<CategoryUsage>,
+    pub reset_time: i64,
+    /// 按剩余比例计算的最紧张类别
+    pub lowest_remaining: Option<LowestRemaining>,
+    /// 拉取失败的原因，成功时为空
+    pub error: Option<String>,
+    /// 网络不可用时使用的缓存数据的说明
+    pub stale: Option<UsageStaleness>,
+}
+
+impl TeamAccountRow {
+    pub fn new(outcome: &RefreshOutcome) -> Self {
+        let mut row = Self {
+            account_id: outcome.account_id.clone(),
+            name: outcome.account_name.clone(),
+            email: outcome.account_email.clone(),
+            plan_type: String::new(),
+            categories: Vec::new(),
+            reset_time: 0,
+            lowest_remaining: None,
+            error: outcome.error.clone(),
+            stale: None,
+        };
+
+        if let Some(summary) = &outcome.summary {
+            row.plan_type = summary.plan_type.clone();
+            row.categories = CategoryUsage::from_summary(summary);
+            row.reset_time = summary.reset_time;
+            row.stale = summary.stale.clone();
+            row.lowest_remaining = row
+                .categories
+                .iter()
+                .min_by(|a, b| (a.left / a.limit as f64).total_cmp(&(b.left / b.limit as f64)))
+                .map(|c| LowestRemaining {
+                    account_id: outcome.account_id.clone(),
+                    account_name: outcome.account_name.clone(),
+                    category: c.category,
+                    left: c.left,
+                    limit: c.limit,
+                });
+        }
+        row
+    }
+}
+
+/// 团队汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TeamTotals {
+    pub account_count: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 各类别额度合计（包括使用缓存数据的账号）
+    pub categories: Vec<CategoryUsage>,
+    pub soonest_reset: Option<SoonestReset>,
+    pub lowest_remaining: Option<LowestRemaining>,
+}
+
+impl TeamTotals {
+    pub fn new(rows: &[TeamAccountRow]) -> Self {
+        let mut totals = Self {
+            account_count: rows.len(),
+            ..Default::default()
+        };
+
+        for row in rows {
+            if row.error.is_none() {
+                totals.succeeded += 1;
+            } else {
+                totals.failed += 1;
+                // 没有缓存数据的账号不计入合计
+                if row.stale.is_none() {
+                    continue;
+                }
+            }
+
+            for usage in &row.categories {
+                match totals.categories.iter_mut().find(|c| c.category == usage.category) {
+                    Some(total) => {
+                        total.used += usage.used;
+                        total.limit += usage.limit;
+                        total.left += usage.left;
+                    }
+                    None => totals.categories.push(usage.clone()),
+                }
+            }
+
+            if row.reset_time > 0 && totals.soonest_reset.as_ref().is_none_or(|r| row.reset_time < r.reset_time) {
+                totals.soonest_reset = Some(SoonestReset {
+                    account_id: row.account_id.clone(),
+                    account_name: row.name.clone(),
+                    reset_time: row.reset_time,
+                });
+            }
+
+            if let Some(lowest) = &row.lowest_remaining {
+                let ratio = |l: &LowestRemaining| l.left / l.limit as f64;
+                if totals.lowest_remaining.as_ref().is_none_or(|current| ratio(lowest) < ratio(current)) {
+                    totals.lowest_remaining = Some(lowest.clone());
+                }
+            }
+        }
+        totals
+    }
+}
+
+/// 团队看板
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamDashboard {
+    pub rows: Vec<TeamAccountRow>,
+    pub totals: TeamTotals,
+    pub fetched_at: i64,
+}
+
+impl TeamDashboard {
+    pub fn new(mut rows: Vec<TeamAccountRow>) -> Self {
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            totals: TeamTotals::new(&rows),
+            rows,
+            fetched_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// 团队看板的部分结果（每完成一个账号推送一次）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamDashboardProgress {
+    pub row: TeamAccountRow,
+    pub completed: usize,
+    pub total: usize,
+}
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::analytics::ForecastBrief;
+use crate::budgets::BudgetStatus;
+use crate::api::{Feature, FeatureFlags, SubscriptionInfo, UsageStaleness, UsageSummary};
+
 /// 账号信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
@@ -20,6 +24,15 @@ pub struct Account {
     /// 账号关联的机器码
     #[serde(default)]
     pub machine_id: Option<String>,
+    /// 最近一次刷新得到的订阅信息
+    #[serde(default)]
+    pub subscription: Option<SubscriptionInfo>,
+    /// 最近一次刷新得到的功能开关
+    #[serde(default)]
+    pub features: FeatureFlags,
+    /// 最近一次成功刷新的使用量（网络不可用时作为缓存返回）
+    #[serde(default)]
+    pub last_usage: Option<CachedUsage>,
 }
 
 impl Account {
@@ -47,7 +60,54 @@ impl Account {
             updated_at: now,
             is_active: true,
             machine_id: None,
+            subscription: None,
+            features: FeatureFlags::default(),
+            last_usage: None,
+        }
+    }
+}
+
+impl Account {
+    /// Token 过期时间（秒），支持 RFC 3339 和时间戳格式
+    pub fn token_expires_at(&self) -> Option<i64> {
+        let expired_at = self.token_expired_at.as_deref()?.trim();
+        if let Ok(time) = chrono::DateTime::parse_from_rfc3339(expired_at) {
+            return Some(time.timestamp());
         }
+        let timestamp: i64 = expired_at.parse().ok()?;
+        // 毫秒时间戳转换为秒
+        Some(if timestamp > 100_000_000_000 { timestamp / 1000 } else { timestamp })
+    }
+
+    /// 凭据（Token 和 Cookies）是否与快照一致，用于判断网络请求期间账号是否被修改
+    pub fn same_credentials(&self, snapshot: &Account) -> bool {
+        self.jwt_token == snapshot.jwt_token && self.cookies == snapshot.cookies
+    }
+}
+
+/// 缓存的使用量及其拉取时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedUsage {
+    pub summary: UsageSummary,
+    pub fetched_at: i64,
+}
+
+impl CachedUsage {
+    pub fn new(summary: &UsageSummary, fetched_at: i64) -> Self {
+        let mut summary = summary.clone();
+        summary.stale = None;
+        Self { summary, fetched_at }
+    }
+
+    /// 将缓存作为过期的使用量返回，附带缓存时长和刷新失败的原因
+    pub fn to_stale(&self, error: &anyhow::Error, now: i64) -> UsageSummary {
+        let mut summary = self.summary.clone();
+        summary.stale = Some(UsageStaleness {
+            fetched_at: self.fetched_at,
+            age_seconds: (now - self.fetched_at).max(0),
+            error: error.to_string(),
+        });
+        summary
     }
 }
 
@@ -61,6 +121,35 @@ pub struct AccountStore {
     pub current_account_id: Option<String>,
 }
 
+/// 最近一次配额响应的诊断信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitlementDiagnostics {
+    pub fetched_at: i64,
+    /// 成功解析的礼包数量
+    pub pack_count: usize,
+    /// 解析失败的礼包及原因
+    pub pack_errors: Vec<String>,
+    /// 接口返回的原始数据
+    pub raw: serde_json::Value,
+}
+
+/// 礼包目录及覆盖文件路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageCatalogView {
+    /// 用户覆盖文件路径（可手动编辑）
+    pub override_path: String,
+    pub catalog: crate::api::PackageCatalog,
+}
+
+/// 账号续费信息（用于按续费时间排序）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRenewal {
+    pub account_id: String,
+    pub name: String,
+    pub email: String,
+    pub subscription: SubscriptionInfo,
+}
+
 /// 简单的 UUID 生成
 fn uuid_simple() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -84,6 +173,27 @@ pub struct AccountBrief {
     pub machine_id: Option<String>,
     /// 是否是当前 Trae IDE 正在使用的账号
     pub is_current: bool,
+    /// 账号可用的功能
+    pub features: FeatureFlags,
+    /// 最近一次额度消耗预测
+    pub forecast: Option<ForecastBrief>,
+    /// 本月预算使用情况（未设置预算时为空）
+    pub budget: Option<BudgetStatus>,
+}
+
+/// 账号列表过滤条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountFilter {
+    /// 只返回开启了这些功能的账号
+    pub features: Vec<Feature>,
+}
+
+impl AccountFilter {
+    /// 账号是否满足过滤条件
+    pub fn matches(&self, account: &Account) -> bool {
+        self.features.iter().all(|feature| account.features.has(*feature))
+    }
 }
 
 impl From<&Account> for AccountBrief {
@@ -98,6 +208,9 @@ impl From<&Account> for AccountBrief {
             created_at: account.created_at,
             machine_id: account.machine_id.clone(),
             is_current: false, // 默认为 false，由 AccountManager 设置
+            features: account.features,
+            forecast: None,
+            budget: None,
         }
     }
 }
@@ -115,6 +228,9 @@ impl AccountBrief {
             created_at: account.created_at,
             machine_id: account.machine_id.clone(),
             is_current,
+            features: account.features,
+            forecast: None,
+            budget: None,
         }
     }
 }
@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::types::*;
+use crate::account::Account;
+use crate::api::UsageSummary;
+
+/// 告警数据文件名
+const ALERTS_FILE: &str = "alerts.json";
+
+/// 告警通知方式（如系统通知）
+pub trait AlertNotifier: Send + Sync {
+    fn notify(&self, alert: &Alert);
+}
+
+/// 持久化的告警数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AlertStore {
+    settings: AlertSettings,
+    /// 全部告警（含已解除），按触发时间升序
+    alerts: Vec<Alert>,
+}
+
+/// 告警中心：评估规则、去重、暂停提醒和保存记录
+pub struct AlertCenter {
+    store: Mutex<AlertStore>,
+    path: PathBuf,
+    notifier: Mutex<Option<Arc<dyn AlertNotifier>>>,
+}
+
+impl AlertCenter {
+    /// 打开数据目录下的告警数据
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(ALERTS_FILE);
+        let store = if path.exists() {
+            fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    println!("[WARN] 告警数据文件无效，使用默认设置: {}", e);
+                    AlertStore::default()
+                })
+        } else {
+            AlertStore::default()
+        };
+
+        Self {
+            store: Mutex::new(store),
+            path,
+            notifier: Mutex::new(None),
+        }
+    }
+
+    /// 设置通知方式
+    pub fn set_notifier(&self, notifier: Arc<dyn AlertNotifier>) {
+        if let Ok(mut current) = self.notifier.lock() {
+            *current = Some(notifier);
+        }
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, AlertStore>> {
+        self.store.lock().map_err(|_| anyhow!("告警数据不可用"))
+    }
+
+    fn save(&self, store: &AlertStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(store)?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// 获取告警设置
+    pub fn settings(&self) -> Result<AlertSettings> {
+        Ok(self.store()?.settings.clone())
+    }
+
+    /// 更新告警设置，被删除或停用的规则对应的告警会被解除
+    pub fn update_settings(&self, mut settings: AlertSettings) -> Result<AlertSettings> {
+        for rule in &mut settings.rules {
+            if rule.name.trim().is_empty() {
+                return Err(anyhow!("告警规则名称不能为空"));
+            }
+            if rule.id.is_empty() {
+                rule.id = uuid::Uuid::new_v4().to_string();
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut store = self.store()?;
+        for alert in store.alerts.iter_mut().filter(|a| a.is_active()) {
+            let still_enabled = settings
+                .rules
+                .iter()
+                .any(|rule| rule.id == alert.rule_id && rule.applies_to(&alert.account_id));
+            if !still_enabled {
+                alert.resolved_at = Some(now);
+            }
+        }
+        store.settings = settings.clone();
+        self.save(&store)?;
+        Ok(settings)
+    }
+
+    /// 根据最新使用量评估账号的告警规则，返回新触发（需要提醒）的告警
+    pub fn evaluate(&self, account: &Account, summary: &UsageSummary) -> Result<Vec<Alert>> {
+        let now = chrono::Utc::now().timestamp();
+        let mut store = self.store()?;
+        let mut raised = Vec::new();
+
+        for rule in store.settings.rules.clone() {
+            if !rule.applies_to(&account.id) {
+                continue;
+            }
+            let value = rule.metric.value(account, summary, now);
+            let triggered = value.is_some_and(|value| rule.comparison.check(value, rule.threshold));
+            let existing = store
+                .alerts
+                .iter_mut()
+                .find(|a| a.is_active() && a.rule_id == rule.id && a.account_id == account.id);
+
+            match (existing, value) {
+                (Some(alert), Some(value)) if triggered => {
+                    alert.value = value;
+                    alert.updated_at = now;
+                    // 暂停结束后仍未确认的告警再提醒一次
+                    if alert.snoozed_until.is_some() && !alert.is_snoozed(now) && !alert.acknowledged {
+                        alert.snoozed_until = None;
+                        raised.push(alert.clone());
+                    }
+                }
+                (Some(alert), _) => {
+                    alert.resolved_at = Some(now);
+                    alert.updated_at = now;
+                }
+                (None, Some(value)) if triggered => {
+                    let alert = Alert {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        account_id: account.id.clone(),
+                        account_name: account.email.clone(),
+                        metric: rule.metric,
+                        comparison: rule.comparison,
+                        threshold: rule.threshold,
+                        value,
+                        message: format!(
+                            "{}: {}（{} {:.1}，阈值 {} {}）",
+                            account.email,
+                            rule.name,
+                            rule.metric.label(),
+                            value,
+                            rule.comparison.symbol(),
+                            rule.threshold
+                        ),
+                        raised_at: now,
+                        updated_at: now,
+                        resolved_at: None,
+                        snoozed_until: None,
+                        acknowledged: false,
+                    };
+                    store.alerts.push(alert.clone());
+                    raised.push(alert);
+                }
+                (None, _) => {}
+            }
+        }
+
+        Self::trim(&mut store);
+        self.save(&store)?;
+        let native = store.settings.native_notifications;
+        drop(store);
+
+        if native && !raised.is_empty() {
+            if let Some(notifier) = self.notifier.lock().ok().and_then(|n| n.clone()) {
+                for alert in &raised {
+                    notifier.notify(alert);
+                }
+            }
+        }
+        Ok(raised)
+    }
+
+    /// 超出记录上限时优先删除最早的已解除告警
+    fn trim(store: &mut AlertStore) {
+        let limit = store.settings.history_limit;
+        let mut excess = store.alerts.len().saturating_sub(limit);
+        store.alerts.retain(|alert| {
+            if excess > 0 && !alert.is_active() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// 获取告警列表（按触发时间倒序），`include_resolved` 为 false 时只返回未解除的告警
+    pub fn list_alerts(&self, include_resolved: bool) -> Result<Vec<Alert>> {
+        let store = self.store()?;
+        Ok(store
+            .alerts
+            .iter()
+            .rev()
+            .filter(|a| include_resolved || a.is_active())
+            .cloned()
+            .collect())
+    }
+
+    fn update_alert(&self, alert_id: &str, f: impl FnOnce(&mut Alert)) -> Result<Alert> {
+        let mut store = self.store()?;
+        let alert = store
+            .alerts
+            .iter_mut()
+            .find(|a| a.id == alert_id)
+            .ok_or_else(|| anyhow!("告警不存在"))?;
+        f(alert);
+        let alert = alert.clone();
+        self.save(&store)?;
+        Ok(alert)
+    }
+
+    /// 暂停提醒直到指定时间
+    pub fn snooze(&self, alert_id: &str, until: i64) -> Result<Alert> {
+        self.update_alert(alert_id, |alert| alert.snoozed_until = Some(until))
+    }
+
+    /// 确认告警（条件解除前不再提醒）
+    pub fn acknowledge(&self, alert_id: &str) -> Result<Alert> {
+        self.update_alert(alert_id, |alert| alert.acknowledged = true)
+    }
+
+    /// 清除已解除的告警记录
+    pub fn clear_resolved(&self) -> Result<usize> {
+        let mut store = self.store()?;
+        let before = store.alerts.len();
+        store.alerts.retain(Alert::is_active);
+        let removed = before - store.alerts.len();
+        self.save(&store)?;
+        Ok(removed)
+    }
+
+    /// 删除账号的全部告警
+    pub fn delete_account(&self, account_id: &str) -> Result<()> {
+        let mut store = self.store()?;
+        store.alerts.retain(|a| a.account_id != account_id);
+        self.save(&store)
+    }
+}
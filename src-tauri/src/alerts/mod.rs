@@ -0,0 +1,5 @@
+pub mod alert_center;
+pub mod types;
+
+pub use alert_center::{AlertCenter, AlertNotifier};
+pub use types::*;
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+
+use crate::account::Account;
+use crate::api::UsageSummary;
+
+const HOUR: f64 = 3600.0;
+
+/// 告警规则监控的指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    FastRequestLeft,
+    FastRequestUsed,
+    /// Fast Request 已用百分比（0-100）
+    FastRequestUsedPercent,
+    SlowRequestLeft,
+    AdvancedModelLeft,
+    AutocompleteLeft,
+    ExtraFastRequestLeft,
+    /// 额外礼包距离过期的小时数
+    ExtraPackExpiresInHours,
+    /// 距离额度重置的小时数
+    ResetInHours,
+    /// Token 距离过期的小时数
+    TokenExpiresInHours,
+}
+
+impl AlertMetric {
+    /// 指标名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::FastRequestLeft => "Fast Request 剩余",
+            Self::FastRequestUsed => "Fast Request 已用",
+            Self::FastRequestUsedPercent => "Fast Request 已用百分比",
+            Self::SlowRequestLeft => "Slow Request 剩余",
+            Self::AdvancedModelLeft => "Advanced Model 剩余",
+            Self::AutocompleteLeft => "Autocomplete 剩余",
+            Self::ExtraFastRequestLeft => "礼包额度剩余",
+            Self::ExtraPackExpiresInHours => "礼包剩余有效小时",
+            Self::ResetInHours => "距离重置小时",
+            Self::TokenExpiresInHours => "Token 剩余有效小时",
+        }
+    }
+
+    /// 计算指标的当前值，不适用时（如没有礼包）返回 None
+    pub fn value(&self, account: &Account, summary: &UsageSummary, now: i64) -> Option<f64> {
+        let hours_until = |time: i64| {
+            if time > 0 {
+                Some((time - now) as f64 / HOUR)
+            } else {
+                None
+            }
+        };
+        let has_extra = summary.extra_fast_request_limit > 0;
+
+        match self {
+            Self::FastRequestLeft => Some(summary.fast_request_left),
+            Self::FastRequestUsed => Some(summary.fast_request_used),
+            Self::FastRequestUsedPercent => (summary.fast_request_limit > 0)
+                .then(|| summary.fast_request_used / summary.fast_request_limit as f64 * 100.0),
+            Self::SlowRequestLeft => (summary.slow_request_limit > 0).then_some(summary.slow_request_left),
+            Self::AdvancedModelLeft => (summary.advanced_model_limit > 0).then_some(summary.advanced_model_left),
+            Self::AutocompleteLeft => (summary.autocomplete_limit > 0).then_some(summary.autocomplete_left),
+            Self::ExtraFastRequestLeft => has_extra.then_some(summary.extra_fast_request_left),
+            Self::ExtraPackExpiresInHours => has_extra.then(|| hours_until(summary.extra_expire_time)).flatten(),
+            Self::ResetInHours => hours_until(summary.reset_time),
+            Self::TokenExpiresInHours => account.token_expires_at().and_then(hours_until),
+        }
+    }
+}
+
+/// 比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+}
+
+impl Comparison {
+    pub fn check(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Lt => value < threshold,
+            Self::Le => value <= threshold,
+            Self::Gt => value > threshold,
+            Self::Ge => value >= threshold,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+/// 告警规则，如 "fast_request_left < 50"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// 为空时自动生成
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub metric: AlertMetric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 适用的账号，为空表示所有账号
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl AlertRule {
+    pub fn new(name: &str, metric: AlertMetric, comparison: Comparison, threshold: f64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            metric,
+            comparison,
+            threshold,
+            enabled: true,
+            account_ids: Vec::new(),
+        }
+    }
+
+    /// 规则是否适用于账号
+    pub fn applies_to(&self, account_id: &str) -> bool {
+        self.enabled && (self.account_ids.is_empty() || self.account_ids.iter().any(|id| id == account_id))
+    }
+
+    /// 默认规则
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("Fast Request 即将用完", AlertMetric::FastRequestLeft, Comparison::Lt, 50.0),
+            Self::new("礼包即将过期", AlertMetric::ExtraPackExpiresInHours, Comparison::Lt, 72.0),
+            Self::new("Token 即将过期", AlertMetric::TokenExpiresInHours, Comparison::Lt, 24.0),
+        ]
+    }
+}
+
+/// 告警（同一规则和账号同时只有一条未解除的告警）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub account_id: String,
+    pub account_name: String,
+    pub metric: AlertMetric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// 最近一次评估时的指标值
+    pub value: f64,
+    pub message: String,
+    pub raised_at: i64,
+    pub updated_at: i64,
+    /// 条件不再满足的时间，未解除时为 None
+    pub resolved_at: Option<i64>,
+    /// 暂停提醒直到该时间
+    pub snoozed_until: Option<i64>,
+    pub acknowledged: bool,
+}
+
+impl Alert {
+    pub fn is_active(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+
+    pub fn is_snoozed(&self, now: i64) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+}
+
+/// 告警设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertSettings {
+    pub rules: Vec<AlertRule>,
+    /// 是否发送系统通知
+    pub native_notifications: bool,
+    /// 最多保留的告警记录数
+    pub history_limit: usize,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            rules: AlertRule::defaults(),
+            native_notifications: true,
+            history_limit: 500,
+        }
+    }
+}
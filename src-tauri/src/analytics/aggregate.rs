@@ -0,0 +1,82 @@
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+use super::time::{billing_cycle, day_bucket, week_bucket};
+use super::types::*;
+use crate::api::UsageSession;
+
+impl UsageGroup {
+    fn new(key: String) -> Self {
+        Self {
+            key,
+            ..Default::default()
+        }
+    }
+
+    /// 累加一条使用事件
+    pub fn add(&mut self, session: &UsageSession) {
+        self.request_count += 1;
+        self.amount += session.amount_float;
+        self.cost += session.cost_money_float;
+        self.input_tokens += session.extra_info.input_token;
+        self.output_tokens += session.extra_info.output_token;
+        self.cache_read_tokens += session.extra_info.cache_read_token;
+        self.cache_write_tokens += session.extra_info.cache_write_token;
+    }
+}
+
+/// 按指定方式聚合使用事件，返回各分组及合计
+///
+/// `cycle_anchor` 为按计费周期分组时使用的重置时间。时间分组按开始时间升序，
+/// 其它分组按消耗量降序。
+pub fn aggregate_sessions(
+    sessions: &[UsageSession],
+    group_by: GroupBy,
+    tz: Tz,
+    cycle_anchor: i64,
+) -> (Vec<UsageGroup>, UsageGroup) {
+    let mut groups: HashMap<String, UsageGroup> = HashMap::new();
+    let mut total = UsageGroup::new("total".to_string());
+
+    for session in sessions {
+        let (key, range) = match group_by {
+            GroupBy::Model => (session.model_name.clone(), None),
+            GroupBy::Mode => (session.mode.clone(), None),
+            GroupBy::MaxMode => {
+                let key = if session.use_max_mode { "max" } else { "normal" };
+                (key.to_string(), None)
+            }
+            GroupBy::Day => {
+                let (key, start, end) = day_bucket(tz, session.usage_time);
+                (key, Some((start, end)))
+            }
+            GroupBy::Week => {
+                let (key, start, end) = week_bucket(tz, session.usage_time);
+                (key, Some((start, end)))
+            }
+            GroupBy::BillingCycle => {
+                let (key, start, end) = billing_cycle(tz, cycle_anchor, session.usage_time);
+                (key, Some((start, end)))
+            }
+        };
+
+        let group = groups.entry(key.clone()).or_insert_with(|| {
+            let mut group = UsageGroup::new(key);
+            if let Some((start, end)) = range {
+                group.start_time = Some(start);
+                group.end_time = Some(end);
+            }
+            group
+        });
+        group.add(session);
+        total.add(session);
+    }
+
+    let mut groups: Vec<UsageGroup> = groups.into_values().collect();
+    if group_by.is_time_based() {
+        groups.sort_by_key(|g| g.start_time);
+    } else {
+        groups.sort_by(|a, b| b.amount.total_cmp(&a.amount).then_with(|| a.key.cmp(&b.key)));
+    }
+    (groups, total)
+}
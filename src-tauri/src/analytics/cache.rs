@@ -0,0 +1,187 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use super::aggregate::aggregate_sessions;
+use super::types::{EventSource, GroupBy, UsageGroup};
+use crate::api::UsageSession;
+
+/// 缓存 token 相对于普通输入 token 的计费倍数
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachePricing {
+    pub read_multiplier: f64,
+    pub write_multiplier: f64,
+}
+
+impl Default for CachePricing {
+    fn default() -> Self {
+        Self {
+            read_multiplier: 0.1,
+            write_multiplier: 1.25,
+        }
+    }
+}
+
+/// 缓存效率报告查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheReportQuery {
+    pub account_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    #[serde(default)]
+    pub source: EventSource,
+    #[serde(default)]
+    pub pricing: CachePricing,
+    /// 列出缓存利用率最差的会话数，默认 10
+    #[serde(default)]
+    pub worst_limit: Option<usize>,
+    /// 参与最差排名的最小提示 token 数，默认 10000（忽略很短的会话）
+    #[serde(default)]
+    pub min_prompt_tokens: Option<i64>,
+}
+
+/// 缓存统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub request_count: u64,
+    pub input_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    /// 提示 token 总数（输入 + 缓存读 + 缓存写）
+    pub prompt_tokens: i64,
+    /// 缓存命中率：缓存读 / 提示 token
+    pub hit_ratio: f64,
+    /// 缓存写入占比：缓存写 / 提示 token
+    pub write_share: f64,
+    /// 折算为普通输入 token 的节省量（可能为负）
+    pub saved_tokens: f64,
+    /// 相对于不使用缓存的节省比例
+    pub savings_ratio: f64,
+}
+
+impl CacheStats {
+    pub fn from_tokens(
+        request_count: u64,
+        input_tokens: i64,
+        cache_read_tokens: i64,
+        cache_write_tokens: i64,
+        pricing: &CachePricing,
+    ) -> Self {
+        let prompt_tokens = input_tokens + cache_read_tokens + cache_write_tokens;
+        let ratio = |tokens: i64| {
+            if prompt_tokens > 0 {
+                tokens as f64 / prompt_tokens as f64
+            } else {
+                0.0
+            }
+        };
+        let actual = input_tokens as f64
+            + cache_read_tokens as f64 * pricing.read_multiplier
+            + cache_write_tokens as f64 * pricing.write_multiplier;
+        let saved_tokens = prompt_tokens as f64 - actual;
+
+        Self {
+            request_count,
+            input_tokens,
+            cache_read_tokens,
+            cache_write_tokens,
+            prompt_tokens,
+            hit_ratio: ratio(cache_read_tokens),
+            write_share: ratio(cache_write_tokens),
+            saved_tokens,
+            savings_ratio: if prompt_tokens > 0 { saved_tokens / prompt_tokens as f64 } else { 0.0 },
+        }
+    }
+
+    fn from_group(group: &UsageGroup, pricing: &CachePricing) -> Self {
+        Self::from_tokens(
+            group.request_count,
+            group.input_tokens,
+            group.cache_read_tokens,
+            group.cache_write_tokens,
+            pricing,
+        )
+    }
+}
+
+/// 单个模型的缓存统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCacheStats {
+    pub model_name: String,
+    pub stats: CacheStats,
+}
+
+/// 单个会话的缓存统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCacheStats {
+    pub session_id: String,
+    pub usage_time: i64,
+    pub model_name: String,
+    pub mode: String,
+    pub stats: CacheStats,
+}
+
+/// 缓存效率报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEfficiencyReport {
+    pub account_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub pricing: CachePricing,
+    pub overall: CacheStats,
+    /// 按提示 token 数降序
+    pub by_model: Vec<ModelCacheStats>,
+    /// 缓存利用率最差的会话（命中率升序）
+    pub worst_sessions: Vec<SessionCacheStats>,
+}
+
+/// 根据使用事件生成缓存效率报告
+pub fn cache_report(sessions: &[UsageSession], query: &CacheReportQuery) -> CacheEfficiencyReport {
+    let pricing = query.pricing;
+    let (groups, total) = aggregate_sessions(sessions, GroupBy::Model, Tz::UTC, 0);
+
+    let mut by_model: Vec<ModelCacheStats> = groups
+        .iter()
+        .map(|group| ModelCacheStats {
+            model_name: group.key.clone(),
+            stats: CacheStats::from_group(group, &pricing),
+        })
+        .collect();
+    by_model.sort_by_key(|m| std::cmp::Reverse(m.stats.prompt_tokens));
+
+    let min_prompt_tokens = query.min_prompt_tokens.unwrap_or(10_000);
+    let mut worst_sessions: Vec<SessionCacheStats> = sessions
+        .iter()
+        .map(|session| SessionCacheStats {
+            session_id: session.session_id.clone(),
+            usage_time: session.usage_time,
+            model_name: session.model_name.clone(),
+            mode: session.mode.clone(),
+            stats: CacheStats::from_tokens(
+                1,
+                session.extra_info.input_token,
+                session.extra_info.cache_read_token,
+                session.extra_info.cache_write_token,
+                &pricing,
+            ),
+        })
+        .filter(|session| session.stats.prompt_tokens >= min_prompt_tokens)
+        .collect();
+    worst_sessions.sort_by(|a, b| {
+        a.stats
+            .hit_ratio
+            .total_cmp(&b.stats.hit_ratio)
+            .then_with(|| b.stats.prompt_tokens.cmp(&a.stats.prompt_tokens))
+    });
+    worst_sessions.truncate(query.worst_limit.unwrap_or(10));
+
+    CacheEfficiencyReport {
+        account_id: query.account_id.clone(),
+        start_time: query.start_time,
+        end_time: query.end_time,
+        pricing,
+        overall: CacheStats::from_group(&total, &pricing),
+        by_model,
+        worst_sessions,
+    }
+}
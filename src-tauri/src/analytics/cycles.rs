@@ -0,0 +1,273 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::aggregate::aggregate_sessions;
+use super::time::{cycle_offset, local_time, month_bucket, shift_months};
+use super::types::{EventSource, GroupBy, UsageGroup};
+use crate::api::UsageSession;
+
+/// 超过该天数的权益周期（如年付）按月重新划分计费周期
+const MAX_CYCLE_DAYS: i64 = 32;
+
+/// 计费周期范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum CycleRange {
+    /// 当前周期
+    Current,
+    /// 上一个周期
+    Previous,
+    /// 最近 N 个周期（包括当前周期）
+    Last { count: u32 },
+}
+
+impl CycleRange {
+    /// (跳过的周期数, 返回的周期数)
+    fn span(&self) -> (u32, u32) {
+        match self {
+            Self::Current => (0, 1),
+            Self::Previous => (1, 1),
+            Self::Last { count } => (0, (*count).max(1)),
+        }
+    }
+}
+
+/// 计费周期
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingCycle {
+    /// 0 为当前周期，1 为上一个周期，以此类推
+    pub index: u32,
+    pub key: String,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// 根据当前权益周期推算计费周期（从新到旧）
+///
+/// `cycle_start`/`cycle_end` 取自基础套餐的 entitlement_base_info。每个周期都是 `cycle_end`
+/// （重置时间）按月偏移得到的，避免月末锚点逐月漂移（如 3/31 → 2/28 → 1/28）。不超过一个月的
+/// 当前周期直接使用接口返回的范围，上一个周期截止到 `cycle_start`；重置时间未知时按自然月划分。
+pub fn resolve_cycles(tz: Tz, cycle_start: i64, cycle_end: i64, now: i64, range: CycleRange) -> Vec<BillingCycle> {
+    let (skip, count) = range.span();
+    let total = (skip + count) as usize;
+
+    let mut bounds = Vec::with_capacity(total);
+    if cycle_end <= 0 {
+        let mut timestamp = now;
+        while bounds.len() < total {
+            let (_, start, end) = month_bucket(tz, timestamp);
+            bounds.push((start, end));
+            timestamp = start - 1;
+        }
+    } else {
+        let monthly = cycle_start > 0 && cycle_end > cycle_start && cycle_end - cycle_start <= MAX_CYCLE_DAYS * 24 * 3600;
+        let current = monthly && now < cycle_end;
+        let offset = if current { -1 } else { cycle_offset(tz, cycle_end, now) };
+        for index in 0..total as i32 {
+            bounds.push((
+                shift_months(tz, cycle_end, offset - index),
+                shift_months(tz, cycle_end, offset - index + 1),
+            ));
+        }
+        if current {
+            bounds[0] = (cycle_start, cycle_end);
+            if let Some(previous) = bounds.get_mut(1) {
+                previous.1 = cycle_start;
+            }
+        }
+    }
+
+    bounds
+        .into_iter()
+        .enumerate()
+        .skip(skip as usize)
+        .map(|(index, (start, end))| BillingCycle {
+            index: index as u32,
+            key: format!(
+                "{} ~ {}",
+                local_time(tz, start).format("%Y-%m-%d"),
+                local_time(tz, end).format("%Y-%m-%d")
+            ),
+            start_time: start,
+            end_time: end,
+        })
+        .collect()
+}
+
+/// 计费周期对比查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleComparisonQuery {
+    pub account_id: String,
+    /// 对比的周期数（包括当前周期），至少为 2
+    #[serde(default = "default_cycle_count")]
+    pub count: u32,
+    /// IANA 时区（如 Asia/Shanghai），为空时使用系统时区
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub source: EventSource,
+}
+
+fn default_cycle_count() -> u32 {
+    2
+}
+
+/// 单个周期的使用情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleUsage {
+    pub cycle: BillingCycle,
+    pub total: UsageGroup,
+    pub by_model: Vec<UsageGroup>,
+    /// 按模式（类别）统计
+    pub by_mode: Vec<UsageGroup>,
+}
+
+impl CycleUsage {
+    pub fn new(cycle: BillingCycle, sessions: &[UsageSession], tz: Tz) -> Self {
+        let (by_model, total) = aggregate_sessions(sessions, GroupBy::Model, tz, cycle.start_time);
+        let (by_mode, _) = aggregate_sessions(sessions, GroupBy::Mode, tz, cycle.start_time);
+        Self { cycle, total, by_model, by_mode }
+    }
+}
+
+/// 当前周期相对上一周期的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleChange {
+    pub key: String,
+    pub current_amount: f64,
+    pub previous_amount: f64,
+    pub amount_change: f64,
+    /// 上一周期没有用量时为空
+    pub amount_change_percent: Option<f64>,
+    pub current_requests: u64,
+    pub previous_requests: u64,
+}
+
+impl CycleChange {
+    fn new(key: &str, current: Option<&UsageGroup>, previous: Option<&UsageGroup>) -> Self {
+        let current_amount = current.map_or(0.0, |g| g.amount);
+        let previous_amount = previous.map_or(0.0, |g| g.amount);
+        Self {
+            key: key.to_string(),
+            current_amount,
+            previous_amount,
+            amount_change: current_amount - previous_amount,
+            amount_change_percent: (previous_amount > 0.0)
+                .then(|| (current_amount - previous_amount) / previous_amount * 100.0),
+            current_requests: current.map_or(0, |g| g.request_count),
+            previous_requests: previous.map_or(0, |g| g.request_count),
+        }
+    }
+
+    /// 按分组对比，结果按变化量绝对值降序
+    fn compare(current: &[UsageGroup], previous: &[UsageGroup]) -> Vec<Self> {
+        let mut keys: BTreeMap<&str, (Option<&UsageGroup>, Option<&UsageGroup>)> = BTreeMap::new();
+        for group in current {
+            keys.entry(group.key.as_str()).or_default().0 = Some(group);
+        }
+        for group in previous {
+            keys.entry(group.key.as_str()).or_default().1 = Some(group);
+        }
+
+        let mut changes: Vec<Self> = keys
+            .into_iter()
+            .map(|(key, (current, previous))| Self::new(key, current, previous))
+            .collect();
+        changes.sort_by(|a, b| b.amount_change.abs().total_cmp(&a.amount_change.abs()));
+        changes
+    }
+}
+
+/// 计费周期对比结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleComparison {
+    pub account_id: String,
+    pub timezone: String,
+    /// 从新到旧
+    pub cycles: Vec<CycleUsage>,
+    /// 当前周期相对上一周期的变化
+    pub total_change: CycleChange,
+    pub model_changes: Vec<CycleChange>,
+    pub mode_changes: Vec<CycleChange>,
+}
+
+impl CycleComparison {
+    pub fn new(account_id: &str, tz: Tz, cycles: Vec<CycleUsage>) -> Self {
+        let current = cycles.first();
+        let previous = cycles.get(1);
+        let by_model = |usage: Option<&CycleUsage>| usage.map(|c| c.by_model.clone()).unwrap_or_default();
+        let by_mode = |usage: Option<&CycleUsage>| usage.map(|c| c.by_mode.clone()).unwrap_or_default();
+
+        Self {
+            account_id: account_id.to_string(),
+            timezone: tz.name().to_string(),
+            total_change: CycleChange::new("total", current.map(|c| &c.total), previous.map(|c| &c.total)),
+            model_changes: CycleChange::compare(&by_model(current), &by_model(previous)),
+            mode_changes: CycleChange::compare(&by_mode(current), &by_mode(previous)),
+            cycles,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32) -> i64 {
+        Tz::UTC.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap().timestamp()
+    }
+
+    fn bounds(cycles: &[BillingCycle]) -> Vec<(i64, i64)> {
+        cycles.iter().map(|c| (c.start_time, c.end_time)).collect()
+    }
+
+    #[test]
+    fn month_end_anchor_does_not_drift() {
+        // 年付周期按重置日（31 日）逐月划分
+        let cycles = resolve_cycles(
+            Tz::UTC,
+            utc(2025, 3, 31),
+            utc(2026, 3, 31),
+            utc(2026, 3, 10),
+            CycleRange::Last { count: 3 },
+        );
+        assert_eq!(
+            bounds(&cycles),
+            vec![
+                (utc(2026, 2, 28), utc(2026, 3, 31)),
+                (utc(2026, 1, 31), utc(2026, 2, 28)),
+                (utc(2025, 12, 31), utc(2026, 1, 31)),
+            ]
+        );
+        assert_eq!(cycles[2].index, 2);
+    }
+
+    #[test]
+    fn short_current_cycle_is_followed_by_contiguous_cycles() {
+        let (start, end) = (utc(2026, 3, 1), utc(2026, 3, 31));
+        let cycles = resolve_cycles(Tz::UTC, start, end, utc(2026, 3, 10), CycleRange::Last { count: 3 });
+        assert_eq!(
+            bounds(&cycles),
+            vec![
+                (start, end),
+                (utc(2026, 1, 31), start),
+                (utc(2025, 12, 31), utc(2026, 1, 31)),
+            ]
+        );
+
+        let previous = resolve_cycles(Tz::UTC, start, end, utc(2026, 3, 10), CycleRange::Previous);
+        assert_eq!(previous.len(), 1);
+        assert_eq!((previous[0].index, previous[0].end_time), (1, start));
+    }
+
+    #[test]
+    fn unknown_reset_time_uses_calendar_months() {
+        let cycles = resolve_cycles(Tz::UTC, 0, 0, utc(2026, 3, 10), CycleRange::Last { count: 2 });
+        assert_eq!(
+            bounds(&cycles),
+            vec![(utc(2026, 3, 1), utc(2026, 4, 1)), (utc(2026, 2, 1), utc(2026, 3, 1))]
+        );
+    }
+}
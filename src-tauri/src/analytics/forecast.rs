@@ -0,0 +1,326 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use super::time::day_bucket;
+use crate::api::{UsageSession, UsageSummary};
+
+const DAY: f64 = 86400.0;
+/// 置信区间对应的 z 值（80%）
+const CONFIDENCE_Z: f64 = 1.2816;
+const CONFIDENCE: f64 = 0.8;
+
+/// 额度类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaCategory {
+    Fast,
+    Slow,
+    AdvancedModel,
+    Autocomplete,
+    ExtraPack,
+}
+
+/// 使用量汇总中单个额度类别的额度
+#[derive(Debug, Clone, Copy)]
+pub struct CategoryQuota {
+    pub category: QuotaCategory,
+    pub used: f64,
+    pub limit: i64,
+    pub left: f64,
+    /// 额度截止时间（基础额度为重置时间，礼包为过期时间）
+    pub deadline: i64,
+}
+
+impl QuotaCategory {
+    /// 使用量汇总中的各类额度
+    pub fn quotas(summary: &UsageSummary) -> [CategoryQuota; 5] {
+        let quota = |category, used, limit, left, deadline| CategoryQuota { category, used, limit, left, deadline };
+        [
+            quota(Self::Fast, summary.fast_request_used, summary.fast_request_limit, summary.fast_request_left, summary.reset_time),
+            quota(Self::Slow, summary.slow_request_used, summary.slow_request_limit, summary.slow_request_left, summary.reset_time),
+            quota(
+                Self::AdvancedModel,
+                summary.advanced_model_used,
+                summary.advanced_model_limit,
+                summary.advanced_model_left,
+                summary.reset_time,
+            ),
+            quota(
+                Self::Autocomplete,
+                summary.autocomplete_used,
+                summary.autocomplete_limit,
+                summary.autocomplete_left,
+                summary.reset_time,
+            ),
+            quota(
+                Self::ExtraPack,
+                summary.extra_fast_request_used,
+                summary.extra_fast_request_limit,
+                summary.extra_fast_request_left,
+                summary.extra_expire_time,
+            ),
+        ]
+    }
+}
+
+/// 单个额度类别的预测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryForecast {
+    pub category: QuotaCategory,
+    pub used: f64,
+    pub limit: i64,
+    pub left: f64,
+    /// 本周期平均每天消耗
+    pub burn_rate_per_day: f64,
+    /// 额度截止时间（基础额度为重置时间，礼包为过期时间）
+    pub deadline: i64,
+    /// 预计耗尽时间，不会耗尽时为 None
+    pub projected_depletion: Option<i64>,
+    /// 置信区间内最早 / 最晚耗尽时间
+    pub earliest_depletion: Option<i64>,
+    pub latest_depletion: Option<i64>,
+    /// 是否预计在截止前耗尽
+    pub depletes_before_deadline: bool,
+}
+
+/// 账号额度消耗预测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurnRateForecast {
+    pub account_id: String,
+    pub generated_at: i64,
+    pub timezone: String,
+    pub cycle_start: i64,
+    pub reset_time: i64,
+    pub elapsed_days: f64,
+    pub event_count: usize,
+    /// 按天统计的使用事件消耗均值与标准差
+    pub daily_amount_mean: f64,
+    pub daily_amount_stddev: f64,
+    /// 置信水平
+    pub confidence: f64,
+    pub categories: Vec<CategoryForecast>,
+}
+
+/// 账号列表中展示的预测摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastBrief {
+    pub generated_at: i64,
+    pub depletes_before_reset: bool,
+    /// 最先耗尽的类别及时间
+    pub first_depleted_category: Option<QuotaCategory>,
+    pub first_depletion: Option<i64>,
+}
+
+impl BurnRateForecast {
+    pub fn brief(&self) -> ForecastBrief {
+        let first = self
+            .categories
+            .iter()
+            .filter(|c| c.depletes_before_deadline)
+            .filter_map(|c| c.projected_depletion.map(|time| (c.category, time)))
+            .min_by_key(|(_, time)| *time);
+
+        ForecastBrief {
+            generated_at: self.generated_at,
+            depletes_before_reset: first.is_some(),
+            first_depleted_category: first.map(|(category, _)| category),
+            first_depletion: first.map(|(_, time)| time),
+        }
+    }
+}
+
+/// 根据当前周期的使用量和使用事件预测各类额度的耗尽时间
+///
+/// 各类别的消耗速率取本周期已用量 / 已过天数（礼包从最早生效的礼包开始计算）；置信区间由
+/// 使用事件按天消耗的变异系数估计，没有使用事件时按 ±100% 的波动处理。
+pub fn forecast_burn_rate(
+    account_id: &str,
+    summary: &UsageSummary,
+    sessions: &[UsageSession],
+    tz: Tz,
+    cycle_start: i64,
+    now: i64,
+) -> BurnRateForecast {
+    let elapsed_days = elapsed_since(cycle_start, now);
+    let (mean, stddev) = daily_amount_stats(sessions, tz, cycle_start, now);
+    let days = elapsed_days.ceil().max(1.0);
+    let cv = if mean > 0.0 { stddev / mean } else { 1.0 };
+    let margin = (CONFIDENCE_Z * cv / days.sqrt()).min(1.0);
+
+    let categories = QuotaCategory::quotas(summary)
+    .into_iter()
+    // 未开放或不限量的类别不做预测
+    .filter(|quota| quota.limit > 0)
+    .map(|CategoryQuota { category, used, limit, left, deadline }| {
+        let rate = match category {
+            QuotaCategory::ExtraPack if summary.extra_start_time > 0 => used / elapsed_since(summary.extra_start_time, now),
+            _ => used / elapsed_days,
+        };
+        let depletion = |rate: f64| -> Option<i64> {
+            if left <= 0.0 {
+                Some(now)
+            } else if rate > 0.0 {
+                Some(now + (left / rate * DAY) as i64)
+            } else {
+                None
+            }
+        };
+        let projected_depletion = depletion(rate);
+
+        CategoryForecast {
+            category,
+            used,
+            limit,
+            left,
+            burn_rate_per_day: rate,
+            deadline,
+            projected_depletion,
+            earliest_depletion: depletion(rate * (1.0 + margin)),
+            latest_depletion: depletion(rate * (1.0 - margin)),
+            depletes_before_deadline: projected_depletion.is_some_and(|time| deadline <= 0 || time < deadline),
+        }
+    })
+    .collect();
+
+    BurnRateForecast {
+        account_id: account_id.to_string(),
+        generated_at: now,
+        timezone: tz.name().to_string(),
+        cycle_start,
+        reset_time: summary.reset_time,
+        elapsed_days,
+        event_count: sessions.len(),
+        daily_amount_mean: mean,
+        daily_amount_stddev: stddev,
+        confidence: CONFIDENCE,
+        categories,
+    }
+}
+
+/// 从 `start` 到现在经过的天数（至少 1 小时，避免刚开始时速率过大）
+fn elapsed_since(start: i64, now: i64) -> f64 {
+    ((now - start) as f64 / DAY).max(1.0 / 24.0)
+}
+
+/// 周期内按天统计的消耗均值与标准差（没有事件的日期计为 0）
+fn daily_amount_stats(sessions: &[UsageSession], tz: Tz, cycle_start: i64, now: i64) -> (f64, f64) {
+    let mut daily: Vec<(i64, f64)> = Vec::new();
+    let mut day_start = cycle_start;
+    while day_start < now {
+        let (_, start, end) = day_bucket(tz, day_start);
+        daily.push((start, 0.0));
+        day_start = end;
+    }
+    if daily.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    for session in sessions {
+        let (_, start, _) = day_bucket(tz, session.usage_time);
+        if let Some(day) = daily.iter_mut().find(|(day, _)| *day == start) {
+            day.1 += session.amount_float;
+        }
+    }
+
+    let n = daily.len() as f64;
+    let mean = daily.iter().map(|(_, amount)| amount).sum::<f64>() / n;
+    let variance = daily.iter().map(|(_, amount)| (amount - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const HOUR: i64 = 3600;
+    const DAYS: i64 = 24 * HOUR;
+
+    fn utc(y: i32, m: u32, d: u32) -> i64 {
+        Tz::UTC.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap().timestamp()
+    }
+
+    fn session(usage_time: i64, amount: f64) -> UsageSession {
+        serde_json::from_value(serde_json::json!({
+            "session_id": format!("s{}", usage_time), "usage_time": usage_time, "mode": "", "model_name": "m",
+            "amount_float": amount, "cost_money_float": 0.0, "use_max_mode": false, "product_type_list": [],
+            "extra_info": { "cache_read_token": 0, "cache_write_token": 0, "input_token": 0, "output_token": 0 },
+        }))
+        .unwrap()
+    }
+
+    /// 只开放快速请求额度的汇总
+    fn summary(cycle_start: i64, reset_time: i64, used: f64, limit: i64) -> UsageSummary {
+        UsageSummary {
+            cycle_start_time: cycle_start,
+            reset_time,
+            fast_request_used: used,
+            fast_request_limit: limit,
+            fast_request_left: limit as f64 - used,
+            slow_request_limit: 0,
+            advanced_model_limit: 0,
+            autocomplete_limit: 0,
+            ..Default::default()
+        }
+    }
+
+    fn category(forecast: &BurnRateForecast, category: QuotaCategory) -> &CategoryForecast {
+        forecast.categories.iter().find(|c| c.category == category).unwrap()
+    }
+
+    #[test]
+    fn rate_is_usage_per_elapsed_day() {
+        // 周期从 12 日开始（与自然月不对齐），已过 10 天
+        let (start, reset) = (utc(2026, 10, 12), utc(2026, 11, 12));
+        let now = start + 10 * DAYS;
+        let forecast = forecast_burn_rate("a", &summary(start, reset, 100.0, 600), &[], Tz::UTC, start, now);
+        assert_eq!(forecast.elapsed_days, 10.0);
+        let fast = category(&forecast, QuotaCategory::Fast);
+        assert_eq!(fast.burn_rate_per_day, 10.0);
+        assert_eq!(fast.projected_depletion, Some(now + 50 * DAYS));
+        assert!(!fast.depletes_before_deadline);
+
+        let forecast = forecast_burn_rate("a", &summary(start, reset, 400.0, 600), &[], Tz::UTC, start, now);
+        let fast = category(&forecast, QuotaCategory::Fast);
+        assert_eq!(fast.projected_depletion, Some(now + 5 * DAYS));
+        assert!(fast.depletes_before_deadline);
+    }
+
+    #[test]
+    fn steady_usage_narrows_the_confidence_band() {
+        let start = utc(2026, 10, 12);
+        let now = start + 10 * DAYS;
+        let steady: Vec<UsageSession> = (0..10).map(|d| session(start + d * DAYS + HOUR, 20.0)).collect();
+        let bursty = vec![session(start + HOUR, 200.0)];
+        let quota = summary(start, utc(2026, 11, 12), 200.0, 600);
+
+        let steady = forecast_burn_rate("a", &quota, &steady, Tz::UTC, start, now);
+        assert_eq!((steady.daily_amount_mean, steady.daily_amount_stddev), (20.0, 0.0));
+        let fast = category(&steady, QuotaCategory::Fast);
+        assert_eq!(fast.earliest_depletion, fast.latest_depletion);
+
+        let bursty = forecast_burn_rate("a", &quota, &bursty, Tz::UTC, start, now);
+        let fast = category(&bursty, QuotaCategory::Fast);
+        let projected = fast.projected_depletion.unwrap();
+        assert!(fast.earliest_depletion.unwrap() < projected);
+        // 波动超过 100% 时最慢速率为 0，不会耗尽
+        assert_eq!(fast.latest_depletion, None);
+    }
+
+    #[test]
+    fn extra_pack_rate_uses_its_own_start() {
+        let start = utc(2026, 10, 12);
+        let now = start + 10 * DAYS;
+        let mut quota = summary(start, utc(2026, 11, 12), 0.0, 600);
+        quota.extra_fast_request_used = 100.0;
+        quota.extra_fast_request_limit = 300;
+        quota.extra_fast_request_left = 200.0;
+        quota.extra_start_time = now - 2 * DAYS;
+        quota.extra_expire_time = now + 30 * DAYS;
+
+        let forecast = forecast_burn_rate("a", &quota, &[], Tz::UTC, start, now);
+        let extra = category(&forecast, QuotaCategory::ExtraPack);
+        assert_eq!(extra.burn_rate_per_day, 50.0);
+        assert_eq!(extra.projected_depletion, Some(now + 4 * DAYS));
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{Datelike, Timelike};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use super::time::local_time;
+use super::types::EventSource;
+use crate::api::UsageSession;
+
+/// 活跃度热力图查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapQuery {
+    /// 为空时统计全部账号
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// IANA 时区（如 Asia/Shanghai），为空时使用系统时区
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub source: EventSource,
+}
+
+/// 无法统计的账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapFailure {
+    pub account_id: String,
+    pub error: String,
+}
+
+/// 星期 × 小时的活跃度矩阵
+///
+/// 矩阵的行是星期（0 为周一），列是小时（0-23）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityHeatmap {
+    pub account_ids: Vec<String>,
+    pub timezone: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub requests: Vec<Vec<u64>>,
+    /// Token 总数（含缓存）
+    pub tokens: Vec<Vec<i64>>,
+    pub amount: Vec<Vec<f64>>,
+    pub max_requests: u64,
+    pub max_tokens: i64,
+    pub total_requests: u64,
+    pub total_tokens: i64,
+    pub failures: Vec<HeatmapFailure>,
+}
+
+impl ActivityHeatmap {
+    pub fn new(account_ids: Vec<String>, tz: Tz, start_time: i64, end_time: i64) -> Self {
+        Self {
+            account_ids,
+            timezone: tz.name().to_string(),
+            start_time,
+            end_time,
+            requests: vec![vec![0; 24]; 7],
+            tokens: vec![vec![0; 24]; 7],
+            amount: vec![vec![0.0; 24]; 7],
+            max_requests: 0,
+            max_tokens: 0,
+            total_requests: 0,
+            total_tokens: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    /// 计入使用事件
+    pub fn add(&mut self, tz: Tz, sessions: &[UsageSession]) {
+        for session in sessions {
+            let time = local_time(tz, session.usage_time);
+            let weekday = time.weekday().num_days_from_monday() as usize;
+            let hour = time.hour() as usize;
+            let info = &session.extra_info;
+            let tokens = info.input_token + info.output_token + info.cache_read_token + info.cache_write_token;
+
+            self.requests[weekday][hour] += 1;
+            self.tokens[weekday][hour] += tokens;
+            self.amount[weekday][hour] += session.amount_float;
+            self.max_requests = self.max_requests.max(self.requests[weekday][hour]);
+            self.max_tokens = self.max_tokens.max(self.tokens[weekday][hour]);
+            self.total_requests += 1;
+            self.total_tokens += tokens;
+        }
+    }
+}
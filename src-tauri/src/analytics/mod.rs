@@ -0,0 +1,16 @@
+pub mod aggregate;
+pub mod cache;
+pub mod cycles;
+pub mod forecast;
+pub mod heatmap;
+pub mod pricing;
+pub mod time;
+pub mod types;
+
+pub use aggregate::aggregate_sessions;
+pub use cache::{cache_report, CacheEfficiencyReport, CacheReportQuery};
+pub use cycles::{BillingCycle, CycleComparison, CycleComparisonQuery, CycleRange, CycleUsage};
+pub use forecast::{forecast_burn_rate, BurnRateForecast, ForecastBrief};
+pub use heatmap::{ActivityHeatmap, HeatmapFailure, HeatmapQuery};
+pub use pricing::{estimate_costs, CostEstimateQuery, CostEstimateReport, PriceTable, PriceTableView};
+pub use types::*;
@@ -0,0 +1,428 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::types::EventSource;
+use crate::api::UsageSession;
+
+/// 内置的模型价格表
+const BUNDLED_PRICE_TABLE: &str = include_str!("../../resources/price_table.json");
+
+static BUNDLED: Lazy<PriceTable> = Lazy::new(|| {
+    serde_json::from_str(BUNDLED_PRICE_TABLE).expect("内置价格表格式错误")
+});
+
+const MILLION: f64 = 1_000_000.0;
+/// 导入价格表文件的大小上限
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// 单个模型的价格（每百万 token）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPrice {
+    /// 模型名称，支持 * 通配符，不区分大小写
+    pub model: String,
+    /// 为空表示匹配任意模式
+    pub mode: Option<String>,
+    /// 为空表示匹配是否 Max 模式
+    pub max_mode: Option<bool>,
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+}
+
+impl ModelPrice {
+    /// 是否匹配，匹配时返回具体程度（模型名中的非通配字符越多、指定的条件越多越具体）
+    fn matches(&self, model_name: &str, mode: &str, max_mode: bool) -> Option<(usize, usize)> {
+        if !wildcard_match(&self.model.to_lowercase(), &model_name.to_lowercase()) {
+            return None;
+        }
+        if self.mode.as_deref().is_some_and(|m| !m.eq_ignore_ascii_case(mode)) {
+            return None;
+        }
+        if self.max_mode.is_some_and(|m| m != max_mode) {
+            return None;
+        }
+
+        let literal = self.model.chars().filter(|c| *c != '*').count();
+        let conditions = self.mode.is_some() as usize + self.max_mode.is_some() as usize;
+        Some((literal, conditions))
+    }
+}
+
+/// 简单通配符匹配（* 匹配任意字符串）
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// 模型价格表（内置价格 + 用户导入的价格）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceTable {
+    pub currency: String,
+    /// 价格来源说明
+    pub source: String,
+    /// 价格整理日期（YYYY-MM-DD），之后厂商调价不会自动更新
+    pub updated_at: String,
+    pub prices: Vec<ModelPrice>,
+}
+
+impl PriceTable {
+    /// 内置价格表
+    pub fn bundled() -> &'static PriceTable {
+        &BUNDLED
+    }
+
+    /// 加载价格表：用户价格表中的条目优先于内置条目
+    pub fn load(override_path: &Path) -> Self {
+        let mut table = Self::bundled().clone();
+        if !override_path.exists() {
+            return table;
+        }
+
+        match Self::read_file(override_path) {
+            Ok(overrides) => {
+                if !overrides.currency.is_empty() {
+                    table.currency = overrides.currency;
+                }
+                if !overrides.source.is_empty() {
+                    table.source = overrides.source;
+                }
+                if !overrides.updated_at.is_empty() {
+                    table.updated_at = overrides.updated_at;
+                }
+                let mut prices = overrides.prices;
+                prices.append(&mut table.prices);
+                table.prices = prices;
+            }
+            Err(e) => println!("[WARN] 用户价格表无效，使用内置价格表: {}", e),
+        }
+        table
+    }
+
+    /// 读取并校验价格表文件（只接受不超过 `MAX_FILE_SIZE` 的 .json 文件）
+    pub fn read_file(path: &Path) -> Result<Self> {
+        if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            return Err(anyhow!("价格表必须是 .json 文件"));
+        }
+        let metadata = fs::metadata(path).map_err(|e| anyhow!("读取价格表失败: {}", e))?;
+        if !metadata.is_file() {
+            return Err(anyhow!("价格表路径不是文件"));
+        }
+        if metadata.len() > MAX_FILE_SIZE {
+            return Err(anyhow!("价格表文件过大（超过 {} KB）", MAX_FILE_SIZE / 1024));
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| anyhow!("读取价格表失败: {}", e))?;
+        let table: Self = serde_json::from_str(&content).map_err(|e| anyhow!("解析价格表失败: {}", e))?;
+        for price in &table.prices {
+            if price.model.trim().is_empty() {
+                return Err(anyhow!("价格表中存在未填写模型名称的条目"));
+            }
+            if [price.input, price.output, price.cache_read, price.cache_write].iter().any(|p| *p < 0.0) {
+                return Err(anyhow!("模型 {} 的价格不能为负数", price.model));
+            }
+        }
+        Ok(table)
+    }
+
+    /// 查找会话适用的价格（最具体的匹配优先，同等具体时靠前的优先）
+    pub fn find(&self, model_name: &str, mode: &str, max_mode: bool) -> Option<&ModelPrice> {
+        let mut best: Option<((usize, usize), &ModelPrice)> = None;
+        for price in &self.prices {
+            if let Some(specificity) = price.matches(model_name, mode, max_mode) {
+                if best.is_none_or(|(current, _)| specificity > current) {
+                    best = Some((specificity, price));
+                }
+            }
+        }
+        best.map(|(_, price)| price)
+    }
+
+    /// 估算单个会话的费用，没有适用价格时返回 None
+    pub fn estimate(&self, session: &UsageSession) -> Option<CostBreakdown> {
+        let price = self.find(&session.model_name, &session.mode, session.use_max_mode)?;
+        let info = &session.extra_info;
+        let input = info.input_token as f64 * price.input / MILLION;
+        let output = info.output_token as f64 * price.output / MILLION;
+        let cache_read = info.cache_read_token as f64 * price.cache_read / MILLION;
+        let cache_write = info.cache_write_token as f64 * price.cache_write / MILLION;
+
+        Some(CostBreakdown {
+            input,
+            output,
+            cache_read,
+            cache_write,
+            total: input + output + cache_read + cache_write,
+        })
+    }
+}
+
+/// 价格表及用户价格表路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceTableView {
+    pub override_path: String,
+    pub table: PriceTable,
+}
+
+/// 费用明细
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+    pub total: f64,
+}
+
+impl CostBreakdown {
+    pub fn add(&mut self, other: &CostBreakdown) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_read += other.cache_read;
+        self.cache_write += other.cache_write;
+        self.total += other.total;
+    }
+}
+
+/// 费用估算查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimateQuery {
+    pub account_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    #[serde(default)]
+    pub source: EventSource,
+}
+
+/// 单个模型的费用估算
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCostEstimate {
+    pub model_name: String,
+    pub request_count: u64,
+    pub amount: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    /// 是否找到了适用的价格
+    pub priced: bool,
+    pub cost: CostBreakdown,
+}
+
+/// 费用估算报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimateReport {
+    pub account_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub currency: String,
+    pub total: CostBreakdown,
+    /// 按估算费用降序
+    pub by_model: Vec<ModelCostEstimate>,
+    /// 没有适用价格的会话数
+    pub unpriced_sessions: usize,
+}
+
+/// 按价格表估算使用事件的费用
+pub fn estimate_costs(sessions: &[UsageSession], table: &PriceTable, query: &CostEstimateQuery) -> CostEstimateReport {
+    let mut models: HashMap<&str, ModelCostEstimate> = HashMap::new();
+    let mut total = CostBreakdown::default();
+    let mut unpriced_sessions = 0;
+
+    for session in sessions {
+        let model = models.entry(session.model_name.as_str()).or_insert_with(|| ModelCostEstimate {
+            model_name: session.model_name.clone(),
+            ..Default::default()
+        });
+        model.request_count += 1;
+        model.amount += session.amount_float;
+        model.input_tokens += session.extra_info.input_token;
+        model.output_tokens += session.extra_info.output_token;
+        model.cache_read_tokens += session.extra_info.cache_read_token;
+        model.cache_write_tokens += session.extra_info.cache_write_token;
+
+        match table.estimate(session) {
+            Some(cost) => {
+                model.priced = true;
+                model.cost.add(&cost);
+                total.add(&cost);
+            }
+            None => unpriced_sessions += 1,
+        }
+    }
+
+    let mut by_model: Vec<ModelCostEstimate> = models.into_values().collect();
+    by_model.sort_by(|a, b| b.cost.total.total_cmp(&a.cost.total).then_with(|| a.model_name.cmp(&b.model_name)));
+
+    CostEstimateReport {
+        account_id: query.account_id.clone(),
+        start_time: query.start_time,
+        end_time: query.end_time,
+        currency: table.currency.clone(),
+        total,
+        by_model,
+        unpriced_sessions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(model: &str, mode: &str, max_mode: bool, input: i64, output: i64) -> UsageSession {
+        serde_json::from_value(serde_json::json!({
+            "session_id": "s", "usage_time": 0, "mode": mode, "model_name": model, "amount_float": 1.0,
+            "cost_money_float": 0.0, "use_max_mode": max_mode, "product_type_list": [],
+            "extra_info": { "cache_read_token": 0, "cache_write_token": 0, "input_token": input, "output_token": output },
+        }))
+        .unwrap()
+    }
+
+    fn price(model: &str, mode: Option<&str>, max_mode: Option<bool>, input: f64) -> ModelPrice {
+        ModelPrice {
+            model: model.to_string(),
+            mode: mode.map(str::to_string),
+            max_mode,
+            input,
+            output: input * 2.0,
+            ..Default::default()
+        }
+    }
+
+    fn table(prices: Vec<ModelPrice>) -> PriceTable {
+        PriceTable {
+            currency: "USD".to_string(),
+            prices,
+            ..Default::default()
+        }
+    }
+
+    fn write_file(name: &str, content: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("trae-prices-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn wildcard_match_handles_prefix_suffix_and_infix() {
+        assert!(wildcard_match("gpt-4o", "gpt-4o"));
+        assert!(!wildcard_match("gpt-4o", "gpt-4o-mini"));
+        assert!(wildcard_match("gpt-4o*", "gpt-4o-mini"));
+        assert!(wildcard_match("*sonnet*", "claude-4-sonnet"));
+        assert!(wildcard_match("*sonnet*", "sonnet"));
+        assert!(wildcard_match("claude-*-sonnet", "claude-4-sonnet"));
+        assert!(!wildcard_match("claude-*-sonnet", "claude-4-sonnet-max"));
+        assert!(wildcard_match("*", ""));
+        // 前后缀不能重叠使用同一段字符
+        assert!(!wildcard_match("abc*bcd", "abcd"));
+        assert!(!wildcard_match("a*b*c", "acb"));
+    }
+
+    #[test]
+    fn find_prefers_the_most_specific_price() {
+        let table = table(vec![
+            price("*", None, None, 1.0),
+            price("*sonnet*", None, None, 2.0),
+            price("claude-4-sonnet", None, None, 3.0),
+            price("claude-4-sonnet", Some("builder"), None, 4.0),
+            price("claude-4-sonnet", Some("builder"), Some(true), 5.0),
+            price("claude-4-sonnet", None, None, 6.0),
+        ]);
+        let input = |model: &str, mode: &str, max_mode: bool| table.find(model, mode, max_mode).unwrap().input;
+
+        assert_eq!(input("gpt-5", "chat", false), 1.0);
+        assert_eq!(input("claude-3.7-sonnet", "chat", false), 2.0);
+        // 同等具体时靠前的优先
+        assert_eq!(input("claude-4-sonnet", "chat", false), 3.0);
+        // 模型名、模式不区分大小写
+        assert_eq!(input("Claude-4-Sonnet", "Builder", false), 4.0);
+        assert_eq!(input("claude-4-sonnet", "builder", true), 5.0);
+        assert!(PriceTable::default().find("claude-4-sonnet", "", false).is_none());
+    }
+
+    #[test]
+    fn read_file_validates_the_table() {
+        let valid = write_file("prices.json", r#"{ "prices": [{ "model": "x*", "input": 1.0 }] }"#);
+        assert_eq!(PriceTable::read_file(&valid).unwrap().prices.len(), 1);
+
+        let cases = [
+            write_file("prices.txt", r#"{ "prices": [] }"#),
+            write_file("prices.json", "not json"),
+            write_file("prices.json", r#"{ "prices": [{ "model": " ", "input": 1.0 }] }"#),
+            write_file("prices.json", r#"{ "prices": [{ "model": "x", "output": -1.0 }] }"#),
+            write_file("prices.json", &" ".repeat(MAX_FILE_SIZE as usize + 1)),
+        ];
+        for path in &cases {
+            assert!(PriceTable::read_file(path).is_err(), "{:?}", path);
+        }
+        // 目录和不存在的文件
+        assert!(PriceTable::read_file(valid.parent().unwrap()).is_err());
+        assert!(PriceTable::read_file(&valid.with_file_name("missing.json")).is_err());
+
+        for path in cases.iter().chain([&valid]) {
+            fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn estimate_costs_groups_by_model() {
+        let table = table(vec![price("model-a", None, None, 2.0)]);
+        let sessions = vec![
+            session("model-a", "chat", false, 1_000_000, 500_000),
+            session("model-a", "chat", false, 500_000, 0),
+            session("model-b", "chat", false, 1_000, 1_000),
+        ];
+        let query = CostEstimateQuery {
+            account_id: "acc".to_string(),
+            start_time: 0,
+            end_time: 100,
+            source: EventSource::default(),
+        };
+
+        let report = estimate_costs(&sessions, &table, &query);
+        assert_eq!(report.currency, "USD");
+        assert_eq!(report.unpriced_sessions, 1);
+        // 输入 1.5M × 2 + 输出 0.5M × 4
+        assert!((report.total.input - 3.0).abs() < 1e-9);
+        assert!((report.total.output - 2.0).abs() < 1e-9);
+        assert!((report.total.total - 5.0).abs() < 1e-9);
+
+        assert_eq!(report.by_model.len(), 2);
+        let (a, b) = (&report.by_model[0], &report.by_model[1]);
+        assert_eq!((a.model_name.as_str(), a.request_count, a.priced), ("model-a", 2, true));
+        assert_eq!((a.input_tokens, a.output_tokens), (1_500_000, 500_000));
+        assert_eq!((b.model_name.as_str(), b.priced), ("model-b", false));
+        assert_eq!(b.cost.total, 0.0);
+    }
+
+    #[test]
+    fn bundled_table_parses_and_prices_known_models() {
+        let table = PriceTable::bundled();
+        assert_eq!(table.currency, "USD");
+        assert_eq!(table.find("claude-opus-4.5", "", false).unwrap().input, 5.0);
+        assert_eq!(table.find("claude-haiku-4.5", "", false).unwrap().output, 5.0);
+    }
+}
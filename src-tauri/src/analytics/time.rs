@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, TimeZone, Timelike};
+use chrono_tz::Tz;
+
+/// 解析 IANA 时区，为空时使用系统时区（无法识别时回退到 UTC）
+pub fn resolve_timezone(timezone: Option<&str>) -> Result<Tz> {
+    match timezone.map(str::trim).filter(|tz| !tz.is_empty()) {
+        Some(name) => name.parse().map_err(|_| anyhow!("无效的时区: {}", name)),
+        None => Ok(iana_time_zone::get_timezone()
+            .ok()
+            .and_then(|name| name.parse().ok())
+            .unwrap_or(Tz::UTC)),
+    }
+}
+
+/// 时间戳转换为时区内的时间
+pub fn local_time(tz: Tz, timestamp: i64) -> DateTime<Tz> {
+    let utc = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+    tz.from_utc_datetime(&utc.naive_utc())
+}
+
+/// 某天 0 点的时间戳（夏令时切换导致不存在时取最早的有效时间）
+fn start_of_day(tz: Tz, date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.timestamp())
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight).timestamp())
+}
+
+/// 时间所在的自然日，返回 (标识, 开始, 结束)
+pub fn day_bucket(tz: Tz, timestamp: i64) -> (String, i64, i64) {
+    let date = local_time(tz, timestamp).date_naive();
+    let next = date + Duration::days(1);
+    (date.format("%Y-%m-%d").to_string(), start_of_day(tz, date), start_of_day(tz, next))
+}
+
+/// 时间所在的整点小时，返回 (标识, 开始, 结束)
+pub fn hour_bucket(tz: Tz, timestamp: i64) -> (String, i64, i64) {
+    let time = local_time(tz, timestamp);
+    let start = timestamp - (time.minute() * 60 + time.second()) as i64;
+    (time.format("%Y-%m-%d %H:00").to_string(), start, start + 3600)
+}
+
+/// 时间所在的 ISO 周（周一开始），返回 (标识, 开始, 结束)
+pub fn week_bucket(tz: Tz, timestamp: i64) -> (String, i64, i64) {
+    let date = local_time(tz, timestamp).date_naive();
+    let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+    let week = date.iso_week();
+    (
+        format!("{}-W{:02}", week.year(), week.week()),
+        start_of_day(tz, monday),
+        start_of_day(tz, monday + Duration::days(7)),
+    )
+}
+
+/// 以 `anchor`（某次重置时间）为锚点按月划分计费周期，返回时间所在周期 (标识, 开始, 结束)
+///
+/// 锚点无效（不大于 0）时按自然月划分。
+pub fn billing_cycle(tz: Tz, anchor: i64, timestamp: i64) -> (String, i64, i64) {
+    if anchor <= 0 {
+        return month_bucket(tz, timestamp);
+    }
+
+    let months = cycle_offset(tz, anchor, timestamp);
+    let (start, end) = (shift_months(tz, anchor, months), shift_months(tz, anchor, months + 1));
+    let key = format!(
+        "{} ~ {}",
+        local_time(tz, start).format("%Y-%m-%d"),
+        local_time(tz, end).format("%Y-%m-%d")
+    );
+    (key, start, end)
+}
+
+/// 锚点前后相差 `months` 个月的时间（月末锚点在较短的月份取该月最后一天）
+pub fn shift_months(tz: Tz, anchor: i64, months: i32) -> i64 {
+    let anchor = local_time(tz, anchor);
+    let shifted = if months >= 0 {
+        anchor.checked_add_months(Months::new(months as u32))
+    } else {
+        anchor.checked_sub_months(Months::new(months.unsigned_abs()))
+    };
+    shifted.map(|t| t.timestamp()).unwrap_or(anchor.timestamp())
+}
+
+/// 时间所在计费周期的开始时间相对锚点的月数
+pub fn cycle_offset(tz: Tz, anchor: i64, timestamp: i64) -> i32 {
+    let (from, target) = (local_time(tz, anchor), local_time(tz, timestamp));
+    // 按年月差直接算出偏移，再根据日期和时刻修正（最多各调整一次）
+    let mut months = (target.year() - from.year()) * 12 + target.month() as i32 - from.month() as i32;
+    if shift_months(tz, anchor, months) > timestamp {
+        months -= 1;
+    }
+    if shift_months(tz, anchor, months + 1) <= timestamp {
+        months += 1;
+    }
+    months
+}
+
+/// 时间所在的自然月，返回 (标识, 开始, 结束)
+pub fn month_bucket(tz: Tz, timestamp: i64) -> (String, i64, i64) {
+    let date = local_time(tz, timestamp).date_naive();
+    let first = date.with_day(1).unwrap_or(date);
+    let next = first.checked_add_months(Months::new(1)).unwrap_or(first);
+    (first.format("%Y-%m").to_string(), start_of_day(tz, first), start_of_day(tz, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32) -> i64 {
+        Tz::UTC.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap().timestamp()
+    }
+
+    #[test]
+    fn billing_cycle_is_anchored_on_reset_day() {
+        let anchor = utc(2020, 1, 15);
+        let (_, start, end) = billing_cycle(Tz::UTC, anchor, utc(2026, 3, 20));
+        assert_eq!((start, end), (utc(2026, 3, 15), utc(2026, 4, 15)));
+
+        let (_, start, end) = billing_cycle(Tz::UTC, anchor, utc(2026, 3, 15) - 1);
+        assert_eq!((start, end), (utc(2026, 2, 15), utc(2026, 3, 15)));
+
+        // 锚点晚于查询时间
+        let (_, start, end) = billing_cycle(Tz::UTC, utc(2030, 6, 15), utc(2026, 3, 14));
+        assert_eq!((start, end), (utc(2026, 2, 15), utc(2026, 3, 15)));
+    }
+
+    #[test]
+    fn billing_cycle_without_anchor_uses_calendar_month() {
+        let (key, start, end) = billing_cycle(Tz::UTC, 0, utc(2026, 3, 20));
+        assert_eq!(key, "2026-03");
+        assert_eq!((start, end), (utc(2026, 3, 1), utc(2026, 4, 1)));
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// 使用事件分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    Model,
+    Mode,
+    MaxMode,
+    Day,
+    Week,
+    /// 按计费周期（以账号的重置时间为锚点，每月一个周期）
+    BillingCycle,
+}
+
+impl GroupBy {
+    /// 是否按时间分组
+    pub fn is_time_based(&self) -> bool {
+        matches!(self, Self::Day | Self::Week | Self::BillingCycle)
+    }
+}
+
+/// 使用事件来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSource {
+    /// 本地已同步的事件
+    #[default]
+    Stored,
+    /// 实时从接口拉取
+    Fetched,
+}
+
+/// 使用分析查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageAnalyticsQuery {
+    pub account_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub group_by: GroupBy,
+    /// IANA 时区（如 Asia/Shanghai），为空时使用系统时区
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub source: EventSource,
+}
+
+/// 一组使用事件的统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageGroup {
+    pub key: String,
+    /// 时间分组的起止时间（秒），非时间分组为 None
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub request_count: u64,
+    pub amount: f64,
+    pub cost: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+}
+
+/// 使用分析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageAnalytics {
+    pub account_id: String,
+    pub group_by: GroupBy,
+    pub timezone: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub groups: Vec<UsageGroup>,
+    pub total: UsageGroup,
+}
@@ -0,0 +1,386 @@
+use anyhow::{anyhow, Result};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::types::*;
+use crate::account::Account;
+use crate::analytics::time;
+use crate::api::UsageSession;
+
+/// 异常数据文件名
+const ANOMALIES_FILE: &str = "anomalies.json";
+/// 每条异常最多记录的会话数
+const MAX_SESSION_IDS: usize = 20;
+
+/// 异常提醒方式（如系统通知）
+pub trait AnomalyNotifier: Send + Sync {
+    fn notify_anomalies(&self, anomalies: &[UsageAnomaly]);
+}
+
+/// 持久化的异常数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AnomalyStore {
+    settings: AnomalySettings,
+    anomalies: Vec<UsageAnomaly>,
+}
+
+/// 用量异常检测：保存检测设置和检测到的异常，并在发现新异常时提醒
+pub struct AnomalyDetector {
+    store: Mutex<AnomalyStore>,
+    path: PathBuf,
+    notifier: Mutex<Option<Arc<dyn AnomalyNotifier>>>,
+}
+
+impl AnomalyDetector {
+    /// 打开数据目录下的异常数据
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(ANOMALIES_FILE);
+        let store = if path.exists() {
+            fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    println!("[WARN] 异常数据文件无效，已忽略: {}", e);
+                    AnomalyStore::default()
+                })
+        } else {
+            AnomalyStore::default()
+        };
+
+        Self {
+            store: Mutex::new(store),
+            path,
+            notifier: Mutex::new(None),
+        }
+    }
+
+    /// 设置提醒方式
+    pub fn set_notifier(&self, notifier: Arc<dyn AnomalyNotifier>) {
+        if let Ok(mut current) = self.notifier.lock() {
+            *current = Some(notifier);
+        }
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, AnomalyStore>> {
+        self.store.lock().map_err(|_| anyhow!("异常数据不可用"))
+    }
+
+    fn save(&self, store: &AnomalyStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(store)?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// 获取检测设置
+    pub fn settings(&self) -> Result<AnomalySettings> {
+        Ok(self.store()?.settings.clone())
+    }
+
+    /// 更新检测设置
+    pub fn update_settings(&self, settings: AnomalySettings) -> Result<AnomalySettings> {
+        if settings.baseline_days == 0 || settings.detection_hours == 0 {
+            return Err(anyhow!("基线天数和检测时长必须大于 0"));
+        }
+        if settings.detection_hours >= settings.baseline_days * 24 {
+            return Err(anyhow!("检测时长必须小于基线天数"));
+        }
+        if settings.z_threshold <= 0.0 || settings.min_ratio <= 1.0 {
+            return Err(anyhow!("标准差倍数必须大于 0，均值倍数必须大于 1"));
+        }
+
+        let mut store = self.store()?;
+        store.settings = settings.clone();
+        self.save(&store)?;
+        Ok(settings)
+    }
+
+    /// 保存账号的检测结果，返回其中新发现的异常
+    ///
+    /// 已记录的异常只更新用量数据，不重复提醒。
+    pub fn record(&self, anomalies: Vec<UsageAnomaly>) -> Result<Vec<UsageAnomaly>> {
+        let mut store = self.store()?;
+        let mut raised = Vec::new();
+        for anomaly in anomalies {
+            match store.anomalies.iter_mut().find(|a| a.id == anomaly.id) {
+                Some(existing) => {
+                    let (detected_at, acknowledged) = (existing.detected_at, existing.acknowledged);
+                    *existing = UsageAnomaly { detected_at, acknowledged, ..anomaly };
+                }
+                None => {
+                    raised.push(anomaly.clone());
+                    store.anomalies.push(anomaly);
+                }
+            }
+        }
+
+        let limit = store.settings.history_limit;
+        if store.anomalies.len() > limit {
+            store.anomalies.sort_by_key(|a| std::cmp::Reverse(a.bucket_start));
+            store.anomalies.truncate(limit);
+        }
+        let notify = store.settings.native_notifications;
+        self.save(&store)?;
+        drop(store);
+
+        if notify && !raised.is_empty() {
+            if let Some(notifier) = self.notifier.lock().ok().and_then(|n| n.clone()) {
+                notifier.notify_anomalies(&raised);
+            }
+        }
+        Ok(raised)
+    }
+
+    /// 获取异常记录（按时段倒序）
+    pub fn list(&self, account_id: Option<&str>, include_acknowledged: bool) -> Result<Vec<UsageAnomaly>> {
+        let store = self.store()?;
+        let mut anomalies: Vec<UsageAnomaly> = store
+            .anomalies
+            .iter()
+            .filter(|a| account_id.is_none_or(|id| a.account_id == id))
+            .filter(|a| include_acknowledged || !a.acknowledged)
+            .cloned()
+            .collect();
+        anomalies.sort_by_key(|a| std::cmp::Reverse(a.bucket_start));
+        Ok(anomalies)
+    }
+
+    /// 标记异常为已确认
+    pub fn acknowledge(&self, anomaly_id: &str) -> Result<UsageAnomaly> {
+        let mut store = self.store()?;
+        let anomaly = store
+            .anomalies
+            .iter_mut()
+            .find(|a| a.id == anomaly_id)
+            .ok_or_else(|| anyhow!("异常记录不存在"))?;
+        anomaly.acknowledged = true;
+        let anomaly = anomaly.clone();
+        self.save(&store)?;
+        Ok(anomaly)
+    }
+
+    /// 删除账号的全部异常记录
+    pub fn delete_account(&self, account_id: &str) -> Result<()> {
+        let mut store = self.store()?;
+        store.anomalies.retain(|a| a.account_id != account_id);
+        self.save(&store)
+    }
+}
+
+/// 单个时段的用量
+#[derive(Default)]
+struct Bucket<'a> {
+    key: String,
+    end: i64,
+    value: f64,
+    request_count: u64,
+    sessions: Vec<&'a UsageSession>,
+}
+
+fn metric_value(metric: AnomalyMetric, session: &UsageSession) -> f64 {
+    match metric {
+        AnomalyMetric::Amount => session.amount_float,
+        AnomalyMetric::Requests => 1.0,
+        AnomalyMetric::Tokens => {
+            let info = &session.extra_info;
+            (info.input_token + info.output_token + info.cache_read_token + info.cache_write_token) as f64
+        }
+    }
+}
+
+fn bucket_of(granularity: AnomalyGranularity, tz: Tz, timestamp: i64) -> (String, i64, i64) {
+    match granularity {
+        AnomalyGranularity::Hour => time::hour_bucket(tz, timestamp),
+        AnomalyGranularity::Day => time::day_bucket(tz, timestamp),
+    }
+}
+
+/// 以账号自身的历史用量为基线检测异常
+///
+/// `sessions` 应覆盖最近 `baseline_days` 天。检测时段为最近 `detection_hours` 小时内的时段，
+/// 基线为此前的全部时段（没有用量的时段计为 0）。用量同时超过基线均值的 `min_ratio` 倍和
+/// `z_threshold` 个标准差时视为异常。
+pub fn detect_anomalies(
+    account: &Account,
+    sessions: &[UsageSession],
+    settings: &AnomalySettings,
+    tz: Tz,
+    now: i64,
+) -> Vec<UsageAnomaly> {
+    let granularity = settings.granularity;
+    let detection_start = bucket_of(granularity, tz, now - settings.detection_hours as i64 * 3600).1;
+    let baseline_start = bucket_of(granularity, tz, now - settings.baseline_days as i64 * 24 * 3600).1;
+
+    // 基线时段（包括没有用量的时段）
+    let mut baseline_buckets = Vec::new();
+    let mut cursor = baseline_start;
+    while cursor < detection_start {
+        let (_, start, end) = bucket_of(granularity, tz, cursor);
+        baseline_buckets.push(start);
+        cursor = end;
+    }
+
+    // None 表示账号全部模型
+    let mut scopes: HashMap<Option<&str>, BTreeMap<i64, Bucket>> = HashMap::new();
+    for session in sessions.iter().filter(|s| s.usage_time >= baseline_start && s.usage_time <= now) {
+        let (key, start, end) = bucket_of(granularity, tz, session.usage_time);
+        let mut targets = vec![None];
+        if settings.per_model {
+            targets.push(Some(session.model_name.as_str()));
+        }
+        for target in targets {
+            let bucket = scopes.entry(target).or_default().entry(start).or_insert_with(|| Bucket {
+                key: key.clone(),
+                end,
+                ..Default::default()
+            });
+            bucket.value += metric_value(settings.metric, session);
+            bucket.request_count += 1;
+            bucket.sessions.push(session);
+        }
+    }
+
+    let mut anomalies = Vec::new();
+    for (model_name, buckets) in &scopes {
+        let values: Vec<f64> = baseline_buckets
+            .iter()
+            .map(|start| buckets.get(start).map_or(0.0, |b| b.value))
+            .collect();
+        if values.iter().filter(|v| **v > 0.0).count() < settings.min_active_buckets.max(1) {
+            // 基线尚未建立
+            continue;
+        }
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt();
+        // 基线完全平稳时避免除以 0
+        let spread = std.max(mean * 0.1);
+
+        for (start, bucket) in buckets.range(detection_start..) {
+            let ratio = bucket.value / mean;
+            let z_score = (bucket.value - mean) / spread;
+            if bucket.value < settings.min_value || ratio < settings.min_ratio || z_score < settings.z_threshold {
+                continue;
+            }
+
+            let mut top = bucket.sessions.clone();
+            top.sort_by(|a, b| metric_value(settings.metric, b).total_cmp(&metric_value(settings.metric, a)));
+            let mut session_ids: Vec<String> = Vec::new();
+            for session in top {
+                if session_ids.len() >= MAX_SESSION_IDS {
+                    break;
+                }
+                if !session_ids.contains(&session.session_id) {
+                    session_ids.push(session.session_id.clone());
+                }
+            }
+
+            anomalies.push(UsageAnomaly {
+                id: format!(
+                    "{}:{}:{:?}:{}",
+                    account.id,
+                    model_name.unwrap_or("*"),
+                    granularity,
+                    start
+                ),
+                account_id: account.id.clone(),
+                account_name: account.name.clone(),
+                model_name: model_name.map(str::to_string),
+                granularity,
+                metric: settings.metric,
+                bucket_key: bucket.key.clone(),
+                bucket_start: *start,
+                bucket_end: bucket.end,
+                value: bucket.value,
+                request_count: bucket.request_count,
+                baseline_mean: mean,
+                baseline_std: std,
+                ratio,
+                z_score,
+                session_ids,
+                detected_at: now,
+                updated_at: now,
+                acknowledged: false,
+            });
+        }
+    }
+
+    anomalies.sort_by(|a, b| b.bucket_start.cmp(&a.bucket_start).then_with(|| a.model_name.cmp(&b.model_name)));
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+    const DAY: i64 = 24 * HOUR;
+    /// 2026-10-18 12:00:00 UTC
+    const NOW: i64 = 1_792_324_800;
+
+    fn session(id: &str, usage_time: i64, model: &str, amount: f64) -> UsageSession {
+        serde_json::from_value(serde_json::json!({
+            "session_id": id, "usage_time": usage_time, "mode": "", "model_name": model, "amount_float": amount,
+            "cost_money_float": 0.0, "use_max_mode": false, "product_type_list": [],
+            "extra_info": { "cache_read_token": 0, "cache_write_token": 0, "input_token": 0, "output_token": 0 },
+        }))
+        .unwrap()
+    }
+
+    fn account() -> Account {
+        Account::new("test".into(), "test@example.com".into(), String::new(), "u".into(), "t".into())
+    }
+
+    /// 过去 14 天每天 `daily` 的平稳用量
+    fn baseline(daily: f64) -> Vec<UsageSession> {
+        (2..=14).map(|d| session(&format!("base-{}", d), NOW - d * DAY, "model-a", daily)).collect()
+    }
+
+    fn detect(sessions: &[UsageSession]) -> Vec<UsageAnomaly> {
+        detect_anomalies(&account(), sessions, &AnomalySettings::default(), Tz::UTC, NOW)
+    }
+
+    #[test]
+    fn spike_above_baseline_is_reported_with_sessions() {
+        let mut sessions = baseline(5.0);
+        sessions.push(session("spike-1", NOW - HOUR, "model-a", 30.0));
+        sessions.push(session("spike-2", NOW - 2 * HOUR, "model-a", 50.0));
+
+        let anomalies = detect(&sessions);
+        assert_eq!(anomalies.len(), 2, "账号整体和单个模型各一条");
+        let total = anomalies.iter().find(|a| a.model_name.is_none()).unwrap();
+        assert_eq!(total.bucket_key, "2026-10-18");
+        assert_eq!(total.value, 80.0);
+        assert_eq!(total.request_count, 2);
+        assert_eq!(total.session_ids, vec!["spike-2", "spike-1"]);
+        assert!(total.ratio >= 3.0);
+        assert!(anomalies.iter().any(|a| a.model_name.as_deref() == Some("model-a")));
+    }
+
+    #[test]
+    fn usage_within_baseline_is_not_reported() {
+        let mut sessions = baseline(20.0);
+        sessions.push(session("today", NOW - HOUR, "model-a", 25.0));
+        assert!(detect(&sessions).is_empty());
+    }
+
+    #[test]
+    fn spike_without_enough_baseline_is_skipped() {
+        let sessions = vec![
+            session("old", NOW - 3 * DAY, "model-a", 1.0),
+            session("spike", NOW - HOUR, "model-a", 100.0),
+        ];
+        assert!(detect(&sessions).is_empty());
+    }
+
+    #[test]
+    fn small_spikes_below_min_value_are_ignored() {
+        let mut sessions = baseline(0.5);
+        sessions.push(session("today", NOW - HOUR, "model-a", 5.0));
+        assert!(detect(&sessions).is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod anomaly_detector;
+pub mod types;
+
+pub use anomaly_detector::{detect_anomalies, AnomalyDetector, AnomalyNotifier};
+pub use types::*;
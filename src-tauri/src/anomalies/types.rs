@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+/// 检测粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyGranularity {
+    Hour,
+    #[default]
+    Day,
+}
+
+/// 检测的用量指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyMetric {
+    /// 消耗的额度
+    #[default]
+    Amount,
+    /// 请求次数
+    Requests,
+    /// Token 总数（含缓存）
+    Tokens,
+}
+
+impl AnomalyMetric {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnomalyMetric::Amount => "额度消耗",
+            AnomalyMetric::Requests => "请求次数",
+            AnomalyMetric::Tokens => "Token 用量",
+        }
+    }
+}
+
+/// 异常检测设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalySettings {
+    pub enabled: bool,
+    pub granularity: AnomalyGranularity,
+    pub metric: AnomalyMetric,
+    /// 基线使用的历史天数
+    pub baseline_days: u32,
+    /// 检测最近多少小时内的用量
+    pub detection_hours: u32,
+    /// 超过基线均值的标准差倍数
+    pub z_threshold: f64,
+    /// 超过基线均值的倍数
+    pub min_ratio: f64,
+    /// 低于该值的用量不视为异常
+    pub min_value: f64,
+    /// 基线中至少有多少个有用量的时段（不足时不检测）
+    pub min_active_buckets: usize,
+    /// 是否按模型分别检测
+    pub per_model: bool,
+    /// 是否发送系统通知
+    pub native_notifications: bool,
+    /// 最多保留的异常记录数
+    pub history_limit: usize,
+}
+
+impl Default for AnomalySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            granularity: AnomalyGranularity::Day,
+            metric: AnomalyMetric::Amount,
+            baseline_days: 14,
+            detection_hours: 24,
+            z_threshold: 3.0,
+            min_ratio: 3.0,
+            min_value: 10.0,
+            min_active_buckets: 3,
+            per_model: true,
+            native_notifications: true,
+            history_limit: 200,
+        }
+    }
+}
+
+/// 用量异常（同一账号、模型和时段只有一条记录）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageAnomaly {
+    pub id: String,
+    pub account_id: String,
+    pub account_name: String,
+    /// 为空表示账号全部模型
+    pub model_name: Option<String>,
+    pub granularity: AnomalyGranularity,
+    pub metric: AnomalyMetric,
+    pub bucket_key: String,
+    pub bucket_start: i64,
+    pub bucket_end: i64,
+    pub value: f64,
+    pub request_count: u64,
+    pub baseline_mean: f64,
+    pub baseline_std: f64,
+    /// 相对基线均值的倍数
+    pub ratio: f64,
+    pub z_score: f64,
+    /// 该时段用量最大的会话
+    pub session_ids: Vec<String>,
+    pub detected_at: i64,
+    pub updated_at: i64,
+    pub acknowledged: bool,
+}
+
+impl UsageAnomaly {
+    pub fn message(&self) -> String {
+        format!(
+            "{}{} 在 {} 的{}为 {:.1}，是平时的 {:.1} 倍（基线 {:.1}）",
+            self.account_name,
+            self.model_name.as_deref().map(|m| format!(" / {}", m)).unwrap_or_default(),
+            self.bucket_key,
+            self.metric.label(),
+            self.value,
+            self.ratio,
+            self.baseline_mean
+        )
+    }
+}
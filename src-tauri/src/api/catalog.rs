@@ -0,0 +1,330 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::enums::*;
+
+/// 基础套餐名称在 `value_labels` 中的分组名（按 product_id 查找）
+pub const PLAN_KIND: &str = "plan";
+
+/// 内置的礼包目录
+const BUNDLED_CATALOG: &str = include_str!("../../resources/package_catalog.json");
+
+static BUNDLED: Lazy<PackageCatalog> = Lazy::new(|| {
+    serde_json::from_str(BUNDLED_CATALOG).expect("内置礼包目录格式错误")
+});
+
+/// 多语言文本（语言代码 -> 文本）
+pub type LocalizedText = BTreeMap<String, String>;
+
+/// 礼包目录条目，未填写的匹配字段表示匹配任意值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackageCatalogEntry {
+    pub source_type: Option<i32>,
+    pub product_id: Option<i32>,
+    pub duration_type: Option<i32>,
+    pub name: LocalizedText,
+    pub description: LocalizedText,
+}
+
+impl PackageCatalogEntry {
+    /// 是否匹配，匹配时返回具体程度（指定的字段越多越具体）
+    fn matches(&self, source_type: i32, product_id: i32, duration_type: i32) -> Option<usize> {
+        let fields = [
+            (self.source_type, source_type),
+            (self.product_id, product_id),
+            (self.duration_type, duration_type),
+        ];
+
+        let mut specificity = 0;
+        for (expected, actual) in fields {
+            match expected {
+                Some(expected) if expected != actual => return None,
+                Some(_) => specificity += 1,
+                None => {}
+            }
+        }
+        Some(specificity)
+    }
+}
+
+/// 礼包目录（内置目录 + 用户覆盖文件）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackageCatalog {
+    /// 默认展示语言
+    pub locale: String,
+    pub packages: Vec<PackageCatalogEntry>,
+    /// 未收录礼包的名称模板，支持 {source}、{source_type}、{product_id}、{duration}
+    pub fallback_name: LocalizedText,
+    /// 来源未收录在 value_labels 中的礼包名称模板
+    pub unknown_source_name: LocalizedText,
+    /// 接口取值的展示名称，按取值类型（如 period_type、currency、plan）分组
+    pub value_labels: BTreeMap<String, ValueLabels>,
+    /// 未收录取值的名称模板，支持 {kind}（取值类型名称）和 {value}
+    pub unknown_value_name: LocalizedText,
+}
+
+/// 一种接口取值的展示名称
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValueLabels {
+    /// 取值类型名称（如 "Billing period"）
+    pub name: LocalizedText,
+    /// 原始数字 -> 名称
+    pub values: BTreeMap<i32, LocalizedText>,
+}
+
+/// 礼包的展示名称
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageLabel {
+    pub name: String,
+    pub description: String,
+    pub names: LocalizedText,
+    pub descriptions: LocalizedText,
+    /// 是否来自目录（false 表示自动生成）
+    pub from_catalog: bool,
+}
+
+impl PackageCatalog {
+    /// 内置目录
+    pub fn bundled() -> &'static PackageCatalog {
+        &BUNDLED
+    }
+
+    /// 加载目录：用户覆盖文件中的条目优先于内置条目
+    pub fn load(override_path: &Path) -> Self {
+        let mut catalog = Self::bundled().clone();
+        if !override_path.exists() {
+            return catalog;
+        }
+
+        match Self::read_file(override_path) {
+            Ok(overrides) => catalog.merge(overrides),
+            Err(e) => println!("[WARN] 礼包目录覆盖文件无效，使用内置目录: {}", e),
+        }
+        catalog
+    }
+
+    /// 读取目录文件
+    pub fn read_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("读取礼包目录失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("解析礼包目录失败: {}", e))
+    }
+
+    /// 合并覆盖目录
+    fn merge(&mut self, overrides: PackageCatalog) {
+        if !overrides.locale.is_empty() {
+            self.locale = overrides.locale;
+        }
+        self.fallback_name.extend(overrides.fallback_name);
+        self.unknown_source_name.extend(overrides.unknown_source_name);
+        self.unknown_value_name.extend(overrides.unknown_value_name);
+        for (kind, labels) in overrides.value_labels {
+            let current = self.value_labels.entry(kind).or_default();
+            current.name.extend(labels.name);
+            current.values.extend(labels.values);
+        }
+
+        let mut packages = overrides.packages;
+        packages.append(&mut self.packages);
+        self.packages = packages;
+    }
+
+    /// 解析礼包名称，未收录时根据来源和时长生成
+    pub fn resolve(&self, package: &PackageDescriptor) -> PackageLabel {
+        let source_type = i32::from(package.source_type);
+        let duration_type = i32::from(package.duration_type);
+
+        let mut best: Option<(usize, &PackageCatalogEntry)> = None;
+        for entry in &self.packages {
+            if let Some(specificity) = entry.matches(source_type, package.product_id, duration_type) {
+                if best.is_none_or(|(current, _)| specificity > current) {
+                    best = Some((specificity, entry));
+                }
+            }
+        }
+
+        if let Some((_, entry)) = best {
+            return PackageLabel {
+                name: self.text(&entry.name),
+                description: self.text(&entry.description),
+                names: entry.name.clone(),
+                descriptions: entry.description.clone(),
+                from_catalog: true,
+            };
+        }
+
+        let template = if self.has_label(package.source_type) {
+            &self.fallback_name
+        } else {
+            &self.unknown_source_name
+        };
+        let names: LocalizedText = template
+            .iter()
+            .map(|(locale, text)| (locale.clone(), self.fill(text, package)))
+            .collect();
+
+        PackageLabel {
+            name: self.text(&names),
+            description: String::new(),
+            names,
+            descriptions: LocalizedText::new(),
+            from_catalog: false,
+        }
+    }
+
+    /// 接口取值的展示名称
+    pub fn label<T: CatalogValue>(&self, value: T) -> String {
+        self.value_label(T::KIND, value.code())
+    }
+
+    /// 目录中是否收录了该取值的名称
+    pub fn has_label<T: CatalogValue>(&self, value: T) -> bool {
+        self.value_labels
+            .get(T::KIND)
+            .is_some_and(|labels| labels.values.contains_key(&value.code()))
+    }
+
+    /// 基础套餐名称（按 product_id 查找）
+    pub fn plan_label(&self, product_id: i32) -> String {
+        self.value_label(PLAN_KIND, product_id)
+    }
+
+    /// 礼包时长描述，如 "30 (Days)"
+    pub fn describe_duration(&self, duration_type: PackageDurationType, duration: i32) -> String {
+        if duration <= 0 {
+            return String::new();
+        }
+        format!("{} ({})", duration, self.label(duration_type))
+    }
+
+    /// 按取值类型查找展示名称，未收录时按 `unknown_value_name` 生成（如 "Billing period #1"）
+    pub fn value_label(&self, kind: &str, code: i32) -> String {
+        let labels = self.value_labels.get(kind);
+        if let Some(text) = labels.and_then(|l| l.values.get(&code)) {
+            return self.text(text);
+        }
+
+        let kind_name = labels
+            .map(|l| self.text(&l.name))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| kind.to_string());
+        let template = Some(self.text(&self.unknown_value_name))
+            .filter(|template| !template.is_empty())
+            .unwrap_or_else(|| "{kind} #{value}".to_string());
+        template
+            .replace("{kind}", &kind_name)
+            .replace("{value}", &code.to_string())
+    }
+
+    /// 按默认语言取文本，缺失时依次回退到英文和任意语言
+    fn text(&self, text: &LocalizedText) -> String {
+        text.get(&self.locale)
+            .or_else(|| text.get("en"))
+            .or_else(|| text.values().next())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn fill(&self, template: &str, package: &PackageDescriptor) -> String {
+        template
+            .replace("{source}", &self.label(package.source_type))
+            .replace("{source_type}", &i32::from(package.source_type).to_string())
+            .replace("{product_id}", &package.product_id.to_string())
+            .replace("{duration}", &self.describe_duration(package.duration_type, package.duration))
+            .trim()
+            .to_string()
+    }
+}
+
+/// 用于查询目录的礼包信息
+#[derive(Debug, Clone, Copy)]
+pub struct PackageDescriptor {
+    pub source_type: PackageSourceType,
+    pub product_id: i32,
+    pub duration_type: PackageDurationType,
+    pub duration: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(json: serde_json::Value) -> PackageCatalog {
+        let mut catalog = PackageCatalog::bundled().clone();
+        catalog.merge(serde_json::from_value(json).unwrap());
+        catalog
+    }
+
+    #[test]
+    fn bundled_catalog_labels_known_values() {
+        let catalog = PackageCatalog::bundled();
+        assert_eq!(catalog.plan_label(0), "Free");
+        assert_eq!(catalog.label(ProductType::Package), "Package");
+        assert_eq!(catalog.label(PackageSourceType::Anniversary), "Anniversary");
+    }
+
+    #[test]
+    fn unknown_values_use_the_kind_name() {
+        let catalog = PackageCatalog::bundled();
+        assert_eq!(catalog.plan_label(7), "Plan #7");
+        assert_eq!(catalog.label(PeriodType::from(1)), "Billing period #1");
+        assert_eq!(catalog.label(Currency::from(0)), "Currency #0");
+        assert_eq!(catalog.describe_duration(PackageDurationType::from(1), 30), "30 (Duration unit #1)");
+        assert_eq!(catalog.describe_duration(PackageDurationType::from(1), 0), "");
+        // 目录中没有的取值类型直接使用分组名
+        assert_eq!(catalog.value_label("unlisted", 3), "unlisted #3");
+    }
+
+    #[test]
+    fn labels_follow_the_catalog_locale() {
+        let catalog = overrides(serde_json::json!({ "locale": "zh" }));
+        assert_eq!(catalog.plan_label(0), "免费版");
+        assert_eq!(catalog.label(PeriodType::from(1)), "计费周期 #1");
+    }
+
+    #[test]
+    fn overrides_add_value_labels() {
+        let catalog = overrides(serde_json::json!({
+            "value_labels": {
+                "period_type": { "values": { "1": { "en": "Monthly" } } },
+                "plan": { "values": { "7": { "en": "Pro" } } }
+            }
+        }));
+        assert_eq!(catalog.label(PeriodType::from(1)), "Monthly");
+        assert_eq!(catalog.label(PeriodType::from(2)), "Billing period #2");
+        assert_eq!(catalog.plan_label(7), "Pro");
+        // 内置的取值不受影响
+        assert_eq!(catalog.plan_label(0), "Free");
+    }
+
+    #[test]
+    fn fallback_package_name_uses_source_label() {
+        let catalog = PackageCatalog::bundled();
+        let label = catalog.resolve(&PackageDescriptor {
+            source_type: PackageSourceType::from(9),
+            product_id: 1,
+            duration_type: PackageDurationType::from(1),
+            duration: 30,
+        });
+        assert!(!label.from_catalog);
+        assert_eq!(label.name, "Package #9");
+
+        let catalog = overrides(serde_json::json!({
+            "value_labels": { "package_source_type": { "values": { "9": { "en": "Referral" } } } }
+        }));
+        let label = catalog.resolve(&PackageDescriptor {
+            source_type: PackageSourceType::from(9),
+            product_id: 1,
+            duration_type: PackageDurationType::from(1),
+            duration: 30,
+        });
+        assert_eq!(label.name, "Referral Package");
+    }
+}
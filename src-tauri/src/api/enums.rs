@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+/// 可以在礼包目录中查找展示名称的接口取值
+pub trait CatalogValue: Copy {
+    /// 目录中 `value_labels` 的分组名
+    const KIND: &'static str;
+
+    /// 接口返回的原始数字
+    fn code(&self) -> i32;
+}
+
+/// 定义以 i32 表示的接口枚举，未识别的值保留在 `Unknown` 中，展示名称由礼包目录提供
+macro_rules! int_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident($kind:literal) {
+            $($(#[$vmeta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "i32", into = "i32")]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)*
+            Unknown(i32),
+        }
+
+        impl From<i32> for $name {
+            fn from(value: i32) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    other => Self::Unknown(other),
+                }
+            }
+        }
+
+        impl From<$name> for i32 {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(other) => other,
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::from(0)
+            }
+        }
+
+        impl CatalogValue for $name {
+            const KIND: &'static str = $kind;
+
+            fn code(&self) -> i32 {
+                i32::from(*self)
+            }
+        }
+
+        impl $name {
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, Self::Unknown(_))
+            }
+        }
+    };
+}
+
+// 接口没有公开这些字段的取值说明。下面只收录解析逻辑依赖的取值（product_type 2 为额外礼包、
+// package_source_type 6 为周年礼包）；展示名称统一由礼包目录（resources/package_catalog.json
+// 的 value_labels，可在覆盖文件中补充）提供，未收录的取值按目录中的模板生成名称。
+
+int_enum! {
+    /// 产品类型（entitlement_base_info.product_type）
+    ProductType("product_type") {
+        /// 额外礼包（如周年礼包、加购包）
+        Package = 2,
+    }
+}
+
+int_enum! {
+    /// 权益包状态（user_entitlement_pack_list[].status）
+    PackStatus("pack_status") {}
+}
+
+int_enum! {
+    /// 订阅周期（subscription_extra.period_type）
+    PeriodType("period_type") {}
+}
+
+int_enum! {
+    /// 礼包时长单位（package_extra.package_duration_type）
+    PackageDurationType("package_duration_type") {}
+}
+
+int_enum! {
+    /// 礼包来源（package_extra.package_source_type）
+    PackageSourceType("package_source_type") {
+        Anniversary = 6,
+    }
+}
+
+int_enum! {
+    /// 计费币种（entitlement_base_info.currency）
+    Currency("currency") {}
+}
+
+impl ProductType {
+    /// 是否为额外礼包
+    pub fn is_package(&self) -> bool {
+        matches!(self, Self::Package)
+    }
+}
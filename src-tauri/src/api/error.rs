@@ -0,0 +1,57 @@
+use std::fmt;
+
+/// 请求没有得到服务器的响应（连接失败、超时等），与服务器返回的错误区分开
+#[derive(Debug)]
+pub struct TransportError(pub String);
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "请求失败: {}", self.0)
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+/// 服务器返回了非成功的 HTTP 状态码
+#[derive(Debug)]
+pub struct HttpStatusError {
+    /// 失败的操作（如 "获取配额信息失败"）
+    pub context: String,
+    pub status: reqwest::StatusCode,
+}
+
+impl HttpStatusError {
+    pub fn new(context: &str, status: reqwest::StatusCode) -> Self {
+        Self {
+            context: context.to_string(),
+            status,
+        }
+    }
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.status)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// 错误是否由网络不可用引起（此时可以改用缓存的数据）
+pub fn is_transport_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.is::<TransportError>()
+            || cause
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request())
+    })
+}
+
+/// 错误是否由凭据失效（HTTP 401）引起
+pub fn is_unauthorized(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<HttpStatusError>()
+            .is_some_and(|e| e.status == reqwest::StatusCode::UNAUTHORIZED)
+    })
+}
@@ -0,0 +1,248 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use super::error::{HttpStatusError, TransportError};
+use super::interface::TraeApi;
+use super::types::*;
+
+/// 内存中的账号数据
+#[derive(Debug, Clone)]
+pub struct FakeAccount {
+    pub user_id: String,
+    pub tenant_id: String,
+    pub screen_name: String,
+    pub email: String,
+    pub avatar_url: String,
+    pub region: String,
+    pub cookies: String,
+    pub token: String,
+    pub entitlements: EntitlementListResponse,
+    pub usage_sessions: Vec<UsageSession>,
+    pub bonus_claimed: bool,
+}
+
+impl FakeAccount {
+    pub fn new(user_id: &str, cookies: &str, token: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            tenant_id: "tenant".to_string(),
+            screen_name: format!("user_{}", user_id),
+            email: format!("{}@example.com", user_id),
+            avatar_url: String::new(),
+            region: "SG".to_string(),
+            cookies: cookies.to_string(),
+            token: token.to_string(),
+            entitlements: EntitlementListResponse::default(),
+            usage_sessions: Vec::new(),
+            bonus_claimed: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    accounts: Vec<FakeAccount>,
+    /// 已过期的 Token，调用时返回 401
+    expired_tokens: HashSet<String>,
+    /// 使用这些 Token 的调用会 panic，用于模拟任务异常退出
+    panicking_tokens: HashSet<String>,
+    /// 网络不可用时所有调用都失败
+    offline: bool,
+    /// 调用记录（方法名）
+    calls: Vec<String>,
+    token_counter: u64,
+}
+
+/// 不访问网络的 TraeApi 实现
+#[derive(Debug, Default)]
+pub struct FakeTraeApi {
+    state: Mutex<FakeState>,
+}
+
+impl FakeTraeApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个账号
+    pub fn add_account(&self, account: FakeAccount) {
+        self.state.lock().unwrap().accounts.push(account);
+    }
+
+    /// 让 Token 过期（之后使用该 Token 的调用返回 401）
+    pub fn expire_token(&self, token: &str) {
+        self.state.lock().unwrap().expired_tokens.insert(token.to_string());
+    }
+
+    /// 让使用该 Token 的调用 panic
+    pub fn panic_on_token(&self, token: &str) {
+        self.state.lock().unwrap().panicking_tokens.insert(token.to_string());
+    }
+
+    /// 切换离线状态
+    pub fn set_offline(&self, offline: bool) {
+        self.state.lock().unwrap().offline = offline;
+    }
+
+    /// 获取调用记录
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// 获取账号当前的 Token
+    pub fn current_token(&self, user_id: &str) -> Option<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .accounts
+            .iter()
+            .find(|a| a.user_id == user_id)
+            .map(|a| a.token.clone())
+    }
+
+    fn with_cookies<T>(&self, method: &str, cookies: &str, f: impl FnOnce(&mut FakeAccount) -> T) -> Result<T> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(method.to_string());
+        if state.offline {
+            return Err(TransportError("network unreachable".to_string()).into());
+        }
+        let account = state
+            .accounts
+            .iter_mut()
+            .find(|a| !cookies.is_empty() && a.cookies == cookies)
+            .ok_or_else(|| HttpStatusError::new("获取 Token 失败", StatusCode::UNAUTHORIZED))?;
+        Ok(f(account))
+    }
+
+    fn with_token<T>(&self, method: &str, token: &str, f: impl FnOnce(&mut FakeAccount) -> T) -> Result<T> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(method.to_string());
+        if state.panicking_tokens.contains(token) {
+            drop(state);
+            panic!("{} 调用 panic: {}", method, token);
+        }
+        if state.offline {
+            return Err(TransportError("network unreachable".to_string()).into());
+        }
+        if state.expired_tokens.contains(token) {
+            return Err(HttpStatusError::new("API 返回错误", StatusCode::UNAUTHORIZED).into());
+        }
+        let account = state
+            .accounts
+            .iter_mut()
+            .find(|a| a.token == token)
+            .ok_or_else(|| HttpStatusError::new("API 返回错误", StatusCode::UNAUTHORIZED))?;
+        Ok(f(account))
+    }
+
+    fn page(sessions: &[UsageSession], start_time: i64, end_time: i64, page_size: i32, page_num: i32) -> UsageQueryResponse {
+        let mut matched: Vec<UsageSession> = sessions
+            .iter()
+            .filter(|s| s.usage_time >= start_time && s.usage_time <= end_time)
+            .cloned()
+            .collect();
+        matched.sort_by_key(|s| std::cmp::Reverse(s.usage_time));
+
+        let size = page_size.max(1) as usize;
+        let skip = (page_num.max(1) as usize - 1) * size;
+        UsageQueryResponse {
+            total: matched.len() as i64,
+            user_usage_group_by_sessions: matched.into_iter().skip(skip).take(size).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl TraeApi for FakeTraeApi {
+    async fn get_user_token(&self, cookies: &str) -> Result<UserTokenResult> {
+        let counter = {
+            let mut state = self.state.lock().unwrap();
+            state.token_counter += 1;
+            state.token_counter
+        };
+        self.with_cookies("get_user_token", cookies, |account| {
+            account.token = format!("{}-token-{}", account.user_id, counter);
+            UserTokenResult {
+                token: account.token.clone(),
+                expired_at: "2099-01-01T00:00:00Z".to_string(),
+                user_id: account.user_id.clone(),
+                tenant_id: account.tenant_id.clone(),
+            }
+        })
+    }
+
+    async fn get_user_info(&self, cookies: &str) -> Result<UserInfoResult> {
+        self.with_cookies("get_user_info", cookies, |account| UserInfoResult {
+            screen_name: account.screen_name.clone(),
+            gender: String::new(),
+            avatar_url: account.avatar_url.clone(),
+            user_id: account.user_id.clone(),
+            description: String::new(),
+            tenant_id: account.tenant_id.clone(),
+            register_time: String::new(),
+            last_login_time: String::new(),
+            last_login_type: String::new(),
+            region: account.region.clone(),
+            ai_region: None,
+            non_plain_text_email: Some(account.email.clone()),
+            store_country: None,
+        })
+    }
+
+    async fn get_user_info_by_token(&self, token: &str) -> Result<TokenUserInfo> {
+        self.with_token("get_user_info_by_token", token, |account| TokenUserInfo {
+            user_id: account.user_id.clone(),
+            tenant_id: account.tenant_id.clone(),
+            screen_name: Some(account.screen_name.clone()),
+            avatar_url: None,
+            email: Some(account.email.clone()),
+        })
+    }
+
+    async fn get_entitlement_list(&self, cookies: &str) -> Result<EntitlementListResponse> {
+        self.with_cookies("get_entitlement_list", cookies, |account| account.entitlements.clone())
+    }
+
+    async fn get_entitlement_list_by_token(&self, token: &str) -> Result<EntitlementListResponse> {
+        self.with_token("get_entitlement_list_by_token", token, |account| account.entitlements.clone())
+    }
+
+    async fn query_usage(
+        &self,
+        cookies: &str,
+        start_time: i64,
+        end_time: i64,
+        page_size: i32,
+        page_num: i32,
+    ) -> Result<UsageQueryResponse> {
+        self.with_cookies("query_usage", cookies, |account| {
+            Self::page(&account.usage_sessions, start_time, end_time, page_size, page_num)
+        })
+    }
+
+    async fn query_usage_by_token(
+        &self,
+        token: &str,
+        start_time: i64,
+        end_time: i64,
+        page_size: i32,
+        page_num: i32,
+    ) -> Result<UsageQueryResponse> {
+        self.with_token("query_usage_by_token", token, |account| {
+            Self::page(&account.usage_sessions, start_time, end_time, page_size, page_num)
+        })
+    }
+
+    async fn query_birthday_bonus(&self, token: &str) -> Result<bool> {
+        self.with_token("query_birthday_bonus", token, |account| account.bonus_claimed)
+    }
+
+    async fn claim_birthday_bonus(&self, token: &str) -> Result<()> {
+        self.with_token("claim_birthday_bonus", token, |account| {
+            account.bonus_claimed = true;
+        })
+    }
+}
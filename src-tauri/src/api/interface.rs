@@ -0,0 +1,123 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::trae_api::TraeApiClient;
+use super::types::*;
+
+/// Trae API 抽象（便于注入 HTTP 实现或内存实现）
+///
+/// 每个方法都显式携带凭据（Cookies 或 Token），实现本身不保存账号状态，
+/// 因此同一个实例可以被所有账号共享。
+#[async_trait]
+pub trait TraeApi: Send + Sync {
+    /// 使用 Cookies 换取 Token
+    async fn get_user_token(&self, cookies: &str) -> Result<UserTokenResult>;
+
+    /// 使用 Cookies 获取用户信息
+    async fn get_user_info(&self, cookies: &str) -> Result<UserInfoResult>;
+
+    /// 使用 Token 获取用户信息
+    async fn get_user_info_by_token(&self, token: &str) -> Result<TokenUserInfo>;
+
+    /// 使用 Cookies 获取配额信息
+    async fn get_entitlement_list(&self, cookies: &str) -> Result<EntitlementListResponse>;
+
+    /// 使用 Token 获取配额信息
+    async fn get_entitlement_list_by_token(&self, token: &str) -> Result<EntitlementListResponse>;
+
+    /// 使用 Cookies 查询使用记录
+    async fn query_usage(
+        &self,
+        cookies: &str,
+        start_time: i64,
+        end_time: i64,
+        page_size: i32,
+        page_num: i32,
+    ) -> Result<UsageQueryResponse>;
+
+    /// 使用 Token 查询使用记录
+    async fn query_usage_by_token(
+        &self,
+        token: &str,
+        start_time: i64,
+        end_time: i64,
+        page_size: i32,
+        page_num: i32,
+    ) -> Result<UsageQueryResponse>;
+
+    /// 查询礼包是否已领取
+    async fn query_birthday_bonus(&self, token: &str) -> Result<bool>;
+
+    /// 领取礼包
+    async fn claim_birthday_bonus(&self, token: &str) -> Result<()>;
+}
+
+/// 基于 HTTP 的真实实现，每次调用按凭据创建 `TraeApiClient`
+#[derive(Debug, Clone, Default)]
+pub struct HttpTraeApi;
+
+#[async_trait]
+impl TraeApi for HttpTraeApi {
+    async fn get_user_token(&self, cookies: &str) -> Result<UserTokenResult> {
+        let mut client = TraeApiClient::new(cookies)?;
+        client.get_user_token().await
+    }
+
+    async fn get_user_info(&self, cookies: &str) -> Result<UserInfoResult> {
+        let client = TraeApiClient::new(cookies)?;
+        client.get_user_info().await
+    }
+
+    async fn get_user_info_by_token(&self, token: &str) -> Result<TokenUserInfo> {
+        let client = TraeApiClient::new_with_token(token)?;
+        client.get_user_info_by_token().await
+    }
+
+    async fn get_entitlement_list(&self, cookies: &str) -> Result<EntitlementListResponse> {
+        let mut client = TraeApiClient::new(cookies)?;
+        // 先获取 token
+        client.get_user_token().await?;
+        client.get_entitlement_list().await
+    }
+
+    async fn get_entitlement_list_by_token(&self, token: &str) -> Result<EntitlementListResponse> {
+        let client = TraeApiClient::new_with_token(token)?;
+        client.get_entitlement_list_by_token().await
+    }
+
+    async fn query_usage(
+        &self,
+        cookies: &str,
+        start_time: i64,
+        end_time: i64,
+        page_size: i32,
+        page_num: i32,
+    ) -> Result<UsageQueryResponse> {
+        let mut client = TraeApiClient::new(cookies)?;
+        // 先获取 token
+        client.get_user_token().await?;
+        client.query_usage(start_time, end_time, page_size, page_num).await
+    }
+
+    async fn query_usage_by_token(
+        &self,
+        token: &str,
+        start_time: i64,
+        end_time: i64,
+        page_size: i32,
+        page_num: i32,
+    ) -> Result<UsageQueryResponse> {
+        let client = TraeApiClient::new_with_token(token)?;
+        client.query_usage(start_time, end_time, page_size, page_num).await
+    }
+
+    async fn query_birthday_bonus(&self, token: &str) -> Result<bool> {
+        let client = TraeApiClient::new_with_token(token)?;
+        client.query_birthday_bonus().await
+    }
+
+    async fn claim_birthday_bonus(&self, token: &str) -> Result<()> {
+        let client = TraeApiClient::new_with_token(token)?;
+        client.claim_birthday_bonus().await
+    }
+}
@@ -1,5 +1,15 @@
+pub mod catalog;
+pub mod enums;
+pub mod error;
+#[cfg(test)]
+pub mod fake;
+pub mod interface;
 pub mod trae_api;
 pub mod types;
 
+pub use catalog::{PackageCatalog, PackageLabel};
+pub use enums::*;
+pub use error::{is_transport_error, is_unauthorized, HttpStatusError, TransportError};
+pub use interface::{HttpTraeApi, TraeApi};
 pub use trae_api::TraeApiClient;
 pub use types::*;
@@ -3,6 +3,8 @@ use reqwest::{header, Client};
 use serde_json::json;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
+use super::catalog::PackageDescriptor;
+use super::{HttpStatusError, PackageCatalog, PackageLabel, TransportError};
 use super::types::*;
 
 const API_BASE_US: &str = "https://api-us-east.trae.ai";
@@ -165,7 +167,7 @@ impl TraeApiClient {
                     }
                 }
                 Ok(resp) => {
-                    last_error = anyhow!("API 返回错误: {}", resp.status());
+                    last_error = HttpStatusError::new("API 返回错误", resp.status()).into();
                 }
                 Err(e) => {
                     last_error = anyhow!("请求失败: {}", e);
@@ -190,7 +192,7 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("获取用户信息失败: {}", response.status()));
+            return Err(HttpStatusError::new("获取用户信息失败", response.status()).into());
         }
 
         let data: GetUserInfoResponse = response.json().await?;
@@ -271,7 +273,7 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("获取 Token 失败: {}", response.status()));
+            return Err(HttpStatusError::new("获取 Token 失败", response.status()).into());
         }
 
         let data: GetUserTokenResponse = response.json().await?;
@@ -293,7 +295,7 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("获取用户信息失败: {}", response.status()));
+            return Err(HttpStatusError::new("获取用户信息失败", response.status()).into());
         }
 
         let data: GetUserInfoResponse = response.json().await?;
@@ -314,7 +316,7 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("获取配额信息失败: {}", response.status()));
+            return Err(HttpStatusError::new("获取配额信息失败", response.status()).into());
         }
 
         let data: EntitlementListResponse = response.json().await?;
@@ -349,26 +351,15 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("查询使用记录失败: {}", response.status()));
+            return Err(HttpStatusError::new("查询使用记录失败", response.status()).into());
         }
 
         let data: UsageQueryResponse = response.json().await?;
         Ok(data)
     }
 
-    /// 获取使用量汇总（简化版，用于前端展示）
-    pub async fn get_usage_summary(&mut self) -> Result<UsageSummary> {
-        // 确保有 token
-        if self.jwt_token.is_none() {
-            self.get_user_token().await?;
-        }
-
-        let entitlements = self.get_entitlement_list().await?;
-        Self::parse_entitlements_to_summary(entitlements)
-    }
-
-    /// 通过 Token 获取使用量汇总
-    pub async fn get_usage_summary_by_token(&self) -> Result<UsageSummary> {
+    /// 通过 Token 获取配额信息（依次尝试多个 API 端点）
+    pub async fn get_entitlement_list_by_token(&self) -> Result<EntitlementListResponse> {
         let headers = self.build_headers_token_only()?;
         let endpoints = [&self.api_base, API_BASE_SG, API_BASE_US];
 
@@ -392,12 +383,7 @@ impl TraeApiClient {
                     println!("[DEBUG] API Response from {}: {}", base, response_text);
 
                     match serde_json::from_str::<EntitlementListResponse>(&response_text) {
-                        Ok(entitlements) => {
-                            let summary = Self::parse_entitlements_to_summary(entitlements)?;
-                            println!("[DEBUG] Parsed Summary: fast_request_limit={}, extra_fast_request_limit={}",
-                                summary.fast_request_limit, summary.extra_fast_request_limit);
-                            return Ok(summary);
-                        }
+                        Ok(entitlements) => return Ok(entitlements),
                         Err(e) => {
                             last_error = anyhow!("解析响应失败: {}", e);
                         }
@@ -405,11 +391,11 @@ impl TraeApiClient {
                 }
                 Ok(resp) => {
                     println!("[DEBUG] API {} returned error: {}", base, resp.status());
-                    last_error = anyhow!("API 返回错误: {}", resp.status());
+                    last_error = HttpStatusError::new("API 返回错误", resp.status()).into();
                 }
                 Err(e) => {
                     println!("[DEBUG] API {} request failed: {}", base, e);
-                    last_error = anyhow!("请求失败: {}", e);
+                    last_error = TransportError(e.to_string()).into();
                 }
             }
         }
@@ -418,66 +404,252 @@ impl TraeApiClient {
     }
 
     /// 解析配额信息为使用量汇总
-    fn parse_entitlements_to_summary(entitlements: EntitlementListResponse) -> Result<UsageSummary> {
+    pub fn parse_entitlements_to_summary(
+        entitlements: EntitlementListResponse,
+        catalog: &PackageCatalog,
+    ) -> Result<UsageSummary> {
+        Ok(Self::summarize_entitlements(
+            &entitlements,
+            catalog,
+            chrono::Utc::now().timestamp(),
+        ))
+    }
+
+    /// 按指定时间（秒）汇总权益包：基础额度取当前生效的基础套餐，礼包额度累加所有未过期的礼包
+    fn summarize_entitlements(
+        entitlements: &EntitlementListResponse,
+        catalog: &PackageCatalog,
+        now: i64,
+    ) -> UsageSummary {
         let mut summary = UsageSummary::default();
+        let mut package_names: Vec<String> = Vec::new();
+        let mut package_statuses: Vec<String> = Vec::new();
+        let mut package_sources: Vec<String> = Vec::new();
+        let mut package_durations: Vec<String> = Vec::new();
 
-        for pack in entitlements.user_entitlement_pack_list {
+        let packs = &entitlements.user_entitlement_pack_list;
+        for pack in packs.iter().filter(|pack| !pack.is_expired_at(now)) {
+            summary.features.merge(pack.features());
+
+            // 只统计额外礼包，基础套餐在下面单独处理
             let base = &pack.entitlement_base_info;
-            let usage = &pack.usage;
-            let quota = &base.quota;
-
-            // 判断是否是额外礼包（product_type == 2）
-            if base.product_type == 2 {
-                // Extra Package
-                summary.extra_fast_request_limit = quota.premium_model_fast_request_limit;
-                // 使用 premium_model_fast_amount 作为实际使用量
-                summary.extra_fast_request_used = usage.premium_model_fast_amount;
-                summary.extra_fast_request_left =
-                    summary.extra_fast_request_limit as f64 - summary.extra_fast_request_used;
-                summary.extra_expire_time = base.end_time;
+            if !base.product_type.is_package() {
+                continue;
+            }
 
-                // 尝试获取礼包名称
-                if let Some(pkg_extra) = &base.product_extra.package_extra {
-                    if pkg_extra.package_source_type == 6 {
-                        summary.extra_package_name = "2026 Anniversary Treat".to_string();
-                    }
-                }
-            } else {
-                // Free/Pro Plan
-                summary.plan_type = if base.product_id == 0 {
-                    "Free".to_string()
-                } else {
-                    "Pro".to_string()
-                };
-                summary.reset_time = base.end_time;
-
-                summary.fast_request_limit = quota.premium_model_fast_request_limit;
-                // 使用 premium_model_fast_amount 作为实际使用量
-                summary.fast_request_used = usage.premium_model_fast_amount;
-                summary.fast_request_left =
-                    summary.fast_request_limit as f64 - summary.fast_request_used;
-
-                summary.slow_request_limit = quota.premium_model_slow_request_limit;
-                // 使用 premium_model_slow_amount 作为实际使用量
-                summary.slow_request_used = usage.premium_model_slow_amount;
-                summary.slow_request_left =
-                    summary.slow_request_limit as f64 - summary.slow_request_used;
-
-                summary.advanced_model_limit = quota.advanced_model_request_limit;
-                // 使用 advanced_model_amount 作为实际使用量
-                summary.advanced_model_used = usage.advanced_model_amount;
-                summary.advanced_model_left =
-                    summary.advanced_model_limit as f64 - summary.advanced_model_used;
-
-                summary.autocomplete_limit = quota.auto_completion_limit;
-                // 使用 auto_completion_amount 作为实际使用量
-                summary.autocomplete_used = usage.auto_completion_amount;
-                summary.autocomplete_left =
-                    summary.autocomplete_limit as f64 - summary.autocomplete_used;
+            // Extra Package（多个礼包累加）
+            summary.extra_package_count += 1;
+            summary.extra_fast_request_limit += base.quota.premium_model_fast_request_limit;
+            // 使用 premium_model_fast_amount 作为实际使用量
+            summary.extra_fast_request_used += pack.usage.premium_model_fast_amount;
+
+            // 到期时间取最早到期的礼包，名称、状态等列出所有礼包的不同取值
+            if summary.extra_expire_time == 0 || base.end_time < summary.extra_expire_time {
+                summary.extra_expire_time = base.end_time;
             }
+            if summary.extra_start_time == 0 || base.start_time < summary.extra_start_time {
+                summary.extra_start_time = base.start_time;
+            }
+            push_unique(&mut package_names, Self::pack_label(pack, catalog).name);
+            push_unique(&mut package_statuses, catalog.label(pack.status));
+            if let Some(pkg_extra) = &base.product_extra.package_extra {
+                push_unique(&mut package_sources, catalog.label(pkg_extra.package_source_type));
+                push_unique(
+                    &mut package_durations,
+                    catalog.describe_duration(pkg_extra.package_duration_type, pkg_extra.duration),
+                );
+            }
+        }
+        summary.extra_fast_request_left =
+            summary.extra_fast_request_limit as f64 - summary.extra_fast_request_used;
+        summary.extra_package_name = package_names.join(", ");
+        summary.extra_package_status = package_statuses.join(", ");
+        summary.extra_package_source = package_sources.join(", ");
+        summary.extra_package_duration = package_durations.join(", ");
+
+        let Some(pack) = Self::active_base_pack(packs, now) else {
+            return summary;
+        };
+        let base = &pack.entitlement_base_info;
+        let usage = &pack.usage;
+        let quota = &base.quota;
+
+        // Free/Pro Plan
+        let period = base
+            .product_extra
+            .subscription_extra
+            .as_ref()
+            .map(|extra| extra.period_type);
+        summary.plan_type = catalog.plan_label(base.product_id);
+        summary.plan_period = period.map(|p| catalog.label(p)).unwrap_or_default();
+        summary.plan_status = catalog.label(pack.status);
+        summary.reset_time = base.end_time;
+        summary.cycle_start_time = base.start_time;
+        summary.subscription =
+            Self::subscription_info(pack, &summary, catalog, entitlements.is_pay_freshman);
+
+        summary.fast_request_limit = quota.premium_model_fast_request_limit;
+        // 使用 premium_model_fast_amount 作为实际使用量
+        summary.fast_request_used = usage.premium_model_fast_amount;
+        summary.fast_request_left =
+            summary.fast_request_limit as f64 - summary.fast_request_used;
+
+        summary.slow_request_limit = quota.premium_model_slow_request_limit;
+        // 使用 premium_model_slow_amount 作为实际使用量
+        summary.slow_request_used = usage.premium_model_slow_amount;
+        summary.slow_request_left =
+            summary.slow_request_limit as f64 - summary.slow_request_used;
+
+        summary.advanced_model_limit = quota.advanced_model_request_limit;
+        // 使用 advanced_model_amount 作为实际使用量
+        summary.advanced_model_used = usage.advanced_model_amount;
+        summary.advanced_model_left =
+            summary.advanced_model_limit as f64 - summary.advanced_model_used;
+
+        summary.autocomplete_limit = quota.auto_completion_limit;
+        // 使用 auto_completion_amount 作为实际使用量
+        summary.autocomplete_used = usage.auto_completion_amount;
+        summary.autocomplete_left =
+            summary.autocomplete_limit as f64 - summary.autocomplete_used;
+
+        summary
+    }
+
+    /// 付费基础套餐的订阅与计费信息（免费套餐没有订阅信息）
+    fn subscription_info(
+        pack: &EntitlementPack,
+        summary: &UsageSummary,
+        catalog: &PackageCatalog,
+        is_pay_freshman: bool,
+    ) -> Option<SubscriptionInfo> {
+        let base = &pack.entitlement_base_info;
+        if base.product_type.is_package() || base.is_free_plan() {
+            return None;
         }
 
-        Ok(summary)
+        Some(SubscriptionInfo {
+            plan_type: summary.plan_type.clone(),
+            period: summary.plan_period.clone(),
+            next_billing_time: pack.next_billing_time,
+            is_last_period: pack.is_last_period,
+            charge_amount: base.charge_amount,
+            currency: catalog.label(base.currency),
+            expire_time: pack.expire_time,
+            yearly_expire_time: pack.yearly_expire_time,
+            is_pay_freshman,
+        })
+    }
+
+    /// 当前生效的基础套餐：优先未过期的付费套餐，其次未过期的免费套餐，同类中取最晚开始的；
+    /// 全部过期时取最晚结束的基础套餐
+    fn active_base_pack(packs: &[EntitlementPack], now: i64) -> Option<&EntitlementPack> {
+        let base_packs = || {
+            packs
+                .iter()
+                .filter(|pack| !pack.entitlement_base_info.product_type.is_package())
+        };
+
+        base_packs()
+            .filter(|pack| !pack.is_expired_at(now))
+            .max_by_key(|pack| {
+                let base = &pack.entitlement_base_info;
+                (!base.is_free_plan(), base.start_time)
+            })
+            .or_else(|| base_packs().max_by_key(|pack| pack.entitlement_base_info.end_time))
+    }
+
+    /// 解析配额信息为使用量明细（列出所有权益包及合计）
+    pub fn parse_entitlements_to_detail(
+        entitlements: EntitlementListResponse,
+        catalog: &PackageCatalog,
+    ) -> Result<UsageDetail> {
+        let now = chrono::Utc::now().timestamp();
+        let packs: Vec<UsagePackDetail> = entitlements
+            .user_entitlement_pack_list
+            .iter()
+            .map(|pack| Self::pack_detail(pack, catalog, now))
+            .collect();
+
+        let mut totals = UsageTotals {
+            pack_count: packs.len(),
+            ..Default::default()
+        };
+        for pack in packs.iter().filter(|p| p.counts_towards_quota) {
+            totals.active_pack_count += 1;
+            totals.fast_request.add(&pack.fast_request);
+            totals.slow_request.add(&pack.slow_request);
+            totals.advanced_model.add(&pack.advanced_model);
+            totals.autocomplete.add(&pack.autocomplete);
+            totals.features.merge(pack.features);
+        }
+
+        let summary = Self::summarize_entitlements(&entitlements, catalog, now);
+        Ok(UsageDetail { summary, packs, totals })
+    }
+
+    /// 单个权益包的使用明细
+    fn pack_detail(pack: &EntitlementPack, catalog: &PackageCatalog, now: i64) -> UsagePackDetail {
+        let base = &pack.entitlement_base_info;
+        let usage = &pack.usage;
+        let quota = &base.quota;
+        let package_extra = base.product_extra.package_extra.as_ref();
+        let label = Self::pack_label(pack, catalog);
+
+        UsagePackDetail {
+            entitlement_id: base.entitlement_id.clone(),
+            source_id: pack.source_id.clone(),
+            name: label.name,
+            description: label.description,
+            names: label.names,
+            product_type: catalog.label(base.product_type),
+            product_id: base.product_id,
+            source: package_extra
+                .map(|extra| catalog.label(extra.package_source_type))
+                .unwrap_or_default(),
+            duration: package_extra
+                .map(|extra| catalog.describe_duration(extra.package_duration_type, extra.duration))
+                .unwrap_or_default(),
+            status: catalog.label(pack.status),
+            counts_towards_quota: !pack.is_expired_at(now),
+            start_time: base.start_time,
+            end_time: base.end_time,
+            fast_request: QuotaUsage::new(
+                quota.premium_model_fast_request_limit,
+                usage.premium_model_fast_amount,
+            ),
+            slow_request: QuotaUsage::new(
+                quota.premium_model_slow_request_limit,
+                usage.premium_model_slow_amount,
+            ),
+            advanced_model: QuotaUsage::new(
+                quota.advanced_model_request_limit,
+                usage.advanced_model_amount,
+            ),
+            autocomplete: QuotaUsage::new(
+                quota.auto_completion_limit,
+                usage.auto_completion_amount,
+            ),
+            features: pack.features(),
+        }
+    }
+
+    /// 权益包名称（额外礼包从礼包目录中查找）
+    fn pack_label(pack: &EntitlementPack, catalog: &PackageCatalog) -> PackageLabel {
+        let base = &pack.entitlement_base_info;
+        if !base.product_type.is_package() {
+            return PackageLabel {
+                name: catalog.plan_label(base.product_id),
+                ..Default::default()
+            };
+        }
+
+        let extra = base.product_extra.package_extra.clone().unwrap_or_default();
+        catalog.resolve(&PackageDescriptor {
+            source_type: extra.package_source_type,
+            product_id: base.product_id,
+            duration_type: extra.package_duration_type,
+            duration: extra.duration,
+        })
     }
 
     /// 查询礼包状态
@@ -493,7 +665,7 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("查询礼包状态失败: {}", response.status()));
+            return Err(HttpStatusError::new("查询礼包状态失败", response.status()).into());
         }
 
         let data: serde_json::Value = response.json().await?;
@@ -515,9 +687,115 @@ impl TraeApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("领取礼包失败: {}", response.status()));
+            return Err(HttpStatusError::new("领取礼包失败", response.status()).into());
         }
 
         Ok(())
     }
 }
+
+/// 追加不重复的非空文本
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !value.is_empty() && !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const NOW: i64 = 1_760_000_000;
+    const DAY: i64 = 86_400;
+
+    fn pack(product_type: i32, product_id: i32, start_time: i64, end_time: i64, fast_limit: i64, fast_used: f64) -> Value {
+        json!({
+            "entitlement_base_info": {
+                "product_type": product_type,
+                "product_id": product_id,
+                "start_time": start_time,
+                "end_time": end_time,
+                "quota": { "premium_model_fast_request_limit": fast_limit },
+                "product_extra": {
+                    "package_extra": { "package_source_type": 6, "duration": 30 }
+                }
+            },
+            "usage": { "premium_model_fast_amount": fast_used },
+            "status": 1
+        })
+    }
+
+    fn summarize(packs: Vec<Value>) -> UsageSummary {
+        let entitlements = EntitlementListResponse::try_from(json!({
+            "is_pay_freshman": false,
+            "user_entitlement_pack_list": packs,
+        }))
+        .unwrap();
+        TraeApiClient::summarize_entitlements(&entitlements, PackageCatalog::bundled(), NOW)
+    }
+
+    #[test]
+    fn summary_uses_active_paid_base_pack_and_live_packages() {
+        let summary = summarize(vec![
+            // 已过期的付费套餐
+            pack(1, 7, NOW - 60 * DAY, NOW - 30 * DAY, 900, 900.0),
+            // 当前付费套餐
+            pack(1, 7, NOW - DAY, NOW + 29 * DAY, 600, 10.0),
+            // 排在后面的免费套餐不应覆盖付费套餐
+            pack(0, 0, NOW - DAY, NOW + 29 * DAY, 50, 5.0),
+            // 已过期的礼包
+            pack(2, 3, NOW - 60 * DAY, NOW - DAY, 1000, 0.0),
+            pack(2, 3, NOW - DAY, NOW + 20 * DAY, 100, 40.0),
+            pack(2, 3, NOW - DAY, NOW + 10 * DAY, 200, 0.0),
+        ]);
+
+        assert_eq!(summary.plan_type, "Plan #7");
+        assert_eq!(summary.fast_request_limit, 600);
+        assert_eq!(summary.fast_request_left, 590.0);
+        assert_eq!(summary.reset_time, NOW + 29 * DAY);
+        assert!(summary.subscription.is_some());
+
+        assert_eq!(summary.extra_package_count, 2);
+        assert_eq!(summary.extra_fast_request_limit, 300);
+        assert_eq!(summary.extra_fast_request_left, 260.0);
+        assert_eq!(summary.extra_expire_time, NOW + 10 * DAY);
+        assert_eq!(summary.extra_package_name, "2026 Anniversary Treat");
+    }
+
+    #[test]
+    fn summary_falls_back_to_latest_base_pack_when_all_expired() {
+        let summary = summarize(vec![
+            pack(1, 7, NOW - 60 * DAY, NOW - 30 * DAY, 600, 0.0),
+            pack(0, 0, NOW - 30 * DAY, NOW - DAY, 50, 0.0),
+        ]);
+
+        assert_eq!(summary.plan_type, "Free");
+        assert_eq!(summary.fast_request_limit, 50);
+        assert!(summary.subscription.is_none());
+        assert_eq!(summary.extra_package_count, 0);
+    }
+
+    #[test]
+    fn subscription_comes_from_active_paid_base_pack() {
+        let mut paid = pack(1, 7, NOW - DAY, NOW + 29 * DAY, 600, 0.0);
+        paid["next_billing_time"] = json!(NOW + 29 * DAY);
+        paid["entitlement_base_info"]["charge_amount"] = json!(1000);
+        let mut expired_paid = pack(1, 8, NOW - 60 * DAY, NOW - 30 * DAY, 600, 0.0);
+        expired_paid["next_billing_time"] = json!(NOW - 30 * DAY);
+
+        let summary = summarize(vec![
+            expired_paid.clone(),
+            paid,
+            pack(0, 0, NOW - DAY, NOW + 29 * DAY, 50, 0.0),
+        ]);
+        let subscription = summary.subscription.expect("付费套餐应有订阅信息");
+        assert_eq!(subscription.plan_type, "Plan #7");
+        assert_eq!(subscription.next_billing_time, NOW + 29 * DAY);
+        assert_eq!(subscription.charge_amount, 1000);
+
+        // 付费套餐已过期、只剩免费套餐时不返回订阅信息
+        let summary = summarize(vec![expired_paid, pack(0, 0, NOW - DAY, NOW + 29 * DAY, 50, 0.0)]);
+        assert!(summary.subscription.is_none());
+    }
+}
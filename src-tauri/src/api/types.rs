@@ -1,4 +1,9 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::catalog::LocalizedText;
+use super::{Currency, PackStatus, PackageDurationType, PackageSourceType, PeriodType, ProductType};
 
 /// JWT Token 解析后的原始数据
 #[derive(Debug, Clone, Deserialize)]
@@ -103,75 +108,173 @@ pub struct UserInfoResult {
     pub store_country: Option<String>,
 }
 
+/// 未识别的字段（接口新增字段时保留原样，便于排查）
+pub type UnknownFields = HashMap<String, Value>;
+
 /// 用户配额/使用量响应
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// 宽松解析：单个礼包解析失败只会记录到 `pack_errors`，不会影响其他礼包。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "Value")]
 pub struct EntitlementListResponse {
     pub is_pay_freshman: bool,
     pub user_entitlement_pack_list: Vec<EntitlementPack>,
+    /// 解析失败的礼包及原因
+    #[serde(skip)]
+    pub pack_errors: Vec<String>,
+    /// 原始响应（用于诊断）
+    #[serde(skip)]
+    pub raw: Value,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TryFrom<Value> for EntitlementListResponse {
+    type Error = String;
+
+    fn try_from(raw: Value) -> std::result::Result<Self, Self::Error> {
+        let object = raw.as_object().ok_or("配额响应不是 JSON 对象")?;
+        let packs = object
+            .get("user_entitlement_pack_list")
+            .and_then(|v| v.as_array())
+            .ok_or("配额响应缺少 user_entitlement_pack_list")?;
+
+        let mut user_entitlement_pack_list = Vec::with_capacity(packs.len());
+        let mut pack_errors = Vec::new();
+        for (index, pack) in packs.iter().enumerate() {
+            match EntitlementPack::deserialize(pack) {
+                Ok(pack) => user_entitlement_pack_list.push(pack),
+                Err(e) => pack_errors.push(format!("礼包 #{} 解析失败: {}", index, e)),
+            }
+        }
+
+        let extra = object
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "is_pay_freshman" | "user_entitlement_pack_list"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        Ok(Self {
+            is_pay_freshman: object
+                .get("is_pay_freshman")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            user_entitlement_pack_list,
+            pack_errors,
+            raw,
+            extra,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EntitlementPack {
     pub entitlement_base_info: EntitlementBaseInfo,
     pub expire_time: i64,
     pub is_last_period: bool,
     pub next_billing_time: i64,
     pub source_id: String,
-    pub status: i32,
+    pub status: PackStatus,
     pub usage: UsageInfo,
     pub yearly_expire_time: i64,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl EntitlementPack {
+    /// 权益包提供的功能开关（基础配额与订阅/礼包配额合并）
+    pub fn features(&self) -> FeatureFlags {
+        let base = &self.entitlement_base_info;
+        let mut features = FeatureFlags::from_quota(&base.quota);
+        if let Some(extra) = &base.product_extra.subscription_extra {
+            features.merge(FeatureFlags::from_quota(&extra.quota));
+        }
+        if let Some(extra) = &base.product_extra.package_extra {
+            features.merge(FeatureFlags::from_quota(&extra.quota));
+        }
+        features
+    }
+
+    /// 在指定时间（秒）是否已过结束时间（结束时间为 0 表示不限期）
+    ///
+    /// `status` 的取值含义未知，因此按起止时间判断权益包是否仍然有效。
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        let end_time = self.entitlement_base_info.end_time;
+        end_time > 0 && end_time <= now
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EntitlementBaseInfo {
     pub charge_amount: i64,
-    pub currency: i32,
+    pub currency: Currency,
     pub end_time: i64,
     pub entitlement_id: String,
     pub product_extra: ProductExtra,
     pub product_id: i32,
-    pub product_type: i32,
+    pub product_type: ProductType,
     pub quota: Quota,
     pub start_time: i64,
     pub user_id: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl EntitlementBaseInfo {
+    /// 是否为免费套餐（沿用原有解析逻辑：product_id 为 0 表示免费套餐）
+    pub fn is_free_plan(&self) -> bool {
+        self.product_id == 0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ProductExtra {
-    #[serde(default)]
     pub package_extra: Option<PackageExtra>,
-    #[serde(default)]
     pub subscription_extra: Option<SubscriptionExtra>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PackageExtra {
     pub duration: i32,
-    pub package_duration_type: i32,
-    pub package_source_type: i32,
+    pub package_duration_type: PackageDurationType,
+    pub package_source_type: PackageSourceType,
     pub quota: Quota,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SubscriptionExtra {
-    pub period_type: i32,
+    pub period_type: PeriodType,
     pub quota: Quota,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Quota {
     pub advanced_model_request_limit: i64,
     pub auto_completion_limit: i64,
     pub enable_solo_builder: bool,
-    #[serde(default)]
     pub enable_solo_builder_v1: bool,
     pub enable_solo_coder: bool,
     pub enable_super_model: bool,
     pub premium_model_fast_request_limit: i64,
     pub premium_model_slow_request_limit: i64,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UsageInfo {
     pub advanced_model_amount: f64,
     pub advanced_model_request_usage: f64,
@@ -182,6 +285,8 @@ pub struct UsageInfo {
     pub premium_model_fast_request_usage: f64,
     pub premium_model_slow_amount: f64,
     pub premium_model_slow_request_usage: f64,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 /// 使用记录查询响应
@@ -216,7 +321,16 @@ pub struct UsageExtraInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageSummary {
     pub plan_type: String,
+    /// 订阅周期（period_type 在礼包目录中的名称，未收录时如 "Billing period #1"）
+    #[serde(default)]
+    pub plan_period: String,
+    /// 基础套餐状态
+    #[serde(default)]
+    pub plan_status: String,
     pub reset_time: i64,
+    /// 当前计费周期开始时间
+    #[serde(default)]
+    pub cycle_start_time: i64,
 
     // Fast Request
     pub fast_request_used: f64,
@@ -228,7 +342,30 @@ pub struct UsageSummary {
     pub extra_fast_request_limit: i64,
     pub extra_fast_request_left: f64,
     pub extra_expire_time: i64,
+    /// 最早生效的礼包的开始时间
+    #[serde(default)]
+    pub extra_start_time: i64,
     pub extra_package_name: String,
+    /// 礼包来源
+    #[serde(default)]
+    pub extra_package_source: String,
+    /// 礼包时长，如 "30 Days"
+    #[serde(default)]
+    pub extra_package_duration: String,
+    /// 礼包状态
+    #[serde(default)]
+    pub extra_package_status: String,
+    /// 计入额度的礼包数量
+    #[serde(default)]
+    pub extra_package_count: usize,
+
+    /// 订阅与计费信息（免费套餐为空）
+    #[serde(default)]
+    pub subscription: Option<SubscriptionInfo>,
+
+    /// 所有有效权益包合并后的功能开关
+    #[serde(default)]
+    pub features: FeatureFlags,
 
     // Slow Request
     pub slow_request_used: f64,
@@ -244,13 +381,31 @@ pub struct UsageSummary {
     pub autocomplete_used: f64,
     pub autocomplete_limit: i64,
     pub autocomplete_left: f64,
+
+    /// 刷新失败时返回的是缓存数据，这里说明数据的时间和失败原因（实时数据为空）
+    #[serde(default)]
+    pub stale: Option<UsageStaleness>,
+}
+
+/// 过期使用量的说明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageStaleness {
+    /// 缓存数据的拉取时间
+    pub fetched_at: i64,
+    /// 缓存数据的时长（秒）
+    pub age_seconds: i64,
+    /// 导致无法刷新的错误
+    pub error: String,
 }
 
 impl Default for UsageSummary {
     fn default() -> Self {
         Self {
             plan_type: "Free".to_string(),
+            plan_period: String::new(),
+            plan_status: String::new(),
             reset_time: 0,
+            cycle_start_time: 0,
             fast_request_used: 0.0,
             fast_request_limit: 10,
             fast_request_left: 10.0,
@@ -258,7 +413,14 @@ impl Default for UsageSummary {
             extra_fast_request_limit: 0,
             extra_fast_request_left: 0.0,
             extra_expire_time: 0,
+            extra_start_time: 0,
             extra_package_name: String::new(),
+            extra_package_source: String::new(),
+            extra_package_duration: String::new(),
+            extra_package_status: String::new(),
+            extra_package_count: 0,
+            subscription: None,
+            features: FeatureFlags::default(),
             slow_request_used: 0.0,
             slow_request_limit: 50,
             slow_request_left: 50.0,
@@ -268,6 +430,157 @@ impl Default for UsageSummary {
             autocomplete_used: 0.0,
             autocomplete_limit: 5000,
             autocomplete_left: 5000.0,
+            stale: None,
+        }
+    }
+}
+
+/// 账号可用的功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    SoloBuilder,
+    SoloBuilderV1,
+    SoloCoder,
+    SuperModel,
+}
+
+/// 功能开关（来自 Quota 中的 enable_* 字段）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureFlags {
+    pub solo_builder: bool,
+    pub solo_builder_v1: bool,
+    pub solo_coder: bool,
+    pub super_model: bool,
+}
+
+impl FeatureFlags {
+    /// 从配额中读取功能开关
+    pub fn from_quota(quota: &Quota) -> Self {
+        Self {
+            solo_builder: quota.enable_solo_builder,
+            solo_builder_v1: quota.enable_solo_builder_v1,
+            solo_coder: quota.enable_solo_coder,
+            super_model: quota.enable_super_model,
+        }
+    }
+
+    /// 合并另一组开关（任一开启即开启）
+    pub fn merge(&mut self, other: FeatureFlags) {
+        self.solo_builder |= other.solo_builder;
+        self.solo_builder_v1 |= other.solo_builder_v1;
+        self.solo_coder |= other.solo_coder;
+        self.super_model |= other.super_model;
+    }
+
+    /// 是否开启指定功能
+    pub fn has(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::SoloBuilder => self.solo_builder,
+            Feature::SoloBuilderV1 => self.solo_builder_v1,
+            Feature::SoloCoder => self.solo_coder,
+            Feature::SuperModel => self.super_model,
         }
     }
 }
+
+/// 订阅与计费信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubscriptionInfo {
+    pub plan_type: String,
+    /// 订阅周期（period_type 在礼包目录中的名称，未收录时如 "Billing period #1"）
+    pub period: String,
+    /// 下次扣费时间（0 表示不再续费）
+    pub next_billing_time: i64,
+    /// 是否为最后一个计费周期（已取消自动续费）
+    pub is_last_period: bool,
+    /// 本周期扣费金额（接口原始值）
+    pub charge_amount: i64,
+    pub currency: String,
+    /// 当前周期结束时间
+    pub expire_time: i64,
+    /// 年付订阅到期时间（非年付为 0）
+    pub yearly_expire_time: i64,
+    /// 是否为首次付费用户
+    pub is_pay_freshman: bool,
+}
+
+impl SubscriptionInfo {
+    /// 是否会自动续费
+    pub fn will_renew(&self) -> bool {
+        !self.is_last_period && self.next_billing_time > 0
+    }
+}
+
+/// 单项配额的使用情况
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub limit: i64,
+    pub used: f64,
+    pub left: f64,
+}
+
+impl QuotaUsage {
+    pub fn new(limit: i64, used: f64) -> Self {
+        Self {
+            limit,
+            used,
+            left: limit as f64 - used,
+        }
+    }
+
+    /// 累加另一项配额
+    pub fn add(&mut self, other: &QuotaUsage) {
+        self.limit += other.limit;
+        self.used += other.used;
+        self.left += other.left;
+    }
+}
+
+/// 单个权益包的使用明细
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsagePackDetail {
+    pub entitlement_id: String,
+    pub source_id: String,
+    pub name: String,
+    pub description: String,
+    /// 各语言的名称
+    pub names: LocalizedText,
+    pub product_type: String,
+    pub product_id: i32,
+    /// 礼包来源（仅额外礼包）
+    pub source: String,
+    /// 礼包时长（仅额外礼包）
+    pub duration: String,
+    pub status: String,
+    /// 是否计入额度（已过期/已取消的权益包不计入）
+    pub counts_towards_quota: bool,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub fast_request: QuotaUsage,
+    pub slow_request: QuotaUsage,
+    pub advanced_model: QuotaUsage,
+    pub autocomplete: QuotaUsage,
+    pub features: FeatureFlags,
+}
+
+/// 所有权益包的合计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub pack_count: usize,
+    pub active_pack_count: usize,
+    pub fast_request: QuotaUsage,
+    pub slow_request: QuotaUsage,
+    pub advanced_model: QuotaUsage,
+    pub autocomplete: QuotaUsage,
+    pub features: FeatureFlags,
+}
+
+/// 使用量明细（汇总 + 每个权益包）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageDetail {
+    pub summary: UsageSummary,
+    pub packs: Vec<UsagePackDetail>,
+    pub totals: UsageTotals,
+}
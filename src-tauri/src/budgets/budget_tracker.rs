@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::types::*;
+use crate::api::{SubscriptionInfo, UsageSession};
+
+/// 预算数据文件名
+const BUDGETS_FILE: &str = "budgets.json";
+
+/// 预算提醒方式（如系统通知）
+pub trait BudgetNotifier: Send + Sync {
+    fn notify_budget(&self, status: &BudgetStatus);
+}
+
+/// 已提醒的最高级别，每月重新计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NotifiedLevel {
+    account_id: String,
+    month: String,
+    level: BudgetLevel,
+}
+
+/// 持久化的预算数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct BudgetStore {
+    budgets: Vec<Budget>,
+    /// 每个账号最近一次计算的预算使用情况
+    statuses: Vec<BudgetStatus>,
+    notified: Vec<NotifiedLevel>,
+}
+
+/// 预算管理：保存预算、计算支出并在超过阈值时提醒
+pub struct BudgetTracker {
+    store: Mutex<BudgetStore>,
+    path: PathBuf,
+    notifier: Mutex<Option<Arc<dyn BudgetNotifier>>>,
+}
+
+impl BudgetTracker {
+    /// 打开数据目录下的预算数据
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(BUDGETS_FILE);
+        let store = if path.exists() {
+            fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    println!("[WARN] 预算数据文件无效，已忽略: {}", e);
+                    BudgetStore::default()
+                })
+        } else {
+            BudgetStore::default()
+        };
+
+        Self {
+            store: Mutex::new(store),
+            path,
+            notifier: Mutex::new(None),
+        }
+    }
+
+    /// 设置提醒方式
+    pub fn set_notifier(&self, notifier: Arc<dyn BudgetNotifier>) {
+        if let Ok(mut current) = self.notifier.lock() {
+            *current = Some(notifier);
+        }
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, BudgetStore>> {
+        self.store.lock().map_err(|_| anyhow!("预算数据不可用"))
+    }
+
+    fn save(&self, store: &BudgetStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(store)?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// 获取全部预算
+    pub fn budgets(&self) -> Result<Vec<Budget>> {
+        Ok(self.store()?.budgets.clone())
+    }
+
+    /// 获取账号的预算
+    pub fn budget(&self, account_id: &str) -> Result<Option<Budget>> {
+        Ok(self.store()?.budgets.iter().find(|b| b.account_id == account_id).cloned())
+    }
+
+    /// 设置账号的预算（已存在时覆盖）
+    pub fn set_budget(&self, budget: Budget) -> Result<()> {
+        if budget.monthly_limit <= 0.0 {
+            return Err(anyhow!("预算金额必须大于 0"));
+        }
+        if budget.warning_percent <= 0.0 || budget.critical_percent < budget.warning_percent {
+            return Err(anyhow!("提醒百分比必须满足 0 < 提醒 ≤ 严重"));
+        }
+        if !budget.is_usd() && !budget.usd_rate.is_some_and(|rate| rate > 0.0) {
+            return Err(anyhow!("使用事件的费用以美元计，{} 预算需要填写大于 0 的美元汇率", budget.currency));
+        }
+        if let Some(charge) = budget.subscription {
+            if charge.amount_divisor <= 0.0 || charge.months_per_charge == 0 {
+                return Err(anyhow!("订阅费用的换算系数和扣费月数必须大于 0"));
+            }
+        }
+
+        let mut store = self.store()?;
+        store.budgets.retain(|b| b.account_id != budget.account_id);
+        store.statuses.retain(|s| s.account_id != budget.account_id);
+        store.budgets.push(budget);
+        self.save(&store)
+    }
+
+    /// 删除账号的预算
+    pub fn remove_budget(&self, account_id: &str) -> Result<()> {
+        let mut store = self.store()?;
+        store.budgets.retain(|b| b.account_id != account_id);
+        store.statuses.retain(|s| s.account_id != account_id);
+        store.notified.retain(|n| n.account_id != account_id);
+        self.save(&store)
+    }
+
+    /// 账号最近一次计算的预算使用情况
+    pub fn status(&self, account_id: &str) -> Option<BudgetStatus> {
+        let store = self.store().ok()?;
+        store.statuses.iter().find(|s| s.account_id == account_id).cloned()
+    }
+
+    /// 保存预算使用情况，级别首次升高到提醒或严重时发出提醒
+    pub fn record_status(&self, status: &BudgetStatus) -> Result<()> {
+        let mut store = self.store()?;
+        store.statuses.retain(|s| s.account_id != status.account_id);
+        store.statuses.push(status.clone());
+
+        let notified = store
+            .notified
+            .iter()
+            .find(|n| n.account_id == status.account_id && n.month == status.month)
+            .map_or(BudgetLevel::Ok, |n| n.level);
+        let crossed = status.level > notified;
+        if crossed {
+            store.notified.retain(|n| n.account_id != status.account_id);
+            store.notified.push(NotifiedLevel {
+                account_id: status.account_id.clone(),
+                month: status.month.clone(),
+                level: status.level,
+            });
+        }
+        self.save(&store)?;
+        drop(store);
+
+        if crossed {
+            if let Some(notifier) = self.notifier.lock().ok().and_then(|n| n.clone()) {
+                notifier.notify_budget(status);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 根据本月的使用事件和订阅信息计算预算使用情况
+pub fn compute_status(
+    budget: &Budget,
+    month: &str,
+    period: (i64, i64),
+    sessions: &[UsageSession],
+    subscription: Option<&SubscriptionInfo>,
+    now: i64,
+) -> BudgetStatus {
+    // 使用事件的 cost_money_float 以美元计（与使用记录页面的 "Bill (USD)" 一致），按汇率换算为预算币种
+    let event_spend: f64 = sessions.iter().map(|s| s.cost_money_float).sum::<f64>() * budget.usd_to_budget_rate();
+    let subscription_spend = match (budget.subscription, subscription) {
+        (Some(charge), Some(subscription)) => charge.monthly_amount(subscription.charge_amount),
+        _ => 0.0,
+    };
+    let spent = event_spend + subscription_spend;
+    let percent = spent / budget.monthly_limit * 100.0;
+
+    let level = if percent >= budget.critical_percent {
+        BudgetLevel::Critical
+    } else if percent >= budget.warning_percent {
+        BudgetLevel::Warning
+    } else {
+        BudgetLevel::Ok
+    };
+
+    BudgetStatus {
+        account_id: budget.account_id.clone(),
+        month: month.to_string(),
+        period_start: period.0,
+        period_end: period.1,
+        limit: budget.monthly_limit,
+        currency: budget.currency.clone(),
+        spent,
+        event_spend,
+        subscription_spend,
+        event_count: sessions.len(),
+        percent,
+        level,
+        computed_at: now,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(subscription: Option<SubscriptionCharge>) -> Budget {
+        Budget {
+            account_id: "acc".to_string(),
+            monthly_limit: 50.0,
+            currency: "USD".to_string(),
+            usd_rate: None,
+            warning_percent: 80.0,
+            critical_percent: 100.0,
+            subscription,
+            enabled: true,
+        }
+    }
+
+    fn session(cost: f64) -> UsageSession {
+        serde_json::from_value(serde_json::json!({
+            "session_id": "s", "usage_time": 0, "mode": "", "model_name": "", "amount_float": 1.0,
+            "cost_money_float": cost, "use_max_mode": false, "product_type_list": [],
+            "extra_info": { "cache_read_token": 0, "cache_write_token": 0, "input_token": 0, "output_token": 0 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn subscription_spend_uses_configured_conversion() {
+        let sessions = vec![session(12.5), session(7.5)];
+        let subscription = SubscriptionInfo { charge_amount: 12000, ..Default::default() };
+
+        let status = compute_status(&budget(None), "2026-10", (0, 1), &sessions, Some(&subscription), 1);
+        assert_eq!(status.event_spend, 20.0);
+        assert_eq!(status.subscription_spend, 0.0);
+        assert_eq!(status.level, BudgetLevel::Ok);
+
+        let yearly = SubscriptionCharge { amount_divisor: 100.0, months_per_charge: 12 };
+        let status = compute_status(&budget(Some(yearly)), "2026-10", (0, 1), &sessions, Some(&subscription), 1);
+        assert_eq!(status.subscription_spend, 10.0);
+        assert_eq!(status.spent, 30.0);
+        assert_eq!(status.percent, 60.0);
+    }
+
+    #[test]
+    fn non_usd_budget_converts_event_spend() {
+        let cny = Budget { currency: "CNY".to_string(), usd_rate: Some(7.0), monthly_limit: 140.0, ..budget(None) };
+        let status = compute_status(&cny, "2026-10", (0, 1), &[session(10.0), session(6.0)], None, 1);
+        assert_eq!(status.event_spend, 112.0);
+        assert_eq!(status.percent, 80.0);
+        assert_eq!(status.level, BudgetLevel::Warning);
+        assert_eq!(status.currency, "CNY");
+    }
+
+    #[test]
+    fn non_usd_budget_requires_rate() {
+        let dir = std::env::temp_dir().join(format!("trae-budgets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tracker = BudgetTracker::open(&dir);
+
+        let eur = Budget { currency: "EUR".to_string(), ..budget(None) };
+        assert!(tracker.set_budget(eur.clone()).is_err());
+        assert!(tracker.set_budget(Budget { usd_rate: Some(0.0), ..eur.clone() }).is_err());
+        tracker.set_budget(Budget { usd_rate: Some(0.9), ..eur }).unwrap();
+        tracker.set_budget(budget(None)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,5 @@
+pub mod budget_tracker;
+pub mod types;
+
+pub use budget_tracker::{compute_status, BudgetNotifier, BudgetTracker};
+pub use types::*;
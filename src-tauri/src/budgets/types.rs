@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+/// 账号月度预算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub account_id: String,
+    /// 每月预算金额
+    pub monthly_limit: f64,
+    /// 币种，如 USD
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// 1 美元折合预算币种的金额（使用事件的费用以美元计），非 USD 预算必须填写
+    #[serde(default)]
+    pub usd_rate: Option<f64>,
+    /// 达到该百分比时提醒
+    #[serde(default = "default_warning_percent")]
+    pub warning_percent: f64,
+    /// 达到该百分比时严重提醒
+    #[serde(default = "default_critical_percent")]
+    pub critical_percent: f64,
+    /// 计入订阅费用时的换算方式，为空时不计入
+    #[serde(default)]
+    pub subscription: Option<SubscriptionCharge>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Budget {
+    pub fn is_usd(&self) -> bool {
+        self.currency.trim().eq_ignore_ascii_case("USD")
+    }
+
+    /// 美元金额换算为预算币种的汇率
+    pub fn usd_to_budget_rate(&self) -> f64 {
+        if self.is_usd() {
+            1.0
+        } else {
+            self.usd_rate.unwrap_or(1.0)
+        }
+    }
+}
+
+/// 订阅费用换算方式
+///
+/// 接口没有说明 `charge_amount` 的单位、币种和扣费周期（`currency`、`period_type` 的取值含义未知），
+/// 因此由用户对照账单填写，不做推测。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SubscriptionCharge {
+    /// `charge_amount` 除以该值得到预算币种的金额（如以分为单位时填 100）
+    pub amount_divisor: f64,
+    /// 每次扣费覆盖的月数，按月分摊（如年付填 12）
+    pub months_per_charge: u32,
+}
+
+impl SubscriptionCharge {
+    /// 每月分摊的订阅费用
+    pub fn monthly_amount(&self, charge_amount: i64) -> f64 {
+        charge_amount as f64 / self.amount_divisor / self.months_per_charge.max(1) as f64
+    }
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_warning_percent() -> f64 {
+    80.0
+}
+
+fn default_critical_percent() -> f64 {
+    100.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 预算级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLevel {
+    #[default]
+    Ok,
+    Warning,
+    Critical,
+}
+
+/// 账号本月的预算使用情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub account_id: String,
+    /// 月份，如 2026-10
+    pub month: String,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub limit: f64,
+    pub currency: String,
+    /// 总支出 = 使用事件费用 + 订阅费用
+    pub spent: f64,
+    pub event_spend: f64,
+    pub subscription_spend: f64,
+    pub event_count: usize,
+    /// 已用百分比
+    pub percent: f64,
+    pub level: BudgetLevel,
+    pub computed_at: i64,
+}
@@ -0,0 +1,5 @@
+pub mod types;
+pub mod usage_history;
+
+pub use types::*;
+pub use usage_history::UsageHistory;
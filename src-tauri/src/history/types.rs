@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// 一次使用量快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    pub account_id: String,
+    /// 记录时间（秒）
+    pub captured_at: i64,
+    pub plan_type: String,
+    pub reset_time: i64,
+    pub fast_request_used: f64,
+    pub fast_request_limit: i64,
+    pub extra_fast_request_used: f64,
+    pub extra_fast_request_limit: i64,
+    pub slow_request_used: f64,
+    pub slow_request_limit: i64,
+    pub advanced_model_used: f64,
+    pub advanced_model_limit: i64,
+    pub autocomplete_used: f64,
+    pub autocomplete_limit: i64,
+}
+
+/// 快照保留与降采样策略
+///
+/// 最近 `raw_days` 天保留全部快照；之后到 `hourly_days` 天每小时保留最后一条；
+/// 之后到 `daily_days` 天每天保留最后一条；更早的快照删除。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub raw_days: i64,
+    pub hourly_days: i64,
+    pub daily_days: i64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            raw_days: 7,
+            hourly_days: 30,
+            daily_days: 365,
+        }
+    }
+}
+
+/// 使用量历史设置（快照在每次刷新使用量时记录，频率由后台刷新计划决定）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    pub retention: RetentionPolicy,
+}
+
+/// 执行保留策略的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionReport {
+    /// 降采样删除的快照数
+    pub downsampled: usize,
+    /// 超出保留期删除的快照数
+    pub expired: usize,
+}
+
+/// 使用事件同步状态（已连续同步的时间范围，秒）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    pub account_id: String,
+    pub synced_from: i64,
+    /// 同步水位线，之后的同步从这里开始
+    pub synced_to: i64,
+    pub last_synced_at: i64,
+}
+
+/// 使用事件同步进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncProgress {
+    pub account_id: String,
+    pub page_num: i32,
+    /// 已拉取的事件数
+    pub fetched: usize,
+    /// 接口返回的事件总数
+    pub total: i64,
+    /// 新写入的事件数（已去重）
+    pub inserted: usize,
+}
+
+/// 使用事件同步结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub account_id: String,
+    /// 实际同步的起始时间（增量同步时为上次水位线）
+    pub start_time: i64,
+    pub end_time: i64,
+    pub pages: i32,
+    pub fetched: usize,
+    pub inserted: usize,
+    pub state: SyncState,
+}
@@ -0,0 +1,551 @@
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, Row};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use super::types::*;
+use crate::api::{UsageExtraInfo, UsageSession, UsageSummary};
+
+/// 历史数据库文件名
+const DATABASE_FILE: &str = "usage_history.db";
+/// 历史设置文件名
+const SETTINGS_FILE: &str = "history_settings.json";
+
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+
+const SNAPSHOT_COLUMNS: &str = "account_id, captured_at, plan_type, reset_time, \
+    fast_request_used, fast_request_limit, extra_fast_request_used, extra_fast_request_limit, \
+    slow_request_used, slow_request_limit, advanced_model_used, advanced_model_limit, \
+    autocomplete_used, autocomplete_limit";
+
+const SESSION_COLUMNS: &str = "session_id, usage_time, mode, model_name, amount_float, \
+    cost_money_float, use_max_mode, product_type_list, cache_read_token, cache_write_token, \
+    input_token, output_token";
+
+/// 本地使用量历史（SQLite）
+pub struct UsageHistory {
+    conn: Mutex<Connection>,
+    settings: Mutex<HistorySettings>,
+    settings_path: PathBuf,
+}
+
+impl UsageHistory {
+    /// 打开（或创建）数据目录下的历史数据库
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        let conn = Connection::open(data_dir.join(DATABASE_FILE))
+            .map_err(|e| anyhow!("打开历史数据库失败: {}", e))?;
+        Self::with_connection(conn, data_dir)
+    }
+
+    /// 使用内存数据库（不写入磁盘，用于数据库文件无法打开时继续运行）
+    pub fn open_in_memory(data_dir: &Path) -> Result<Self> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| anyhow!("创建内存数据库失败: {}", e))?;
+        Self::with_connection(conn, data_dir)
+    }
+
+    fn with_connection(conn: Connection, data_dir: &Path) -> Result<Self> {
+        Self::init_schema(&conn)?;
+
+        let settings_path = data_dir.join(SETTINGS_FILE);
+        let settings = if settings_path.exists() {
+            let content = fs::read_to_string(&settings_path)?;
+            serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!("[WARN] 历史设置文件无效，使用默认设置: {}", e);
+                HistorySettings::default()
+            })
+        } else {
+            HistorySettings::default()
+        };
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            settings: Mutex::new(settings),
+            settings_path,
+        })
+    }
+
+    /// 初始化表结构
+    fn init_schema(conn: &Connection) -> Result<()> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_id TEXT NOT NULL,
+                captured_at INTEGER NOT NULL,
+                plan_type TEXT NOT NULL,
+                reset_time INTEGER NOT NULL,
+                fast_request_used REAL NOT NULL,
+                fast_request_limit INTEGER NOT NULL,
+                extra_fast_request_used REAL NOT NULL,
+                extra_fast_request_limit INTEGER NOT NULL,
+                slow_request_used REAL NOT NULL,
+                slow_request_limit INTEGER NOT NULL,
+                advanced_model_used REAL NOT NULL,
+                advanced_model_limit INTEGER NOT NULL,
+                autocomplete_used REAL NOT NULL,
+                autocomplete_limit INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_usage_snapshots_account_time
+                ON usage_snapshots (account_id, captured_at);
+            CREATE TABLE IF NOT EXISTS usage_sessions (
+                account_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                usage_time INTEGER NOT NULL,
+                mode TEXT NOT NULL,
+                model_name TEXT NOT NULL,
+                amount_float REAL NOT NULL,
+                cost_money_float REAL NOT NULL,
+                use_max_mode INTEGER NOT NULL,
+                product_type_list TEXT NOT NULL,
+                cache_read_token INTEGER NOT NULL,
+                cache_write_token INTEGER NOT NULL,
+                input_token INTEGER NOT NULL,
+                output_token INTEGER NOT NULL,
+                PRIMARY KEY (account_id, session_id, usage_time)
+            );
+            CREATE TABLE IF NOT EXISTS usage_sync_state (
+                account_id TEXT PRIMARY KEY,
+                synced_from INTEGER NOT NULL,
+                synced_to INTEGER NOT NULL,
+                last_synced_at INTEGER NOT NULL
+            );",
+        )?;
+        Ok(())
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| anyhow!("历史数据库不可用"))
+    }
+
+    /// 记录一次使用量快照
+    pub fn record_snapshot(&self, account_id: &str, summary: &UsageSummary) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            &format!(
+                "INSERT INTO usage_snapshots ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                SNAPSHOT_COLUMNS
+            ),
+            params![
+                account_id,
+                chrono::Utc::now().timestamp(),
+                summary.plan_type,
+                summary.reset_time,
+                summary.fast_request_used,
+                summary.fast_request_limit,
+                summary.extra_fast_request_used,
+                summary.extra_fast_request_limit,
+                summary.slow_request_used,
+                summary.slow_request_limit,
+                summary.advanced_model_used,
+                summary.advanced_model_limit,
+                summary.autocomplete_used,
+                summary.autocomplete_limit,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 查询时间范围内的快照
+    ///
+    /// 指定 `bucket_seconds` 时每个时间段只返回最后一条快照（降采样）。
+    pub fn query_snapshots(
+        &self,
+        account_id: &str,
+        start_time: i64,
+        end_time: i64,
+        bucket_seconds: Option<i64>,
+    ) -> Result<Vec<UsageSnapshot>> {
+        let conn = self.conn()?;
+        let rows = match bucket_seconds.filter(|b| *b > 0) {
+            Some(bucket) => {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM usage_snapshots WHERE id IN (
+                        SELECT MAX(id) FROM usage_snapshots
+                        WHERE account_id = ?1 AND captured_at BETWEEN ?2 AND ?3
+                        GROUP BY captured_at / ?4
+                    ) ORDER BY captured_at",
+                    SNAPSHOT_COLUMNS
+                ))?;
+                let rows = stmt
+                    .query_map(params![account_id, start_time, end_time, bucket], Self::snapshot_from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows
+            }
+            None => {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM usage_snapshots
+                    WHERE account_id = ?1 AND captured_at BETWEEN ?2 AND ?3
+                    ORDER BY captured_at",
+                    SNAPSHOT_COLUMNS
+                ))?;
+                let rows = stmt
+                    .query_map(params![account_id, start_time, end_time], Self::snapshot_from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows
+            }
+        };
+        Ok(rows)
+    }
+
+    /// 账号最近一次快照
+    pub fn latest_snapshot(&self, account_id: &str) -> Result<Option<UsageSnapshot>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM usage_snapshots WHERE account_id = ?1 ORDER BY captured_at DESC, id DESC LIMIT 1",
+            SNAPSHOT_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map(params![account_id], Self::snapshot_from_row)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    fn snapshot_from_row(row: &Row<'_>) -> rusqlite::Result<UsageSnapshot> {
+        Ok(UsageSnapshot {
+            account_id: row.get(0)?,
+            captured_at: row.get(1)?,
+            plan_type: row.get(2)?,
+            reset_time: row.get(3)?,
+            fast_request_used: row.get(4)?,
+            fast_request_limit: row.get(5)?,
+            extra_fast_request_used: row.get(6)?,
+            extra_fast_request_limit: row.get(7)?,
+            slow_request_used: row.get(8)?,
+            slow_request_limit: row.get(9)?,
+            advanced_model_used: row.get(10)?,
+            advanced_model_limit: row.get(11)?,
+            autocomplete_used: row.get(12)?,
+            autocomplete_limit: row.get(13)?,
+        })
+    }
+
+    /// 按保留策略降采样并删除过期快照
+    pub fn apply_retention(&self, now: i64) -> Result<RetentionReport> {
+        let policy = self.settings().retention;
+        let raw_cutoff = now - policy.raw_days * DAY;
+        let hourly_cutoff = now - policy.hourly_days * DAY;
+        let daily_cutoff = now - policy.daily_days * DAY;
+
+        let conn = self.conn()?;
+        let expired = conn.execute(
+            "DELETE FROM usage_snapshots WHERE captured_at < ?1",
+            params![daily_cutoff],
+        )?;
+
+        let mut downsampled = 0;
+        for (from, to, bucket) in [(hourly_cutoff, raw_cutoff, HOUR), (daily_cutoff, hourly_cutoff, DAY)] {
+            downsampled += conn.execute(
+                "DELETE FROM usage_snapshots
+                WHERE captured_at >= ?1 AND captured_at < ?2 AND id NOT IN (
+                    SELECT MAX(id) FROM usage_snapshots
+                    WHERE captured_at >= ?1 AND captured_at < ?2
+                    GROUP BY account_id, captured_at / ?3
+                )",
+                params![from, to, bucket],
+            )?;
+        }
+
+        Ok(RetentionReport { downsampled, expired })
+    }
+
+    /// 写入使用事件，已存在的（同一 session_id + usage_time）忽略，返回新写入的条数
+    pub fn insert_sessions(&self, account_id: &str, sessions: &[UsageSession]) -> Result<usize> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT OR IGNORE INTO usage_sessions (account_id, {})
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                SESSION_COLUMNS
+            ))?;
+            for session in sessions {
+                inserted += stmt.execute(params![
+                    account_id,
+                    session.session_id,
+                    session.usage_time,
+                    session.mode,
+                    session.model_name,
+                    session.amount_float,
+                    session.cost_money_float,
+                    session.use_max_mode,
+                    serde_json::to_string(&session.product_type_list)?,
+                    session.extra_info.cache_read_token,
+                    session.extra_info.cache_write_token,
+                    session.extra_info.input_token,
+                    session.extra_info.output_token,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// 查询本地已同步的使用事件（按时间倒序）
+    pub fn query_sessions(&self, account_id: &str, start_time: i64, end_time: i64) -> Result<Vec<UsageSession>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM usage_sessions
+            WHERE account_id = ?1 AND usage_time BETWEEN ?2 AND ?3
+            ORDER BY usage_time DESC",
+            SESSION_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map(params![account_id, start_time, end_time], Self::session_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    fn session_from_row(row: &Row<'_>) -> rusqlite::Result<UsageSession> {
+        let product_type_list: String = row.get(7)?;
+        Ok(UsageSession {
+            session_id: row.get(0)?,
+            usage_time: row.get(1)?,
+            mode: row.get(2)?,
+            model_name: row.get(3)?,
+            amount_float: row.get(4)?,
+            cost_money_float: row.get(5)?,
+            use_max_mode: row.get(6)?,
+            product_type_list: serde_json::from_str(&product_type_list).unwrap_or_default(),
+            extra_info: UsageExtraInfo {
+                cache_read_token: row.get(8)?,
+                cache_write_token: row.get(9)?,
+                input_token: row.get(10)?,
+                output_token: row.get(11)?,
+            },
+        })
+    }
+
+    /// 获取账号的同步状态
+    pub fn sync_state(&self, account_id: &str) -> Result<Option<SyncState>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT account_id, synced_from, synced_to, last_synced_at
+            FROM usage_sync_state WHERE account_id = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![account_id], |row| {
+            Ok(SyncState {
+                account_id: row.get(0)?,
+                synced_from: row.get(1)?,
+                synced_to: row.get(2)?,
+                last_synced_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// 保存账号的同步状态，与已保存的范围合并，水位线不会后退
+    ///
+    /// 两段范围相交或相接时合并为一段；不相交时保留较新的一段。
+    pub fn save_sync_state(&self, state: &SyncState) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO usage_sync_state (account_id, synced_from, synced_to, last_synced_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(account_id) DO UPDATE SET
+                synced_from = CASE
+                    WHEN excluded.synced_from <= synced_to AND excluded.synced_to >= synced_from
+                        THEN MIN(synced_from, excluded.synced_from)
+                    WHEN excluded.synced_from > synced_to THEN excluded.synced_from
+                    ELSE synced_from
+                END,
+                synced_to = MAX(synced_to, excluded.synced_to),
+                last_synced_at = excluded.last_synced_at",
+            params![state.account_id, state.synced_from, state.synced_to, state.last_synced_at],
+        )?;
+        Ok(())
+    }
+
+    /// 删除账号的全部历史
+    pub fn delete_account(&self, account_id: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM usage_snapshots WHERE account_id = ?1", params![account_id])?;
+        conn.execute("DELETE FROM usage_sessions WHERE account_id = ?1", params![account_id])?;
+        conn.execute("DELETE FROM usage_sync_state WHERE account_id = ?1", params![account_id])?;
+        Ok(())
+    }
+
+    /// 获取历史设置
+    pub fn settings(&self) -> HistorySettings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// 更新历史设置
+    pub fn update_settings(&self, settings: HistorySettings) -> Result<()> {
+        let retention = &settings.retention;
+        if retention.raw_days < 0
+            || retention.hourly_days < retention.raw_days
+            || retention.daily_days < retention.hourly_days
+        {
+            return Err(anyhow!("保留天数必须满足 0 ≤ 原始 ≤ 按小时 ≤ 按天"));
+        }
+
+        let content = serde_json::to_string_pretty(&settings)?;
+        fs::write(&self.settings_path, content)?;
+        *self.settings.lock().map_err(|_| anyhow!("历史设置不可用"))? = settings;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(synced_from: i64, synced_to: i64) -> SyncState {
+        SyncState {
+            account_id: "acc".to_string(),
+            synced_from,
+            synced_to,
+            last_synced_at: synced_to,
+        }
+    }
+
+    fn history(policy: RetentionPolicy) -> UsageHistory {
+        let history = UsageHistory::open_in_memory(&std::env::temp_dir()).unwrap();
+        history.settings.lock().unwrap().retention = policy;
+        history
+    }
+
+    /// 写入指定时间的快照，用 fast_request_used 区分不同快照
+    fn snapshot_at(history: &UsageHistory, account_id: &str, captured_at: i64, used: f64) {
+        let summary = UsageSummary {
+            fast_request_used: used,
+            ..Default::default()
+        };
+        history.record_snapshot(account_id, &summary).unwrap();
+        history
+            .conn()
+            .unwrap()
+            .execute(
+                "UPDATE usage_snapshots SET captured_at = ?1 WHERE id = last_insert_rowid()",
+                params![captured_at],
+            )
+            .unwrap();
+    }
+
+    fn used(history: &UsageHistory, account_id: &str) -> Vec<f64> {
+        history
+            .query_snapshots(account_id, 0, i64::MAX, None)
+            .unwrap()
+            .iter()
+            .map(|s| s.fast_request_used)
+            .collect()
+    }
+
+    fn saved(history: &UsageHistory) -> (i64, i64) {
+        let state = history.sync_state("acc").unwrap().unwrap();
+        (state.synced_from, state.synced_to)
+    }
+
+    #[test]
+    fn sync_state_never_moves_backwards() {
+        let dir = std::env::temp_dir();
+        let history = UsageHistory::open_in_memory(&dir).unwrap();
+
+        history.save_sync_state(&state(100, 200)).unwrap();
+        // 重新同步较早的一段，与已有范围相交
+        history.save_sync_state(&state(50, 150)).unwrap();
+        assert_eq!(saved(&history), (50, 200));
+
+        // 完全早于已有范围的一段不会覆盖水位线
+        history.save_sync_state(&state(0, 10)).unwrap();
+        assert_eq!(saved(&history), (50, 200));
+
+        // 相接的较新范围合并
+        history.save_sync_state(&state(200, 300)).unwrap();
+        assert_eq!(saved(&history), (50, 300));
+
+        // 不相接的较新范围取代旧范围，避免把中间未同步的时段算作已同步
+        history.save_sync_state(&state(400, 500)).unwrap();
+        assert_eq!(saved(&history), (400, 500));
+    }
+
+    #[test]
+    fn retention_downsamples_by_age_and_drops_expired_snapshots() {
+        let history = history(RetentionPolicy { raw_days: 7, hourly_days: 30, daily_days: 365 });
+        let now = 400 * DAY;
+
+        // 超过 daily_days：删除；正好在边界上的保留
+        snapshot_at(&history, "acc", now - 365 * DAY - 1, 1.0);
+        snapshot_at(&history, "acc", now - 365 * DAY, 2.0);
+        // 按天保留区：同一天只保留最后一条
+        snapshot_at(&history, "acc", now - 100 * DAY + HOUR, 3.0);
+        snapshot_at(&history, "acc", now - 100 * DAY + 2 * HOUR, 4.0);
+        // 按小时保留区：同一小时只保留最后一条，其他账号单独计算
+        snapshot_at(&history, "acc", now - 10 * DAY + 60, 5.0);
+        snapshot_at(&history, "acc", now - 10 * DAY + 120, 6.0);
+        snapshot_at(&history, "other", now - 10 * DAY + 90, 7.0);
+        snapshot_at(&history, "acc", now - 10 * DAY + HOUR, 8.0);
+        // 原始保留区：全部保留，raw_days 边界上的快照属于原始保留区
+        snapshot_at(&history, "acc", now - 7 * DAY, 9.0);
+        snapshot_at(&history, "acc", now - 7 * DAY + 1, 10.0);
+        snapshot_at(&history, "acc", now - 60, 11.0);
+
+        let report = history.apply_retention(now).unwrap();
+        assert_eq!(report.expired, 1);
+        assert_eq!(report.downsampled, 2);
+        assert_eq!(used(&history, "acc"), vec![2.0, 4.0, 6.0, 8.0, 9.0, 10.0, 11.0]);
+        assert_eq!(used(&history, "other"), vec![7.0]);
+
+        // 重复执行不会再删除
+        let report = history.apply_retention(now).unwrap();
+        assert_eq!((report.expired, report.downsampled), (0, 0));
+    }
+
+    #[test]
+    fn retention_with_zero_raw_days_downsamples_recent_snapshots() {
+        let history = history(RetentionPolicy { raw_days: 0, hourly_days: 1, daily_days: 2 });
+        let now = 10 * DAY;
+
+        snapshot_at(&history, "acc", now - 2 * HOUR, 1.0);
+        snapshot_at(&history, "acc", now - 2 * HOUR + 60, 2.0);
+        snapshot_at(&history, "acc", now - 30 * HOUR, 3.0);
+        snapshot_at(&history, "acc", now - 29 * HOUR, 4.0);
+        snapshot_at(&history, "acc", now - 3 * DAY, 5.0);
+
+        let report = history.apply_retention(now).unwrap();
+        assert_eq!((report.expired, report.downsampled), (1, 2));
+        assert_eq!(used(&history, "acc"), vec![4.0, 2.0]);
+    }
+
+    #[test]
+    fn query_snapshots_keeps_latest_snapshot_per_bucket() {
+        let history = history(RetentionPolicy::default());
+        let start = 1000 * HOUR;
+
+        snapshot_at(&history, "acc", start + 10, 1.0);
+        snapshot_at(&history, "acc", start + 20, 2.0);
+        snapshot_at(&history, "acc", start + HOUR + 5, 3.0);
+        snapshot_at(&history, "acc", start + HOUR + 30, 4.0);
+        snapshot_at(&history, "acc", start + 3 * HOUR, 5.0);
+        snapshot_at(&history, "other", start + 15, 6.0);
+
+        let bucketed: Vec<f64> = history
+            .query_snapshots("acc", start, start + 2 * HOUR, Some(HOUR))
+            .unwrap()
+            .iter()
+            .map(|s| s.fast_request_used)
+            .collect();
+        assert_eq!(bucketed, vec![2.0, 4.0]);
+
+        // bucket_seconds 不大于 0 时返回全部快照
+        let all = history.query_snapshots("acc", start, start + 2 * HOUR, Some(0)).unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(all.windows(2).all(|w| w[0].captured_at <= w[1].captured_at));
+    }
+
+    #[test]
+    fn query_snapshots_returns_nothing_for_an_empty_range() {
+        let history = history(RetentionPolicy::default());
+        snapshot_at(&history, "acc", 5000, 1.0);
+
+        assert!(history.query_snapshots("acc", 6000, 7000, None).unwrap().is_empty());
+        assert!(history.query_snapshots("acc", 6000, 7000, Some(HOUR)).unwrap().is_empty());
+        // 起始时间晚于结束时间
+        assert!(history.query_snapshots("acc", 7000, 4000, Some(HOUR)).unwrap().is_empty());
+        assert!(history.query_snapshots("missing", 0, i64::MAX, None).unwrap().is_empty());
+    }
+}
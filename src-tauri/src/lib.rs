@@ -1,16 +1,51 @@
 mod api;
 mod account;
+mod alerts;
+mod anomalies;
+mod analytics;
+mod budgets;
+mod history;
 mod machine;
+mod scheduler;
+mod webhooks;
 
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::Mutex;
-use tauri::State;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
 
-use account::{AccountBrief, AccountManager, Account};
-use api::{UsageSummary, UsageQueryResponse};
+use alerts::{Alert, AlertCenter, AlertNotifier, AlertSettings};
+use anomalies::{AnomalyDetector, AnomalyNotifier, AnomalySettings, UsageAnomaly};
+use budgets::{Budget, BudgetLevel, BudgetNotifier, BudgetStatus, BudgetTracker};
+use analytics::{
+    ActivityHeatmap, BillingCycle, BurnRateForecast, CacheEfficiencyReport, CacheReportQuery, CostEstimateQuery, CostEstimateReport,
+    CycleComparison, CycleComparisonQuery, CycleRange, HeatmapQuery, PriceTableView, UsageAnalytics, UsageAnalyticsQuery,
+};
+use account::{
+    AccountBrief, AccountEvent, AccountFilter, AccountManager, Account, AccountRenewal, BatchRefreshOptions, BatchRefreshReport, CancelToken,
+    EntitlementDiagnostics, PackageCatalogView, TeamAccountRow, TeamDashboard, TeamDashboardProgress, TeamDashboardQuery,
+};
+use api::{SubscriptionInfo, UsageDetail, UsageSession, UsageSummary, UsageQueryResponse};
+use scheduler::{AccountSchedule, RefreshScheduler, SchedulerSettings, SchedulerStatus};
+use webhooks::{DeliveryLog, WebhookDispatcher, WebhookSink};
+use history::{HistorySettings, RetentionReport, SyncReport, UsageHistory, UsageSnapshot};
+
+/// 执行历史保留策略的最短间隔（秒）
+const RETENTION_INTERVAL_SECS: i64 = 3600;
 
 /// 应用状态
 pub struct AppState {
-    pub account_manager: Mutex<AccountManager>,
+    /// 账号管理器（内部自行加锁，网络请求期间不阻塞其它命令）
+    pub account_manager: AccountManager,
+    pub history: Arc<UsageHistory>,
+    pub alerts: Arc<AlertCenter>,
+    pub webhooks: Arc<WebhookDispatcher>,
+    pub budgets: Arc<BudgetTracker>,
+    pub anomalies: Arc<AnomalyDetector>,
+    pub scheduler: Arc<RefreshScheduler>,
+    /// 正在进行的批量刷新
+    pub batch_refresh: Mutex<Option<CancelToken>>,
 }
 
 /// 错误类型
@@ -34,64 +69,166 @@ type Result<T> = std::result::Result<T, ApiError>;
 /// 添加账号（通过 Token，可选 Cookies）
 #[tauri::command]
 async fn add_account_by_token(token: String, cookies: Option<String>, state: State<'_, AppState>) -> Result<Account> {
-    let mut manager = state.account_manager.lock().await;
-    manager.add_account_by_token(token, cookies).await.map_err(Into::into)
+    state.account_manager.add_account_by_token(token, cookies).await.map_err(Into::into)
 }
 
 /// 删除账号
 #[tauri::command]
 async fn remove_account(account_id: String, state: State<'_, AppState>) -> Result<()> {
-    let mut manager = state.account_manager.lock().await;
-    manager.remove_account(&account_id).map_err(Into::into)
+    state.account_manager.remove_account(&account_id).map_err(Into::into)
 }
 
-/// 获取所有账号
+/// 获取所有账号（可选过滤条件，如只返回开启 SOLO Coder 的账号）
 #[tauri::command]
-async fn get_accounts(state: State<'_, AppState>) -> Result<Vec<AccountBrief>> {
-    let manager = state.account_manager.lock().await;
-    Ok(manager.get_accounts())
+async fn get_accounts(filter: Option<AccountFilter>, state: State<'_, AppState>) -> Result<Vec<AccountBrief>> {
+    Ok(state.account_manager.get_accounts(&filter.unwrap_or_default()))
 }
 
 /// 获取单个账号详情
 #[tauri::command]
 async fn get_account(account_id: String, state: State<'_, AppState>) -> Result<Account> {
-    let manager = state.account_manager.lock().await;
-    manager.get_account(&account_id).map_err(Into::into)
+    state.account_manager.get_account(&account_id).map_err(Into::into)
+}
+
+/// 从磁盘重新加载账号数据（accounts.json 被其它程序修改后使用），返回账号数量
+#[tauri::command]
+async fn reload_accounts(state: State<'_, AppState>) -> Result<usize> {
+    state.account_manager.reload_store().map_err(Into::into)
 }
 
 /// 切换账号（设置活跃账号并更新机器码）
 #[tauri::command]
 async fn switch_account(account_id: String, state: State<'_, AppState>) -> Result<()> {
-    let mut manager = state.account_manager.lock().await;
-    manager.switch_account(&account_id).map_err(Into::into)
+    state.account_manager.switch_account(&account_id).map_err(Into::into)
 }
 
 /// 获取账号使用量
 #[tauri::command]
 async fn get_account_usage(account_id: String, state: State<'_, AppState>) -> Result<UsageSummary> {
-    let mut manager = state.account_manager.lock().await;
-    manager.get_account_usage(&account_id).await.map_err(Into::into)
+    state.account_manager.get_account_usage(&account_id).await.map_err(Into::into)
+}
+
+/// 获取团队看板（并发拉取多个账号的使用量，每完成一个账号通过 team-dashboard-progress 事件推送）
+#[tauri::command]
+async fn get_team_dashboard(
+    query: TeamDashboardQuery,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>
+) -> Result<TeamDashboard> {
+    let requests = state.account_manager.entitlement_requests(&query.account_ids, &query.filter)?;
+
+    let report = account::refresh_usage(&state.account_manager, requests, query.limits, CancelToken::new(), |progress| {
+        let progress = TeamDashboardProgress {
+            row: TeamAccountRow::new(&progress.outcome),
+            completed: progress.completed,
+            total: progress.total,
+        };
+        if let Err(e) = app.emit("team-dashboard-progress", &progress) {
+            println!("[WARN] 推送团队看板进度失败: {}", e);
+        }
+    })
+    .await?;
+
+    Ok(TeamDashboard::new(report.outcomes.iter().map(TeamAccountRow::new).collect()))
+}
+
+/// 批量刷新账号使用量（每完成一个账号通过 batch-refresh-progress 事件推送，可通过 cancel_batch_refresh 取消）
+#[tauri::command]
+async fn refresh_all_accounts(
+    options: BatchRefreshOptions,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>
+) -> Result<BatchRefreshReport> {
+    let cancel = {
+        let mut current = state.batch_refresh.lock().await;
+        if current.is_some() {
+            return Err(anyhow::anyhow!("已有批量刷新正在进行").into());
+        }
+        let cancel = CancelToken::new();
+        *current = Some(cancel.clone());
+        cancel
+    };
+
+    let result = async {
+        let requests = state.account_manager.entitlement_requests(&options.account_ids, &options.filter)?;
+        account::refresh_usage(&state.account_manager, requests, options.limits, cancel, |progress| {
+            if let Err(e) = app.emit("batch-refresh-progress", progress) {
+                println!("[WARN] 推送批量刷新进度失败: {}", e);
+            }
+        })
+        .await
+    }
+    .await;
+
+    // 无论成功与否都要清除标记，否则之后无法再次批量刷新
+    *state.batch_refresh.lock().await = None;
+    result.map_err(Into::into)
+}
+
+/// 取消正在进行的批量刷新，没有进行中的刷新时返回 false
+#[tauri::command]
+async fn cancel_batch_refresh(state: State<'_, AppState>) -> Result<bool> {
+    match state.batch_refresh.lock().await.as_ref() {
+        Some(cancel) => {
+            cancel.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 获取账号使用量明细（列出所有权益包）
+#[tauri::command]
+async fn get_account_usage_detail(account_id: String, state: State<'_, AppState>) -> Result<UsageDetail> {
+    state.account_manager.get_account_usage_detail(&account_id).await.map_err(Into::into)
+}
+
+/// 获取账号订阅信息
+#[tauri::command]
+async fn get_account_subscription(account_id: String, state: State<'_, AppState>) -> Result<Option<SubscriptionInfo>> {
+    state.account_manager.get_account_subscription(&account_id).map_err(Into::into)
+}
+
+/// 获取所有账号的续费计划（按下次扣费时间排序）
+#[tauri::command]
+async fn get_upcoming_renewals(state: State<'_, AppState>) -> Result<Vec<AccountRenewal>> {
+    Ok(state.account_manager.get_upcoming_renewals())
+}
+
+/// 获取账号最近一次配额响应的诊断信息
+#[tauri::command]
+async fn get_entitlement_diagnostics(account_id: String, state: State<'_, AppState>) -> Result<Option<EntitlementDiagnostics>> {
+    state.account_manager.get_entitlement_diagnostics(&account_id).map_err(Into::into)
+}
+
+/// 获取礼包目录
+#[tauri::command]
+async fn get_package_catalog(state: State<'_, AppState>) -> Result<PackageCatalogView> {
+    Ok(state.account_manager.get_package_catalog())
+}
+
+/// 重新加载礼包目录
+#[tauri::command]
+async fn reload_package_catalog(state: State<'_, AppState>) -> Result<PackageCatalogView> {
+    state.account_manager.reload_package_catalog().map_err(Into::into)
 }
 
 /// 更新账号 Token
 #[tauri::command]
 async fn update_account_token(account_id: String, token: String, state: State<'_, AppState>) -> Result<UsageSummary> {
-    let mut manager = state.account_manager.lock().await;
-    manager.update_account_token(&account_id, token).await.map_err(Into::into)
+    state.account_manager.update_account_token(&account_id, token).await.map_err(Into::into)
 }
 
 /// 导出账号
 #[tauri::command]
 async fn export_accounts(state: State<'_, AppState>) -> Result<String> {
-    let manager = state.account_manager.lock().await;
-    manager.export_accounts().map_err(Into::into)
+    state.account_manager.export_accounts().map_err(Into::into)
 }
 
 /// 导入账号
 #[tauri::command]
 async fn import_accounts(data: String, state: State<'_, AppState>) -> Result<usize> {
-    let mut manager = state.account_manager.lock().await;
-    manager.import_accounts(&data).await.map_err(Into::into)
+    state.account_manager.import_accounts(&data).await.map_err(Into::into)
 }
 
 /// 获取使用事件
@@ -104,17 +241,132 @@ async fn get_usage_events(
     page_size: i32,
     state: State<'_, AppState>
 ) -> Result<UsageQueryResponse> {
-    let mut manager = state.account_manager.lock().await;
-    manager.get_usage_events(&account_id, start_time, end_time, page_num, page_size)
+    state.account_manager.get_usage_events(&account_id, start_time, end_time, page_num, page_size)
+        .await
+        .map_err(Into::into)
+}
+
+/// 同步时间范围内的全部使用事件到本地（进度通过 usage-sync-progress 事件推送）
+#[tauri::command]
+async fn sync_usage_events(
+    account_id: String,
+    start_time: i64,
+    end_time: i64,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>
+) -> Result<SyncReport> {
+    state
+        .account_manager
+        .sync_usage_events(&account_id, start_time, end_time, |progress| {
+            if let Err(e) = app.emit("usage-sync-progress", progress) {
+                println!("[WARN] 推送同步进度失败: {}", e);
+            }
+        })
+        .await
+        .map_err(Into::into)
+}
+
+/// 查询本地已同步的使用事件
+#[tauri::command]
+async fn get_synced_usage_events(
+    account_id: String,
+    start_time: i64,
+    end_time: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<UsageSession>> {
+    state.account_manager.get_synced_usage_events(&account_id, start_time, end_time).map_err(Into::into)
+}
+
+/// 按模型、模式、天、周或计费周期统计使用事件
+#[tauri::command]
+async fn get_usage_analytics(query: UsageAnalyticsQuery, state: State<'_, AppState>) -> Result<UsageAnalytics> {
+    state.account_manager.get_usage_analytics(&query).await.map_err(Into::into)
+}
+
+/// 获取缓存效率报告
+#[tauri::command]
+async fn get_cache_efficiency_report(query: CacheReportQuery, state: State<'_, AppState>) -> Result<CacheEfficiencyReport> {
+    state.account_manager.get_cache_efficiency_report(&query).await.map_err(Into::into)
+}
+
+/// 获取账号的计费周期（当前、上一个或最近 N 个）
+#[tauri::command]
+async fn get_billing_cycles(
+    account_id: String,
+    range: CycleRange,
+    timezone: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Vec<BillingCycle>> {
+    state.account_manager.get_billing_cycles(&account_id, range, timezone.as_deref()).await.map_err(Into::into)
+}
+
+/// 获取某个计费周期内的使用事件
+#[tauri::command]
+async fn get_cycle_usage_events(
+    account_id: String,
+    cycle_index: u32,
+    page_num: i32,
+    page_size: i32,
+    timezone: Option<String>,
+    state: State<'_, AppState>
+) -> Result<UsageQueryResponse> {
+    state
+        .account_manager
+        .get_cycle_usage_events(&account_id, cycle_index, page_num, page_size, timezone.as_deref())
         .await
         .map_err(Into::into)
 }
 
+/// 对比最近几个计费周期的用量
+#[tauri::command]
+async fn compare_billing_cycles(query: CycleComparisonQuery, state: State<'_, AppState>) -> Result<CycleComparison> {
+    state.account_manager.compare_billing_cycles(&query).await.map_err(Into::into)
+}
+
+/// 获取按星期和小时统计的活跃度热力图
+#[tauri::command]
+async fn get_activity_heatmap(query: HeatmapQuery, state: State<'_, AppState>) -> Result<ActivityHeatmap> {
+    state.account_manager.get_activity_heatmap(&query).await.map_err(Into::into)
+}
+
+/// 获取模型价格表
+#[tauri::command]
+async fn get_price_table(state: State<'_, AppState>) -> Result<PriceTableView> {
+    Ok(state.account_manager.get_price_table())
+}
+
+/// 从 JSON 文件导入模型价格表
+#[tauri::command]
+async fn import_price_table(path: String, state: State<'_, AppState>) -> Result<PriceTableView> {
+    state.account_manager.import_price_table(&path).map_err(Into::into)
+}
+
+/// 恢复内置模型价格表
+#[tauri::command]
+async fn reset_price_table(state: State<'_, AppState>) -> Result<PriceTableView> {
+    state.account_manager.reset_price_table().map_err(Into::into)
+}
+
+/// 按模型价格表估算费用
+#[tauri::command]
+async fn get_cost_estimate(query: CostEstimateQuery, state: State<'_, AppState>) -> Result<CostEstimateReport> {
+    state.account_manager.get_cost_estimate(&query).await.map_err(Into::into)
+}
+
+/// 预测账号额度耗尽时间
+#[tauri::command]
+async fn get_account_forecast(
+    account_id: String,
+    timezone: Option<String>,
+    state: State<'_, AppState>
+) -> Result<BurnRateForecast> {
+    state.account_manager.get_account_forecast(&account_id, timezone.as_deref()).await.map_err(Into::into)
+}
+
 /// 从 Trae IDE号
 #[tauri::command]
 async fn read_trae_account(state: State<'_, AppState>) -> Result<Option<Account>> {
-    let mut manager = state.account_manager.lock().await;
-    manager.read_trae_ide_account().await.map_err(Into::into)
+    state.account_manager.read_trae_ide_account().await.map_err(Into::into)
 }
 
 /// 获取当前系统机器码
@@ -138,8 +390,7 @@ async fn set_machine_id(machine_id: String) -> Result<()> {
 /// 绑定账号机器码（保存当前系统机器码到账号）
 #[tauri::command]
 async fn bind_account_machine_id(account_id: String, state: State<'_, AppState>) -> Result<String> {
-    let mut manager = state.account_manager.lock().await;
-    manager.bind_machine_id(&account_id).map_err(Into::into)
+    state.account_manager.bind_machine_id(&account_id).map_err(Into::into)
 }
 
 /// 获取 Trae IDE 的机器码
@@ -181,31 +432,397 @@ async fn scan_trae_path() -> Result<String> {
 /// 领取礼包
 #[tauri::command]
 async fn claim_gift(account_id: String, state: State<'_, AppState>) -> Result<()> {
-    let mut manager = state.account_manager.lock().await;
-    manager.claim_birthday_bonus(&account_id).await.map_err(Into::into)
+    state.account_manager.claim_birthday_bonus(&account_id).await.map_err(Into::into)
+}
+
+// ============ 使用量历史 ============
+
+/// 查询账号使用量历史（可按时间段降采样）
+#[tauri::command]
+async fn get_usage_history(
+    account_id: String,
+    start_time: i64,
+    end_time: i64,
+    bucket_seconds: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<Vec<UsageSnapshot>> {
+    state.history
+        .query_snapshots(&account_id, start_time, end_time, bucket_seconds)
+        .map_err(Into::into)
+}
+
+/// 获取使用量历史设置
+#[tauri::command]
+async fn get_history_settings(state: State<'_, AppState>) -> Result<HistorySettings> {
+    Ok(state.history.settings())
+}
+
+/// 更新使用量历史设置
+#[tauri::command]
+async fn set_history_settings(settings: HistorySettings, state: State<'_, AppState>) -> Result<()> {
+    state.history.update_settings(settings).map_err(Into::into)
+}
+
+/// 立即执行历史保留策略
+#[tauri::command]
+async fn prune_usage_history(state: State<'_, AppState>) -> Result<RetentionReport> {
+    state.history
+        .apply_retention(chrono::Utc::now().timestamp())
+        .map_err(Into::into)
+}
+
+// ============ 告警 ============
+
+/// 获取告警设置
+#[tauri::command]
+async fn get_alert_settings(state: State<'_, AppState>) -> Result<AlertSettings> {
+    state.alerts.settings().map_err(Into::into)
+}
+
+/// 更新告警设置
+#[tauri::command]
+async fn set_alert_settings(settings: AlertSettings, state: State<'_, AppState>) -> Result<AlertSettings> {
+    state.alerts.update_settings(settings).map_err(Into::into)
+}
+
+/// 获取告警列表
+#[tauri::command]
+async fn get_alerts(include_resolved: Option<bool>, state: State<'_, AppState>) -> Result<Vec<Alert>> {
+    state.alerts.list_alerts(include_resolved.unwrap_or(false)).map_err(Into::into)
+}
+
+/// 暂停告警提醒
+#[tauri::command]
+async fn snooze_alert(alert_id: String, until: i64, state: State<'_, AppState>) -> Result<Alert> {
+    state.alerts.snooze(&alert_id, until).map_err(Into::into)
+}
+
+/// 确认告警
+#[tauri::command]
+async fn acknowledge_alert(alert_id: String, state: State<'_, AppState>) -> Result<Alert> {
+    state.alerts.acknowledge(&alert_id).map_err(Into::into)
+}
+
+/// 清除已解除的告警记录
+#[tauri::command]
+async fn clear_resolved_alerts(state: State<'_, AppState>) -> Result<usize> {
+    state.alerts.clear_resolved().map_err(Into::into)
+}
+
+/// 通过系统通知和 alert-raised 事件发出告警
+struct DesktopNotifier {
+    app: tauri::AppHandle,
+}
+
+impl AlertNotifier for DesktopNotifier {
+    fn notify(&self, alert: &Alert) {
+        if let Err(e) = self
+            .app
+            .notification()
+            .builder()
+            .title(&alert.rule_name)
+            .body(&alert.message)
+            .show()
+        {
+            println!("[WARN] 发送系统通知失败: {}", e);
+        }
+        if let Err(e) = self.app.emit("alert-raised", alert) {
+            println!("[WARN] 推送告警事件失败: {}", e);
+        }
+    }
+}
+
+impl BudgetNotifier for DesktopNotifier {
+    fn notify_budget(&self, status: &BudgetStatus) {
+        let title = match status.level {
+            BudgetLevel::Critical => "预算已超出",
+            _ => "预算即将用完",
+        };
+        let body = format!(
+            "{} 月已支出 {:.2} {}（预算 {:.2}，{:.0}%）",
+            status.month, status.spent, status.currency, status.limit, status.percent
+        );
+        if let Err(e) = self.app.notification().builder().title(title).body(body).show() {
+            println!("[WARN] 发送系统通知失败: {}", e);
+        }
+        if let Err(e) = self.app.emit("budget-threshold", status) {
+            println!("[WARN] 推送预算事件失败: {}", e);
+        }
+    }
+}
+
+impl AnomalyNotifier for DesktopNotifier {
+    fn notify_anomalies(&self, anomalies: &[UsageAnomaly]) {
+        let body = anomalies
+            .iter()
+            .map(|a| format!("{}\n会话: {}", a.message(), a.session_ids.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Err(e) = self.app.notification().builder().title("用量异常").body(body).show() {
+            println!("[WARN] 发送系统通知失败: {}", e);
+        }
+        if let Err(e) = self.app.emit("usage-anomaly", anomalies) {
+            println!("[WARN] 推送异常事件失败: {}", e);
+        }
+    }
+}
+
+// ============ 预算 ============
+
+/// 获取全部预算
+#[tauri::command]
+async fn get_budgets(state: State<'_, AppState>) -> Result<Vec<Budget>> {
+    state.budgets.budgets().map_err(Into::into)
+}
+
+/// 设置账号预算
+#[tauri::command]
+async fn set_budget(budget: Budget, state: State<'_, AppState>) -> Result<()> {
+    state.budgets.set_budget(budget).map_err(Into::into)
+}
+
+/// 删除账号预算
+#[tauri::command]
+async fn remove_budget(account_id: String, state: State<'_, AppState>) -> Result<()> {
+    state.budgets.remove_budget(&account_id).map_err(Into::into)
+}
+
+/// 计算账号本月的预算使用情况
+#[tauri::command]
+async fn get_budget_status(
+    account_id: String,
+    timezone: Option<String>,
+    state: State<'_, AppState>
+) -> Result<BudgetStatus> {
+    state.account_manager.get_budget_status(&account_id, timezone.as_deref()).await.map_err(Into::into)
+}
+
+// ============ 用量异常 ============
+
+/// 获取异常检测设置
+#[tauri::command]
+async fn get_anomaly_settings(state: State<'_, AppState>) -> Result<AnomalySettings> {
+    state.anomalies.settings().map_err(Into::into)
+}
+
+/// 更新异常检测设置
+#[tauri::command]
+async fn set_anomaly_settings(settings: AnomalySettings, state: State<'_, AppState>) -> Result<AnomalySettings> {
+    state.anomalies.update_settings(settings).map_err(Into::into)
+}
+
+/// 立即检测账号的用量异常
+#[tauri::command]
+async fn detect_usage_anomalies(
+    account_id: String,
+    timezone: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Vec<UsageAnomaly>> {
+    state.account_manager.detect_usage_anomalies(&account_id, timezone.as_deref()).await.map_err(Into::into)
+}
+
+/// 获取用量异常记录（account_id 为空时返回全部账号）
+#[tauri::command]
+async fn get_usage_anomalies(
+    account_id: Option<String>,
+    include_acknowledged: bool,
+    state: State<'_, AppState>
+) -> Result<Vec<UsageAnomaly>> {
+    state.anomalies.list(account_id.as_deref(), include_acknowledged).map_err(Into::into)
+}
+
+/// 确认用量异常
+#[tauri::command]
+async fn acknowledge_anomaly(anomaly_id: String, state: State<'_, AppState>) -> Result<UsageAnomaly> {
+    state.anomalies.acknowledge(&anomaly_id).map_err(Into::into)
+}
+
+// ============ 后台刷新 ============
+
+/// 获取后台刷新状态（设置及每个账号的上次/下次刷新时间）
+#[tauri::command]
+async fn get_refresh_schedule(state: State<'_, AppState>) -> Result<SchedulerStatus> {
+    state.scheduler.status().map_err(Into::into)
+}
+
+/// 更新后台刷新设置
+#[tauri::command]
+async fn set_refresh_scheduler_settings(
+    settings: SchedulerSettings,
+    state: State<'_, AppState>
+) -> Result<SchedulerSettings> {
+    state.scheduler.update_settings(settings).map_err(Into::into)
+}
+
+/// 设置账号的刷新间隔（分钟，为空时使用全局默认）
+#[tauri::command]
+async fn set_account_refresh_interval(
+    account_id: String,
+    interval_minutes: Option<u64>,
+    state: State<'_, AppState>
+) -> Result<AccountSchedule> {
+    state.account_manager.get_account(&account_id)?;
+    state.scheduler.set_account_interval(&account_id, interval_minutes).map_err(Into::into)
+}
+
+/// 立即在后台刷新指定账号（为空时刷新全部账号）
+#[tauri::command]
+async fn run_refresh_now(account_ids: Vec<String>, state: State<'_, AppState>) -> Result<()> {
+    for account_id in &account_ids {
+        state.account_manager.get_account(account_id)?;
+    }
+    state.scheduler.run_now(&account_ids).map_err(Into::into)
+}
+
+// ============ Webhook ============
+
+/// 获取 Webhook 接收端
+#[tauri::command]
+async fn get_webhooks(state: State<'_, AppState>) -> Result<Vec<WebhookSink>> {
+    state.webhooks.sinks().map_err(Into::into)
+}
+
+/// 保存 Webhook 接收端
+#[tauri::command]
+async fn set_webhooks(sinks: Vec<WebhookSink>, state: State<'_, AppState>) -> Result<Vec<WebhookSink>> {
+    state.webhooks.set_sinks(sinks).map_err(Into::into)
+}
+
+/// 向 Webhook 发送测试事件
+#[tauri::command]
+async fn test_webhook(sink_id: String, state: State<'_, AppState>) -> Result<DeliveryLog> {
+    state.webhooks.test_sink(&sink_id).await.map_err(Into::into)
+}
+
+/// 获取 Webhook 投递记录
+#[tauri::command]
+async fn get_webhook_logs(sink_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<DeliveryLog>> {
+    state.webhooks.delivery_logs(sink_id.as_deref()).map_err(Into::into)
+}
+
+// ============ 后台任务 ============
+
+/// 将账号领域事件转发到所有窗口（account-event 事件），前端据此更新而无需轮询
+async fn forward_account_events(app: tauri::AppHandle, mut events: broadcast::Receiver<AccountEvent>) {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if let Err(e) = app.emit("account-event", &event) {
+                    println!("[WARN] 推送账号事件失败: {}", e);
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => println!("[WARN] 账号事件积压，已丢弃 {} 条", skipped),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// 按刷新计划在后台刷新账号使用量，并定期执行历史保留策略
+async fn run_refresh_scheduler(app: tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let mut last_retention_at = 0;
+
+    loop {
+        state.scheduler.wait().await;
+        if let Err(e) = state.scheduler.run_due(&state.account_manager).await {
+            println!("[WARN] 后台刷新失败: {}", e);
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if now - last_retention_at >= RETENTION_INTERVAL_SECS {
+            last_retention_at = now;
+            match state.history.apply_retention(now) {
+                Ok(report) => println!("[INFO] 历史保留策略: 降采样 {} 条，过期 {} 条", report.downsampled, report.expired),
+                Err(e) => println!("[WARN] 执行历史保留策略失败: {}", e),
+            }
+        }
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let account_manager = AccountManager::new().expect("无法初始化账号管理器");
+    let data_dir = AccountManager::data_dir().expect("无法获取应用数据目录");
+    // 历史数据库损坏或被占用时不阻止启动，改用内存数据库（本次运行的历史不会保存）
+    let history = UsageHistory::open(&data_dir)
+        .or_else(|e| {
+            println!("[WARN] 无法打开使用量历史数据库，本次运行不保存历史: {}", e);
+            UsageHistory::open_in_memory(&data_dir)
+        })
+        .expect("无法创建内存历史数据库");
+    let history = Arc::new(history);
+
+    let alerts = Arc::new(AlertCenter::open(&data_dir));
+    let webhooks = Arc::new(WebhookDispatcher::open(&data_dir));
+    let budgets = Arc::new(BudgetTracker::open(&data_dir));
+    let anomalies = Arc::new(AnomalyDetector::open(&data_dir));
+    let scheduler = Arc::new(RefreshScheduler::open(&data_dir));
+
+    let mut account_manager = AccountManager::new().expect("无法初始化账号管理器");
+    account_manager.set_history(history.clone());
+    account_manager.set_alerts(alerts.clone());
+    account_manager.set_webhooks(webhooks.clone());
+    account_manager.set_budgets(budgets.clone());
+    account_manager.set_anomalies(anomalies.clone());
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(AppState {
-            account_manager: Mutex::new(account_manager),
+            account_manager,
+            history,
+            alerts,
+            webhooks,
+            budgets,
+            anomalies,
+            scheduler,
+            batch_refresh: Mutex::new(None),
+        })
+        .setup(|app| {
+            let notifier = Arc::new(DesktopNotifier { app: app.handle().clone() });
+            let state = app.state::<AppState>();
+            state.alerts.set_notifier(notifier.clone());
+            state.budgets.set_notifier(notifier.clone());
+            state.anomalies.set_notifier(notifier);
+            let events = state.account_manager.subscribe_events();
+            tauri::async_runtime::spawn(forward_account_events(app.handle().clone(), events));
+            tauri::async_runtime::spawn(run_refresh_scheduler(app.handle().clone()));
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             add_account_by_token,
             remove_account,
             get_accounts,
             get_account,
+            reload_accounts,
             switch_account,
             get_account_usage,
+            get_team_dashboard,
+            refresh_all_accounts,
+            cancel_batch_refresh,
+            get_account_usage_detail,
+            get_account_subscription,
+            get_upcoming_renewals,
+            get_entitlement_diagnostics,
+            get_package_catalog,
+            reload_package_catalog,
             update_account_token,
             export_accounts,
             import_accounts,
             get_usage_events,
+            sync_usage_events,
+            get_synced_usage_events,
+            get_usage_analytics,
+            get_cache_efficiency_report,
+            get_billing_cycles,
+            get_cycle_usage_events,
+            compare_billing_cycles,
+            get_activity_heatmap,
+            get_price_table,
+            import_price_table,
+            reset_price_table,
+            get_cost_estimate,
+            get_account_forecast,
             read_trae_account,
             get_machine_id,
             reset_machine_id,
@@ -218,6 +835,33 @@ pub fn run() {
             set_trae_path,
             scan_trae_path,
             claim_gift,
+            get_usage_history,
+            get_history_settings,
+            set_history_settings,
+            prune_usage_history,
+            get_alert_settings,
+            set_alert_settings,
+            get_alerts,
+            snooze_alert,
+            acknowledge_alert,
+            clear_resolved_alerts,
+            get_webhooks,
+            set_webhooks,
+            test_webhook,
+            get_webhook_logs,
+            get_budgets,
+            set_budget,
+            remove_budget,
+            get_budget_status,
+            get_anomaly_settings,
+            set_anomaly_settings,
+            detect_usage_anomalies,
+            get_usage_anomalies,
+            acknowledge_anomaly,
+            get_refresh_schedule,
+            set_refresh_scheduler_settings,
+            set_account_refresh_interval,
+            run_refresh_now,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,5 @@
+pub mod refresh_scheduler;
+pub mod types;
+
+pub use refresh_scheduler::RefreshScheduler;
+pub use types::*;
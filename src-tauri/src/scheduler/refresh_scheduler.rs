@@ -0,0 +1,346 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+
+use super::types::*;
+use crate::account::batch::{RefreshOutcome, RefreshStatus};
+use crate::account::{self, AccountFilter, AccountManager, BatchRefreshReport, CancelToken};
+
+/// 刷新计划文件名
+const SCHEDULE_FILE: &str = "refresh_schedule.json";
+/// 空闲时重新检查设置和到期账号的最长间隔（秒）
+const MAX_IDLE_SECS: i64 = 60;
+/// 网络不可用时重新检查的间隔（秒）
+const OFFLINE_RETRY_SECS: i64 = 60;
+/// 两次刷新之间的最短间隔（秒）
+const MIN_DELAY_SECS: i64 = 60;
+/// 检查网络时尝试连接的地址，任意一个能连上即视为在线
+const PROBE_HOSTS: [&str; 2] = ["api-sg-central.trae.ai:443", "api-us-east.trae.ai:443"];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 持久化的刷新计划
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ScheduleStore {
+    settings: SchedulerSettings,
+    accounts: Vec<AccountSchedule>,
+}
+
+/// 运行时状态（不保存）
+#[derive(Debug, Default)]
+struct RuntimeState {
+    running: bool,
+    paused_offline: bool,
+    last_online_check_at: Option<i64>,
+}
+
+/// 后台刷新调度：按账号的刷新间隔定时刷新使用量（同时记录快照、更新预测、检查预算和异常），
+/// 失败时退避，离线时暂停
+pub struct RefreshScheduler {
+    store: Mutex<ScheduleStore>,
+    path: PathBuf,
+    runtime: Mutex<RuntimeState>,
+    /// 设置或计划变化时唤醒等待中的调度循环
+    wake: Notify,
+}
+
+impl RefreshScheduler {
+    /// 打开数据目录下的刷新计划
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(SCHEDULE_FILE);
+        let store = if path.exists() {
+            fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    println!("[WARN] 刷新计划文件无效，已忽略: {}", e);
+                    ScheduleStore::default()
+                })
+        } else {
+            ScheduleStore::default()
+        };
+
+        Self {
+            store: Mutex::new(store),
+            path,
+            runtime: Mutex::new(RuntimeState::default()),
+            wake: Notify::new(),
+        }
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, ScheduleStore>> {
+        self.store.lock().map_err(|_| anyhow!("刷新计划不可用"))
+    }
+
+    fn runtime(&self) -> MutexGuard<'_, RuntimeState> {
+        self.runtime.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self, store: &ScheduleStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(store)?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// 获取刷新设置
+    pub fn settings(&self) -> Result<SchedulerSettings> {
+        Ok(self.store()?.settings.clone())
+    }
+
+    /// 更新刷新设置
+    pub fn update_settings(&self, settings: SchedulerSettings) -> Result<SchedulerSettings> {
+        if settings.default_interval_minutes == 0 {
+            return Err(anyhow!("刷新间隔必须大于 0"));
+        }
+        if !(0.0..=1.0).contains(&settings.jitter_ratio) {
+            return Err(anyhow!("抖动比例必须在 0 到 1 之间"));
+        }
+        if settings.limits.concurrency == 0 || settings.limits.requests_per_second < 0.0 {
+            return Err(anyhow!("并发数必须大于 0，请求速率不能为负数"));
+        }
+
+        let mut store = self.store()?;
+        store.settings = settings.clone();
+        self.save(&store)?;
+        drop(store);
+
+        self.wake.notify_one();
+        Ok(settings)
+    }
+
+    /// 设置账号的刷新间隔（为空时使用全局默认），按新间隔重新安排下次刷新
+    pub fn set_account_interval(&self, account_id: &str, interval_minutes: Option<u64>) -> Result<AccountSchedule> {
+        if interval_minutes == Some(0) {
+            return Err(anyhow!("刷新间隔必须大于 0"));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut store = self.store()?;
+        let settings = store.settings.clone();
+        let schedule = match store.accounts.iter().position(|s| s.account_id == account_id) {
+            Some(index) => &mut store.accounts[index],
+            None => {
+                store.accounts.push(AccountSchedule::new(account_id, now));
+                store.accounts.last_mut().expect("刚插入的刷新计划")
+            }
+        };
+        schedule.interval_minutes = interval_minutes;
+        if let Some(last_run_at) = schedule.last_run_at {
+            schedule.next_run_at = (last_run_at + next_delay(&settings, schedule, 0.0)).max(now);
+        }
+        let schedule = schedule.clone();
+        self.save(&store)?;
+        drop(store);
+
+        self.wake.notify_one();
+        Ok(schedule)
+    }
+
+    /// 获取刷新状态
+    pub fn status(&self) -> Result<SchedulerStatus> {
+        let store = self.store()?;
+        let mut accounts = store.accounts.clone();
+        accounts.sort_by_key(|s| s.next_run_at);
+
+        let runtime = self.runtime();
+        Ok(SchedulerStatus {
+            settings: store.settings.clone(),
+            running: runtime.running,
+            paused_offline: runtime.paused_offline,
+            last_online_check_at: runtime.last_online_check_at,
+            accounts,
+        })
+    }
+
+    /// 立即刷新指定账号（为空时刷新全部账号），不影响失败退避的计数
+    ///
+    /// 调用方需先确认账号存在；还没有刷新计划的账号会被加入。
+    pub fn run_now(&self, account_ids: &[String]) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut store = self.store()?;
+        for account_id in account_ids {
+            if !store.accounts.iter().any(|s| &s.account_id == account_id) {
+                store.accounts.push(AccountSchedule::new(account_id, now));
+            }
+        }
+        for schedule in &mut store.accounts {
+            if account_ids.is_empty() || account_ids.contains(&schedule.account_id) {
+                schedule.next_run_at = now;
+            }
+        }
+        self.save(&store)?;
+        drop(store);
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// 与账号列表同步（新账号立即刷新，删除已不存在的账号），返回已到期的账号
+    pub fn due_accounts(&self, account_ids: &[String], now: i64) -> Result<Vec<String>> {
+        let mut store = self.store()?;
+        let before = store.accounts.len();
+        store.accounts.retain(|s| account_ids.contains(&s.account_id));
+        let mut changed = store.accounts.len() != before;
+
+        for account_id in account_ids {
+            if !store.accounts.iter().any(|s| &s.account_id == account_id) {
+                store.accounts.push(AccountSchedule::new(account_id, now));
+                changed = true;
+            }
+        }
+        if changed {
+            self.save(&store)?;
+        }
+
+        Ok(store
+            .accounts
+            .iter()
+            .filter(|s| s.next_run_at <= now)
+            .map(|s| s.account_id.clone())
+            .collect())
+    }
+
+    /// 记录刷新结果并安排下次刷新
+    pub fn record(&self, outcomes: &[RefreshOutcome], now: i64) -> Result<()> {
+        let mut store = self.store()?;
+        let settings = store.settings.clone();
+        for outcome in outcomes {
+            let Some(schedule) = store.accounts.iter_mut().find(|s| s.account_id == outcome.account_id) else {
+                continue;
+            };
+            match outcome.status {
+                RefreshStatus::Succeeded => {
+                    schedule.last_run_at = Some(now);
+                    schedule.last_success_at = Some(now);
+                    schedule.consecutive_failures = 0;
+                    schedule.last_error = None;
+                }
+                RefreshStatus::Failed => {
+                    schedule.last_run_at = Some(now);
+                    schedule.consecutive_failures += 1;
+                    schedule.last_error = outcome.error.clone();
+                }
+                RefreshStatus::Cancelled => {}
+            }
+            schedule.next_run_at = now + next_delay(&settings, schedule, random_unit());
+        }
+        self.save(&store)
+    }
+
+    /// 离线时推迟到期账号的刷新（不计为失败）
+    fn postpone(&self, account_ids: &[String], until: i64) -> Result<()> {
+        let mut store = self.store()?;
+        for schedule in &mut store.accounts {
+            if account_ids.contains(&schedule.account_id) {
+                schedule.next_run_at = schedule.next_run_at.max(until);
+            }
+        }
+        self.save(&store)
+    }
+
+    /// 等待到下一个账号到期（最长等待 `MAX_IDLE_SECS`，设置或计划变化时提前返回）
+    pub async fn wait(&self) {
+        let now = chrono::Utc::now().timestamp();
+        let next_run_at = self
+            .store()
+            .ok()
+            .filter(|store| store.settings.enabled)
+            .and_then(|store| store.accounts.iter().map(|s| s.next_run_at).min());
+        let secs = next_run_at.map_or(MAX_IDLE_SECS, |next| (next - now).clamp(1, MAX_IDLE_SECS));
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(secs as u64)) => {}
+            _ = self.wake.notified() => {}
+        }
+    }
+
+    /// 刷新所有已到期的账号；未开启、没有到期账号或离线时返回 None
+    pub async fn run_due(&self, manager: &AccountManager) -> Result<Option<BatchRefreshReport>> {
+        let settings = self.settings()?;
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let account_ids: Vec<String> = manager
+            .get_accounts(&AccountFilter::default())
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        let due = self.due_accounts(&account_ids, now)?;
+        if due.is_empty() {
+            return Ok(None);
+        }
+
+        let online = is_online().await;
+        {
+            let mut runtime = self.runtime();
+            runtime.last_online_check_at = Some(now);
+            if runtime.paused_offline == online {
+                println!("[INFO] 网络{}，后台刷新{}", if online { "已恢复" } else { "不可用" }, if online { "继续" } else { "暂停" });
+            }
+            runtime.paused_offline = !online;
+        }
+        if !online {
+            self.postpone(&due, now + OFFLINE_RETRY_SECS)?;
+            return Ok(None);
+        }
+
+        let requests = manager.entitlement_requests(&due, &AccountFilter::default())?;
+        self.runtime().running = true;
+        let result = account::refresh_usage(manager, requests, settings.limits, CancelToken::new(), |_| {}).await;
+        self.runtime().running = false;
+
+        let report = result?;
+        self.record(&report.outcomes, chrono::Utc::now().timestamp())?;
+        println!(
+            "[INFO] 后台刷新完成: 成功 {}，失败 {}",
+            report.succeeded, report.failed
+        );
+
+        for outcome in report.outcomes.iter().filter(|o| o.status == RefreshStatus::Succeeded) {
+            if let Some(summary) = &outcome.summary {
+                manager.update_after_refresh(&outcome.account_id, summary).await;
+            }
+        }
+        Ok(Some(report))
+    }
+}
+
+/// 下次刷新前等待的秒数：按刷新间隔和连续失败次数指数退避，再加上随机抖动
+///
+/// `random` 取值范围为 -1~1，抖动幅度为间隔乘以 `jitter_ratio`。
+pub fn next_delay(settings: &SchedulerSettings, schedule: &AccountSchedule, random: f64) -> i64 {
+    let interval = schedule.interval_minutes.unwrap_or(settings.default_interval_minutes).max(1) as i64 * 60;
+    let base = if schedule.consecutive_failures > 0 {
+        let max_backoff = (settings.max_backoff_minutes as i64 * 60).max(interval);
+        interval
+            .saturating_mul(1 << schedule.consecutive_failures.min(16))
+            .min(max_backoff)
+    } else {
+        interval
+    };
+    let jitter = base as f64 * settings.jitter_ratio.clamp(0.0, 1.0) * random.clamp(-1.0, 1.0);
+    (base + jitter.round() as i64).max(MIN_DELAY_SECS)
+}
+
+/// -1~1 之间的随机数
+fn random_unit() -> f64 {
+    let bits = (uuid::Uuid::new_v4().as_u128() >> 64) as u64;
+    bits as f64 / u64::MAX as f64 * 2.0 - 1.0
+}
+
+/// 网络是否可用（能连上任意一个接口地址）
+pub async fn is_online() -> bool {
+    for host in PROBE_HOSTS {
+        if let Ok(Ok(_)) = tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(host)).await {
+            return true;
+        }
+    }
+    false
+}
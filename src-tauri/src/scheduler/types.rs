@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::account::BatchLimits;
+
+/// 后台刷新设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerSettings {
+    pub enabled: bool,
+    /// 全局默认刷新间隔（分钟），账号未单独设置时使用
+    pub default_interval_minutes: u64,
+    /// 随机抖动比例（0~1），避免所有账号在同一时刻刷新
+    pub jitter_ratio: f64,
+    /// 连续失败时退避的最长间隔（分钟）
+    pub max_backoff_minutes: u64,
+    /// 每次刷新的并发数和请求速率
+    #[serde(flatten)]
+    pub limits: BatchLimits,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_interval_minutes: 30,
+            jitter_ratio: 0.1,
+            max_backoff_minutes: 360,
+            limits: BatchLimits::default(),
+        }
+    }
+}
+
+/// 单个账号的刷新计划
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSchedule {
+    pub account_id: String,
+    /// 账号单独设置的刷新间隔（分钟），为空时使用全局默认
+    #[serde(default)]
+    pub interval_minutes: Option<u64>,
+    #[serde(default)]
+    pub last_run_at: Option<i64>,
+    #[serde(default)]
+    pub last_success_at: Option<i64>,
+    pub next_run_at: i64,
+    /// 连续失败次数（成功后清零）
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl AccountSchedule {
+    pub fn new(account_id: &str, next_run_at: i64) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            interval_minutes: None,
+            last_run_at: None,
+            last_success_at: None,
+            next_run_at,
+            consecutive_failures: 0,
+            last_error: None,
+        }
+    }
+}
+
+/// 后台刷新状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerStatus {
+    pub settings: SchedulerSettings,
+    /// 是否正在刷新
+    pub running: bool,
+    /// 是否因为网络不可用而暂停
+    pub paused_offline: bool,
+    /// 最近一次检查网络的时间
+    pub last_online_check_at: Option<i64>,
+    /// 按下次刷新时间升序排列
+    pub accounts: Vec<AccountSchedule>,
+}
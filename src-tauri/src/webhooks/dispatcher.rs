@@ -0,0 +1,484 @@
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::types::*;
+
+/// Webhook 配置与投递记录文件名
+const WEBHOOKS_FILE: &str = "webhooks.json";
+/// 最多保留的投递记录数
+const MAX_DELIVERY_LOGS: usize = 200;
+/// 响应内容在记录中保留的最大长度
+const MAX_RESPONSE_BODY: usize = 2000;
+/// 失败重试次数上限
+const MAX_RETRIES: u32 = 10;
+/// 单次请求超时上限（秒）
+const MAX_TIMEOUT_SECONDS: u64 = 60;
+
+/// 持久化的 Webhook 数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WebhookStore {
+    sinks: Vec<WebhookSink>,
+    /// 投递记录，最新的在前
+    logs: Vec<DeliveryLog>,
+}
+
+/// Webhook 投递器
+pub struct WebhookDispatcher {
+    store: Mutex<WebhookStore>,
+    path: PathBuf,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    /// 打开数据目录下的 Webhook 配置
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(WEBHOOKS_FILE);
+        let store = if path.exists() {
+            fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    println!("[WARN] Webhook 配置文件无效，已忽略: {}", e);
+                    WebhookStore::default()
+                })
+        } else {
+            WebhookStore::default()
+        };
+
+        Self {
+            store: Mutex::new(store),
+            path,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, WebhookStore>> {
+        self.store.lock().map_err(|_| anyhow!("Webhook 配置不可用"))
+    }
+
+    fn save(&self, store: &WebhookStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(store)?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// 获取全部接收端（签名密钥以掩码代替）
+    pub fn sinks(&self) -> Result<Vec<WebhookSink>> {
+        Ok(self.store()?.sinks.iter().map(WebhookSink::masked).collect())
+    }
+
+    /// 保存全部接收端，密钥为掩码时保留原有密钥，返回掩码后的接收端
+    pub fn set_sinks(&self, mut sinks: Vec<WebhookSink>) -> Result<Vec<WebhookSink>> {
+        let mut store = self.store()?;
+        for sink in &mut sinks {
+            if sink.secret.as_deref() == Some(SECRET_MASK) {
+                sink.secret = store
+                    .sinks
+                    .iter()
+                    .find(|existing| existing.id == sink.id)
+                    .and_then(|existing| existing.secret.clone());
+            }
+
+            let url = reqwest::Url::parse(&sink.url).map_err(|e| anyhow!("无效的 Webhook 地址 {}: {}", sink.url, e))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(anyhow!("Webhook 地址必须是 http 或 https: {}", sink.url));
+            }
+            for (name, value) in &sink.headers {
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| anyhow!("Webhook {} 的请求头名称无效: {}", sink.name, name))?;
+                reqwest::header::HeaderValue::from_str(value)
+                    .map_err(|_| anyhow!("Webhook {} 的请求头 {} 的值无效", sink.name, name))?;
+            }
+            sink.max_retries = sink.max_retries.min(MAX_RETRIES);
+            sink.timeout_seconds = sink.timeout_seconds.clamp(1, MAX_TIMEOUT_SECONDS);
+            if sink.body_template.trim().is_empty() {
+                sink.body_template = DEFAULT_BODY_TEMPLATE.to_string();
+            }
+            render_body(&sink.body_template, &sample_event())
+                .map_err(|e| anyhow!("Webhook {} 的请求体模板无效: {}", sink.name, e))?;
+            if sink.id.is_empty() {
+                sink.id = uuid::Uuid::new_v4().to_string();
+            }
+        }
+
+        store.sinks = sinks.clone();
+        self.save(&store)?;
+        Ok(sinks.iter().map(WebhookSink::masked).collect())
+    }
+
+    /// 获取投递记录（最新的在前）
+    pub fn delivery_logs(&self, sink_id: Option<&str>) -> Result<Vec<DeliveryLog>> {
+        let store = self.store()?;
+        Ok(store
+            .logs
+            .iter()
+            .filter(|log| sink_id.is_none_or(|id| log.sink_id == id))
+            .cloned()
+            .collect())
+    }
+
+    /// 在后台向订阅了该事件的接收端投递
+    pub fn dispatch(self: &Arc<Self>, event: WebhookEvent) {
+        let sinks = match self.store() {
+            Ok(store) => store
+                .sinks
+                .iter()
+                .filter(|sink| sink.enabled && sink.subscribes(event.event))
+                .cloned()
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                println!("[WARN] {}", e);
+                return;
+            }
+        };
+
+        for sink in sinks {
+            let dispatcher = Arc::clone(self);
+            let event = event.clone();
+            tauri::async_runtime::spawn(async move {
+                dispatcher.deliver(&sink, &event).await;
+            });
+        }
+    }
+
+    /// 向指定接收端发送测试事件并返回投递结果
+    pub async fn test_sink(&self, sink_id: &str) -> Result<DeliveryLog> {
+        let sink = self
+            .store()?
+            .sinks
+            .iter()
+            .find(|sink| sink.id == sink_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Webhook 不存在"))?;
+        Ok(self.deliver(&sink, &sample_event()).await)
+    }
+
+    /// 投递一次事件（失败时按指数退避重试），并保存投递记录
+    pub async fn deliver(&self, sink: &WebhookSink, event: &WebhookEvent) -> DeliveryLog {
+        let started = Instant::now();
+        let mut log = DeliveryLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            sink_id: sink.id.clone(),
+            sink_name: sink.name.clone(),
+            event: event.event,
+            account_id: event.account_id.clone(),
+            url: sink.url.clone(),
+            attempts: 0,
+            status_code: None,
+            success: false,
+            error: None,
+            request_body: String::new(),
+            response_body: String::new(),
+            delivered_at: chrono::Utc::now().timestamp(),
+            duration_ms: 0,
+        };
+
+        match render_body(&sink.body_template, event) {
+            Ok(body) => log.request_body = body,
+            Err(e) => {
+                log.error = Some(format!("渲染请求体失败: {}", e));
+                self.record(log.clone());
+                return log;
+            }
+        }
+
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            log.attempts += 1;
+            let retryable = match self.send(sink, &log.request_body).await {
+                Ok((status, body)) => {
+                    log.status_code = Some(status.as_u16());
+                    log.response_body = body.chars().take(MAX_RESPONSE_BODY).collect();
+                    log.success = status.is_success();
+                    log.error = (!log.success).then(|| format!("HTTP {}", status));
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => {
+                    log.error = Some(e.to_string());
+                    true
+                }
+            };
+
+            if log.success || !retryable || log.attempts > sink.max_retries.min(MAX_RETRIES) {
+                break;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+
+        log.duration_ms = started.elapsed().as_millis() as u64;
+        if !log.success {
+            println!(
+                "[WARN] Webhook {} 投递失败（{} 次）: {}",
+                sink.name,
+                log.attempts,
+                log.error.as_deref().unwrap_or_default()
+            );
+        }
+        self.record(log.clone());
+        log
+    }
+
+    async fn send(&self, sink: &WebhookSink, body: &str) -> Result<(reqwest::StatusCode, String)> {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let mut request = self
+            .client
+            .post(&sink.url)
+            .timeout(Duration::from_secs(sink.timeout_seconds.clamp(1, MAX_TIMEOUT_SECONDS)))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Trae-Timestamp", &timestamp);
+
+        if let Some(secret) = sink.secret.as_deref().filter(|s| !s.is_empty()) {
+            request = request.header("X-Trae-Signature", format!("sha256={}", sign(secret, &timestamp, body)?));
+        }
+        for (name, value) in &sink.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.body(body.to_string()).send().await?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        Ok((status, text))
+    }
+
+    fn record(&self, log: DeliveryLog) {
+        let Ok(mut store) = self.store() else {
+            return;
+        };
+        store.logs.insert(0, log);
+        store.logs.truncate(MAX_DELIVERY_LOGS);
+        if let Err(e) = self.save(&store) {
+            println!("[WARN] 保存 Webhook 投递记录失败: {}", e);
+        }
+    }
+}
+
+/// 计算签名：HMAC-SHA256(secret, "{timestamp}.{body}")，十六进制编码
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| anyhow!("无效的签名密钥: {}", e))?;
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// 按模板渲染请求体，渲染结果必须是合法的 JSON
+pub fn render_body(template: &str, event: &WebhookEvent) -> Result<String> {
+    // 字符串占位符插入 JSON 转义后的内容（不含引号）
+    let escape = |text: &str| {
+        let quoted = serde_json::to_string(text).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    let event_name = serde_json::to_value(event.event)?
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    let data = serde_json::to_string(&event.data)?;
+    let occurred_at = event.occurred_at.to_string();
+    let account_id = escape(&event.account_id);
+    let account_name = escape(&event.account_name);
+    let message = escape(&event.message);
+
+    // 一次扫描完成替换，插入的内容中即使包含占位符也不会被再次展开
+    let mut body = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        body.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let value = match &after[..end] {
+            "event" => Some(event_name.as_str()),
+            "account_id" => Some(account_id.as_str()),
+            "account_name" => Some(account_name.as_str()),
+            "message" => Some(message.as_str()),
+            "occurred_at" => Some(occurred_at.as_str()),
+            "data" => Some(data.as_str()),
+            _ => None,
+        };
+        match value {
+            Some(value) => {
+                body.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                // 未知占位符原样保留
+                body.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    body.push_str(rest);
+
+    serde_json::from_str::<serde_json::Value>(&body).map_err(|e| anyhow!("请求体不是合法的 JSON: {}", e))?;
+    Ok(body)
+}
+
+/// 测试事件
+fn sample_event() -> WebhookEvent {
+    WebhookEvent {
+        event: WebhookEventKind::Test,
+        account_id: "test".to_string(),
+        account_name: "test@example.com".to_string(),
+        message: "这是一条测试消息".to_string(),
+        occurred_at: chrono::Utc::now().timestamp(),
+        data: serde_json::json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 在本地端口接收一次请求，返回 (请求头, 请求体)
+    async fn receive_once(listener: TcpListener, status: &'static str) -> (String, String) {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let (head, body) = loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            if let Some(split) = text.find("\r\n\r\n") {
+                let head = text[..split].to_lowercase();
+                let length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if buf.len() >= split + 4 + length {
+                    break (head, text[split + 4..split + 4 + length].to_string());
+                }
+            }
+        };
+        let response = format!("HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok", status);
+        socket.write_all(response.as_bytes()).await.unwrap();
+        (head, body)
+    }
+
+    fn dispatcher() -> WebhookDispatcher {
+        let dir = std::env::temp_dir().join(format!("webhook-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        WebhookDispatcher::open(&dir)
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_event_to_local_receiver() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let receiver = tokio::spawn(receive_once(listener, "200 OK"));
+
+        let dispatcher = dispatcher();
+        let sinks = dispatcher
+            .set_sinks(vec![WebhookSink {
+                name: "local".to_string(),
+                url,
+                secret: Some("s3cret".to_string()),
+                ..Default::default()
+            }])
+            .unwrap();
+        assert_eq!(sinks[0].secret.as_deref(), Some(SECRET_MASK));
+
+        let log = dispatcher.test_sink(&sinks[0].id).await.unwrap();
+        let (head, body) = receiver.await.unwrap();
+
+        assert!(log.success, "{:?}", log.error);
+        assert_eq!(log.status_code, Some(200));
+        assert_eq!(log.response_body, "ok");
+        assert_eq!(body, log.request_body);
+        let timestamp = head
+            .lines()
+            .find_map(|line| line.strip_prefix("x-trae-timestamp:"))
+            .unwrap()
+            .trim()
+            .to_string();
+        let expected = format!("x-trae-signature: sha256={}", sign("s3cret", &timestamp, &body).unwrap());
+        assert!(head.contains(&expected));
+
+        // 传回掩码时保留原有密钥
+        dispatcher.set_sinks(sinks).unwrap();
+        let stored = dispatcher.store().unwrap().sinks[0].secret.clone();
+        assert_eq!(stored.as_deref(), Some("s3cret"));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let receiver = tokio::spawn(receive_once(listener, "400 Bad Request"));
+
+        let dispatcher = dispatcher();
+        let sink = WebhookSink { id: "sink".to_string(), url, ..Default::default() };
+        let log = dispatcher.deliver(&sink, &sample_event()).await;
+        receiver.await.unwrap();
+
+        assert!(!log.success);
+        assert_eq!(log.attempts, 1);
+        assert_eq!(log.status_code, Some(400));
+    }
+
+    #[test]
+    fn placeholders_in_values_are_not_expanded() {
+        let event = WebhookEvent {
+            message: "{{account_id}} \"quoted\"".to_string(),
+            ..sample_event()
+        };
+        let body = render_body(DEFAULT_BODY_TEMPLATE, &event).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["message"], "{{account_id}} \"quoted\"");
+        assert_eq!(value["account_id"], "test");
+
+        let body = render_body(r#"{"a":"{{unknown}}","b":"{{event}}"}"#, &event).unwrap();
+        assert_eq!(body, r#"{"a":"{{unknown}}","b":"test"}"#);
+    }
+
+    #[test]
+    fn set_sinks_validates_headers_and_clamps_limits() {
+        let dispatcher = dispatcher();
+        let sink = WebhookSink {
+            name: "local".to_string(),
+            url: "http://127.0.0.1/hook".to_string(),
+            ..Default::default()
+        };
+
+        let invalid_name = WebhookSink {
+            headers: [("Bad Header".to_string(), "v".to_string())].into(),
+            ..sink.clone()
+        };
+        assert!(dispatcher.set_sinks(vec![invalid_name]).is_err());
+        let invalid_value = WebhookSink {
+            headers: [("X-Token".to_string(), "a\nb".to_string())].into(),
+            ..sink.clone()
+        };
+        assert!(dispatcher.set_sinks(vec![invalid_value]).is_err());
+        assert!(dispatcher.sinks().unwrap().is_empty());
+
+        let saved = dispatcher
+            .set_sinks(vec![WebhookSink {
+                headers: [("X-Token".to_string(), "abc".to_string())].into(),
+                max_retries: 1000,
+                timeout_seconds: 0,
+                ..sink.clone()
+            }])
+            .unwrap();
+        assert_eq!(saved[0].max_retries, MAX_RETRIES);
+        assert_eq!(saved[0].timeout_seconds, 1);
+
+        let saved = dispatcher
+            .set_sinks(vec![WebhookSink { timeout_seconds: 3600, ..sink }])
+            .unwrap();
+        assert_eq!(saved[0].timeout_seconds, MAX_TIMEOUT_SECONDS);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod dispatcher;
+pub mod types;
+
+pub use dispatcher::WebhookDispatcher;
+pub use types::*;
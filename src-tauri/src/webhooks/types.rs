@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 触发 Webhook 的事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    /// 额度告警
+    QuotaThreshold,
+    /// Token 即将过期
+    TokenExpiry,
+    /// 凭据失效（Token 过期且无法用 Cookies 刷新）
+    CredentialsInvalid,
+    /// 刷新使用量失败
+    RefreshFailed,
+    /// 手动测试
+    Test,
+}
+
+/// 默认请求体模板
+pub const DEFAULT_BODY_TEMPLATE: &str = r#"{"event":"{{event}}","account_id":"{{account_id}}","account":"{{account_name}}","message":"{{message}}","occurred_at":{{occurred_at}},"data":{{data}}}"#;
+
+/// Webhook 接收端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSink {
+    /// 为空时自动生成
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    /// 订阅的事件类型
+    pub events: Vec<WebhookEventKind>,
+    /// JSON 请求体模板，支持 {{event}}、{{account_id}}、{{account_name}}、{{message}}、
+    /// {{occurred_at}}（原样插入）和 {{data}}（插入 JSON）
+    pub body_template: String,
+    /// 设置后使用 HMAC-SHA256 签名请求体（读取时返回 `SECRET_MASK`）
+    pub secret: Option<String>,
+    /// 额外请求头
+    pub headers: BTreeMap<String, String>,
+    pub enabled: bool,
+    /// 失败后的最大重试次数（保存时限制在 10 次以内）
+    pub max_retries: u32,
+    /// 单次请求超时（秒，保存时限制在 1～60）
+    pub timeout_seconds: u64,
+}
+
+impl Default for WebhookSink {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            url: String::new(),
+            events: vec![
+                WebhookEventKind::QuotaThreshold,
+                WebhookEventKind::TokenExpiry,
+                WebhookEventKind::CredentialsInvalid,
+                WebhookEventKind::RefreshFailed,
+            ],
+            body_template: DEFAULT_BODY_TEMPLATE.to_string(),
+            secret: None,
+            headers: BTreeMap::new(),
+            enabled: true,
+            max_retries: 3,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+/// 返回给前端时代替签名密钥的掩码，保存时原样传回表示不修改密钥
+pub const SECRET_MASK: &str = "********";
+
+impl WebhookSink {
+    /// 隐藏签名密钥后的副本
+    pub fn masked(&self) -> Self {
+        let mut sink = self.clone();
+        if sink.secret.as_deref().is_some_and(|s| !s.is_empty()) {
+            sink.secret = Some(SECRET_MASK.to_string());
+        }
+        sink
+    }
+
+    /// 是否订阅了该事件（测试事件总是发送）
+    pub fn subscribes(&self, event: WebhookEventKind) -> bool {
+        event == WebhookEventKind::Test || self.events.contains(&event)
+    }
+}
+
+/// Webhook 事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub event: WebhookEventKind,
+    pub account_id: String,
+    pub account_name: String,
+    pub message: String,
+    pub occurred_at: i64,
+    /// 事件相关数据（如告警详情）
+    pub data: Value,
+}
+
+/// 一次投递记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryLog {
+    pub id: String,
+    pub sink_id: String,
+    pub sink_name: String,
+    pub event: WebhookEventKind,
+    pub account_id: String,
+    pub url: String,
+    /// 实际请求次数（含重试）
+    pub attempts: u32,
+    pub status_code: Option<u16>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub request_body: String,
+    pub response_body: String,
+    pub delivered_at: i64,
+    pub duration_ms: u64,
+}
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Account, AccountBrief, UsageSummary, UsageEventsResponse } from "./types";
+import type { Account, AccountBrief, UsageSummary, UsageEventsResponse, EntitlementDiagnostics, UsageDetail, PackageCatalogView, SubscriptionInfo, AccountRenewal, AccountFilter, UsageSnapshot, HistorySettings, RetentionReport, UsageEvent, SyncReport, UsageAnalyticsQuery, UsageAnalytics, CacheReportQuery, CacheEfficiencyReport, CycleRange, BillingCycle, CycleComparisonQuery, CycleComparison, HeatmapQuery, ActivityHeatmap, PriceTableView, CostEstimateQuery, CostEstimateReport, BurnRateForecast, AlertSettings, Alert, WebhookSink, DeliveryLog, Budget, BudgetStatus, AnomalySettings, UsageAnomaly, TeamDashboardQuery, TeamDashboard, BatchRefreshOptions, BatchRefreshReport, SchedulerSettings, AccountSchedule, SchedulerStatus } from "./types";
 
 // 添加账号（通过 Cookies）
 export async function addAccount(cookies: string): Promise<Account> {
@@ -16,9 +16,9 @@ export async function removeAccount(accountId: string): Promise<void> {
   return invoke("remove_account", { accountId });
 }
 
-// 获取所有账号
-export async function getAccounts(): Promise<AccountBrief[]> {
-  return invoke("get_accounts");
+// 获取所有账号（可选过滤条件）
+export async function getAccounts(filter?: AccountFilter): Promise<AccountBrief[]> {
+  return invoke("get_accounts", { filter });
 }
 
 // 获取单个账号详情（包含 token）
@@ -26,6 +26,11 @@ export async function getAccount(accountId: string): Promise<Account> {
   return invoke("get_account", { accountId });
 }
 
+// 从磁盘重新加载账号数据，返回账号数量
+export async function reloadAccounts(): Promise<number> {
+  return invoke("reload_accounts");
+}
+
 // 设置活跃账号
 export async function setActiveAccount(accountId: string): Promise<void> {
   return invoke("switch_account", { accountId });
@@ -41,6 +46,51 @@ export async function getAccountUsage(accountId: string): Promise<UsageSummary>
   return invoke("get_account_usage", { accountId });
 }
 
+// 获取团队看板（每完成一个账号推送 team-dashboard-progress 事件）
+export async function getTeamDashboard(query: TeamDashboardQuery = {}): Promise<TeamDashboard> {
+  return invoke("get_team_dashboard", { query });
+}
+
+// 批量刷新账号使用量（每完成一个账号推送 batch-refresh-progress 事件）
+export async function refreshAllAccounts(options: BatchRefreshOptions = {}): Promise<BatchRefreshReport> {
+  return invoke("refresh_all_accounts", { options });
+}
+
+// 取消正在进行的批量刷新
+export async function cancelBatchRefresh(): Promise<boolean> {
+  return invoke("cancel_batch_refresh");
+}
+
+// 获取账号使用量明细（列出所有权益包）
+export async function getAccountUsageDetail(accountId: string): Promise<UsageDetail> {
+  return invoke("get_account_usage_detail", { accountId });
+}
+
+// 获取账号订阅信息
+export async function getAccountSubscription(accountId: string): Promise<SubscriptionInfo | null> {
+  return invoke("get_account_subscription", { accountId });
+}
+
+// 获取所有账号的续费计划（按下次扣费时间排序）
+export async function getUpcomingRenewals(): Promise<AccountRenewal[]> {
+  return invoke("get_upcoming_renewals");
+}
+
+// 获取最近一次配额响应的诊断信息
+export async function getEntitlementDiagnostics(accountId: string): Promise<EntitlementDiagnostics | null> {
+  return invoke("get_entitlement_diagnostics", { accountId });
+}
+
+// 获取礼包目录
+export async function getPackageCatalog(): Promise<PackageCatalogView> {
+  return invoke("get_package_catalog");
+}
+
+// 重新加载礼包目录（修改覆盖文件后调用）
+export async function reloadPackageCatalog(): Promise<PackageCatalogView> {
+  return invoke("reload_package_catalog");
+}
+
 // 更新账号 Token
 export async function updateAccountToken(accountId: string, token: string): Promise<UsageSummary> {
   return invoke("update_account_token", { accountId, token });
@@ -83,11 +133,220 @@ export async function getUsageEvents(
   });
 }
 
+// 同步时间范围内的全部使用事件到本地
+export async function syncUsageEvents(accountId: string, startTime: number, endTime: number): Promise<SyncReport> {
+  return invoke("sync_usage_events", { accountId, startTime, endTime });
+}
+
+// 查询本地已同步的使用事件
+export async function getSyncedUsageEvents(accountId: string, startTime: number, endTime: number): Promise<UsageEvent[]> {
+  return invoke("get_synced_usage_events", { accountId, startTime, endTime });
+}
+
 // 从 Trae IDE 读取当前登录账号
 export async function readTraeAccount(): Promise<Account | null> {
   return invoke("read_trae_account");
 }
 
+// ============ 使用分析相关 API ============
+
+// 按模型、模式、天、周或计费周期统计使用事件
+export async function getUsageAnalytics(query: UsageAnalyticsQuery): Promise<UsageAnalytics> {
+  return invoke("get_usage_analytics", { query });
+}
+
+// 获取缓存效率报告
+export async function getCacheEfficiencyReport(query: CacheReportQuery): Promise<CacheEfficiencyReport> {
+  return invoke("get_cache_efficiency_report", { query });
+}
+
+// 获取账号的计费周期（当前、上一个或最近 N 个）
+export async function getBillingCycles(accountId: string, range: CycleRange, timezone?: string): Promise<BillingCycle[]> {
+  return invoke("get_billing_cycles", { accountId, range, timezone });
+}
+
+// 获取某个计费周期内的使用事件（0 为当前周期，1 为上一个周期）
+export async function getCycleUsageEvents(
+  accountId: string,
+  cycleIndex: number = 0,
+  pageNum: number = 1,
+  pageSize: number = 20,
+  timezone?: string
+): Promise<UsageEventsResponse> {
+  return invoke("get_cycle_usage_events", {
+    accountId,
+    cycleIndex,
+    pageNum,
+    pageSize,
+    timezone
+  });
+}
+
+// 对比最近几个计费周期的用量
+export async function compareBillingCycles(query: CycleComparisonQuery): Promise<CycleComparison> {
+  return invoke("compare_billing_cycles", { query });
+}
+
+// 获取按星期和小时统计的活跃度热力图
+export async function getActivityHeatmap(query: HeatmapQuery): Promise<ActivityHeatmap> {
+  return invoke("get_activity_heatmap", { query });
+}
+
+// 获取模型价格表
+export async function getPriceTable(): Promise<PriceTableView> {
+  return invoke("get_price_table");
+}
+
+// 从 JSON 文件导入模型价格表
+export async function importPriceTable(path: string): Promise<PriceTableView> {
+  return invoke("import_price_table", { path });
+}
+
+// 恢复内置模型价格表
+export async function resetPriceTable(): Promise<PriceTableView> {
+  return invoke("reset_price_table");
+}
+
+// 按模型价格表估算费用
+export async function getCostEstimate(query: CostEstimateQuery): Promise<CostEstimateReport> {
+  return invoke("get_cost_estimate", { query });
+}
+
+// 预测账号额度耗尽时间
+export async function getAccountForecast(accountId: string, timezone?: string): Promise<BurnRateForecast> {
+  return invoke("get_account_forecast", { accountId, timezone });
+}
+
+// ============ 使用量历史相关 API ============
+
+// 查询使用量历史快照
+export async function getUsageHistory(
+  accountId: string,
+  startTime: number,
+  endTime: number,
+  bucketSeconds?: number
+): Promise<UsageSnapshot[]> {
+  return invoke("get_usage_history", { accountId, startTime, endTime, bucketSeconds });
+}
+
+// 获取历史设置
+export async function getHistorySettings(): Promise<HistorySettings> {
+  return invoke("get_history_settings");
+}
+
+// 更新历史设置
+export async function setHistorySettings(settings: HistorySettings): Promise<void> {
+  return invoke("set_history_settings", { settings });
+}
+
+// 立即执行保留策略
+export async function pruneUsageHistory(): Promise<RetentionReport> {
+  return invoke("prune_usage_history");
+}
+
+// ============ 告警相关 API ============
+
+// 获取告警设置
+export async function getAlertSettings(): Promise<AlertSettings> {
+  return invoke("get_alert_settings");
+}
+
+// 更新告警设置
+export async function setAlertSettings(settings: AlertSettings): Promise<AlertSettings> {
+  return invoke("set_alert_settings", { settings });
+}
+
+// 获取告警列表
+export async function getAlerts(includeResolved: boolean = false): Promise<Alert[]> {
+  return invoke("get_alerts", { includeResolved });
+}
+
+// 暂停告警提醒
+export async function snoozeAlert(alertId: string, until: number): Promise<Alert> {
+  return invoke("snooze_alert", { alertId, until });
+}
+
+// 确认告警
+export async function acknowledgeAlert(alertId: string): Promise<Alert> {
+  return invoke("acknowledge_alert", { alertId });
+}
+
+// 清除已解除的告警记录
+export async function clearResolvedAlerts(): Promise<number> {
+  return invoke("clear_resolved_alerts");
+}
+
+// ============ 预算相关 API ============
+
+// 获取全部预算
+export async function getBudgets(): Promise<Budget[]> {
+  return invoke("get_budgets");
+}
+
+// 设置账号预算
+export async function setBudget(budget: Budget): Promise<void> {
+  return invoke("set_budget", { budget });
+}
+
+// 删除账号预算
+export async function removeBudget(accountId: string): Promise<void> {
+  return invoke("remove_budget", { accountId });
+}
+
+// 计算账号本月的预算使用情况
+export async function getBudgetStatus(accountId: string, timezone?: string): Promise<BudgetStatus> {
+  return invoke("get_budget_status", { accountId, timezone });
+}
+
+// ============ 用量异常相关 API ============
+
+// 获取异常检测设置
+export async function getAnomalySettings(): Promise<AnomalySettings> {
+  return invoke("get_anomaly_settings");
+}
+
+// 更新异常检测设置
+export async function setAnomalySettings(settings: AnomalySettings): Promise<AnomalySettings> {
+  return invoke("set_anomaly_settings", { settings });
+}
+
+// 立即检测账号的用量异常
+export async function detectUsageAnomalies(accountId: string, timezone?: string): Promise<UsageAnomaly[]> {
+  return invoke("detect_usage_anomalies", { accountId, timezone });
+}
+
+// 获取用量异常记录（不传账号时返回全部账号）
+export async function getUsageAnomalies(accountId?: string, includeAcknowledged = false): Promise<UsageAnomaly[]> {
+  return invoke("get_usage_anomalies", { accountId, includeAcknowledged });
+}
+
+// 确认用量异常
+export async function acknowledgeAnomaly(anomalyId: string): Promise<UsageAnomaly> {
+  return invoke("acknowledge_anomaly", { anomalyId });
+}
+
+// ============ Webhook 相关 API ============
+
+// 获取 Webhook 接收端
+export async function getWebhooks(): Promise<WebhookSink[]> {
+  return invoke("get_webhooks");
+}
+
+// 保存 Webhook 接收端
+export async function setWebhooks(sinks: WebhookSink[]): Promise<WebhookSink[]> {
+  return invoke("set_webhooks", { sinks });
+}
+
+// 向 Webhook 发送测试事件
+export async function testWebhook(sinkId: string): Promise<DeliveryLog> {
+  return invoke("test_webhook", { sinkId });
+}
+
+// 获取 Webhook 投递记录
+export async function getWebhookLogs(sinkId?: string): Promise<DeliveryLog[]> {
+  return invoke("get_webhook_logs", { sinkId });
+}
+
 // ============ 机器码相关 API ============
 
 // 获取当前系统机器码
@@ -150,3 +409,23 @@ export async function scanTraePath(): Promise<string> {
 export async function claimGift(accountId: string): Promise<void> {
   return invoke("claim_gift", { accountId });
 }
+
+// 获取后台刷新状态
+export async function getRefreshSchedule(): Promise<SchedulerStatus> {
+  return invoke("get_refresh_schedule");
+}
+
+// 更新后台刷新设置
+export async function setRefreshSchedulerSettings(settings: SchedulerSettings): Promise<SchedulerSettings> {
+  return invoke("set_refresh_scheduler_settings", { settings });
+}
+
+// 设置账号的刷新间隔（分钟，为空时使用全局默认）
+export async function setAccountRefreshInterval(accountId: string, intervalMinutes: number | null): Promise<AccountSchedule> {
+  return invoke("set_account_refresh_interval", { accountId, intervalMinutes });
+}
+
+// 立即在后台刷新指定账号（为空时刷新全部账号）
+export async function runRefreshNow(accountIds: string[] = []): Promise<void> {
+  return invoke("run_refresh_now", { accountIds });
+}
@@ -1,5 +1,7 @@
+import { useEffect, useState } from "react";
 import { useTranslation } from "react-i18next";
-import type { AccountBrief, UsageSummary } from "../types";
+import { acknowledgeAnomaly, getUsageAnomalies } from "../api";
+import type { AccountBrief, UsageAnomaly, UsageSummary } from "../types";
 
 interface DetailModalProps {
   isOpen: boolean;
@@ -10,9 +12,26 @@ interface DetailModalProps {
 
 export function DetailModal({ isOpen, onClose, account, usage }: DetailModalProps) {
   const { t } = useTranslation();
-  if (!isOpen || !account) return null;
+  const [anomalies, setAnomalies] = useState<UsageAnomaly[]>([]);
+  const accountId = account?.id;
+
+  useEffect(() => {
+    if (!isOpen || !accountId) return;
+    getUsageAnomalies(accountId)
+      .then(setAnomalies)
+      .catch((err) => console.error("Failed to load usage anomalies:", err));
+  }, [isOpen, accountId]);
 
+  const handleAcknowledge = async (anomalyId: string) => {
+    try {
+      await acknowledgeAnomaly(anomalyId);
+      setAnomalies((prev) => prev.filter((a) => a.id !== anomalyId));
+    } catch (err) {
+      console.error("Failed to acknowledge anomaly:", err);
+    }
+  };
 
+  if (!isOpen || !account) return null;
 
   return (
     <div className="modal-overlay" onClick={onClose}>
@@ -116,6 +135,35 @@ export function DetailModal({ isOpen, onClose, account, usage }: DetailModalProp
                 </div>
               </div>
             </div>
+
+            {/* Usage Spikes */}
+            {anomalies.length > 0 && (
+              <div className="detail-section column-span-full">
+                <h4>{t("accounts.usage_spikes")}</h4>
+                {anomalies.map((anomaly) => (
+                  <div className="detail-anomaly" key={anomaly.id}>
+                    <div className="detail-item">
+                      <span className="detail-label">
+                        {anomaly.bucket_key}
+                        {anomaly.model_name ? ` · ${anomaly.model_name}` : ""}
+                      </span>
+                      <span className="detail-value highlight">
+                        {anomaly.value.toFixed(1)} ({t("accounts.spike_ratio", { ratio: anomaly.ratio.toFixed(1) })})
+                      </span>
+                    </div>
+                    <div className="detail-anomaly-sessions">
+                      <span className="detail-label">{t("accounts.spike_sessions")}</span>
+                      {anomaly.session_ids.map((sessionId) => (
+                        <code key={sessionId}>{sessionId}</code>
+                      ))}
+                    </div>
+                    <button className="modal-btn-secondary" onClick={() => handleAcknowledge(anomaly.id)}>
+                      {t("accounts.spike_acknowledge")}
+                    </button>
+                  </div>
+                ))}
+              </div>
+            )}
           </div>
         </div>
 
@@ -9,6 +9,25 @@ export interface AccountBrief {
   created_at: number;
   machine_id: string | null;
   is_current: boolean; // 是否是当前 Trae IDE 正在使用的账号
+  features: FeatureFlags;
+  forecast: ForecastBrief | null; // 最近一次额度消耗预测
+  budget: BudgetStatus | null; // 本月预算使用情况
+}
+
+// 账号可用的功能
+export type Feature = "solo_builder" | "solo_builder_v1" | "solo_coder" | "super_model";
+
+// 功能开关
+export interface FeatureFlags {
+  solo_builder: boolean;
+  solo_builder_v1: boolean;
+  solo_coder: boolean;
+  super_model: boolean;
+}
+
+// 账号列表过滤条件
+export interface AccountFilter {
+  features?: Feature[];
 }
 
 // 完整账号信息
@@ -28,12 +47,45 @@ export interface Account {
   updated_at: number;
   is_active: boolean;
   machine_id: string | null;
+  subscription: SubscriptionInfo | null;
+  features: FeatureFlags;
+  last_usage: CachedUsage | null;
+}
+
+// 缓存的使用量及其拉取时间
+export interface CachedUsage {
+  summary: UsageSummary;
+  fetched_at: number;
+}
+
+// 订阅与计费信息
+export interface SubscriptionInfo {
+  plan_type: string;
+  period: string;
+  next_billing_time: number;
+  is_last_period: boolean;
+  charge_amount: number;
+  currency: string;
+  expire_time: number;
+  yearly_expire_time: number;
+  is_pay_freshman: boolean;
+}
+
+// 账号续费信息
+export interface AccountRenewal {
+  account_id: string;
+  name: string;
+  email: string;
+  subscription: SubscriptionInfo;
 }
 
 // 使用量汇总
 export interface UsageSummary {
   plan_type: string;
+  plan_period: string;
+  plan_status: string;
   reset_time: number;
+  cycle_start_time: number; // 当前计费周期开始时间
 
   // Fast Request
   fast_request_used: number;
@@ -45,7 +97,18 @@ export interface UsageSummary {
   extra_fast_request_limit: number;
   extra_fast_request_left: number;
   extra_expire_time: number;
+  extra_start_time: number; // 最早生效的礼包的开始时间
   extra_package_name: string;
+  extra_package_source: string;
+  extra_package_duration: string;
+  extra_package_status: string;
+  extra_package_count: number;
+
+  // 订阅与计费信息（免费套餐为 null）
+  subscription: SubscriptionInfo | null;
+
+  // 功能开关
+  features: FeatureFlags;
 
   // Slow Request
   slow_request_used: number;
@@ -61,6 +124,63 @@ export interface UsageSummary {
   autocomplete_used: number;
   autocomplete_limit: number;
   autocomplete_left: number;
+
+  // 刷新失败时返回缓存数据的说明（实时数据为 null）
+  stale: UsageStaleness | null;
+}
+
+// 过期使用量的说明
+export interface UsageStaleness {
+  fetched_at: number;
+  age_seconds: number;
+  error: string;
+}
+
+// 单项配额使用情况
+export interface QuotaUsage {
+  limit: number;
+  used: number;
+  left: number;
+}
+
+// 单个权益包的使用明细
+export interface UsagePackDetail {
+  entitlement_id: string;
+  source_id: string;
+  name: string;
+  description: string;
+  names: Record<string, string>;
+  product_type: string;
+  product_id: number;
+  source: string;
+  duration: string;
+  status: string;
+  counts_towards_quota: boolean;
+  start_time: number;
+  end_time: number;
+  fast_request: QuotaUsage;
+  slow_request: QuotaUsage;
+  advanced_model: QuotaUsage;
+  autocomplete: QuotaUsage;
+  features: FeatureFlags;
+}
+
+// 所有权益包的合计
+export interface UsageTotals {
+  pack_count: number;
+  active_pack_count: number;
+  fast_request: QuotaUsage;
+  slow_request: QuotaUsage;
+  advanced_model: QuotaUsage;
+  autocomplete: QuotaUsage;
+  features: FeatureFlags;
+}
+
+// 使用量明细
+export interface UsageDetail {
+  summary: UsageSummary;
+  packs: UsagePackDetail[];
+  totals: UsageTotals;
 }
 
 // 使用事件
@@ -87,6 +207,716 @@ export interface UsageEventsResponse {
   user_usage_group_by_sessions: UsageEvent[];
 }
 
+// 配额响应诊断信息
+export interface EntitlementDiagnostics {
+  fetched_at: number;
+  pack_count: number;
+  pack_errors: string[];
+  raw: unknown;
+}
+
+// 礼包目录条目
+export interface PackageCatalogEntry {
+  source_type: number | null;
+  product_id: number | null;
+  duration_type: number | null;
+  name: Record<string, string>;
+  description: Record<string, string>;
+}
+
+// 一种接口取值的展示名称
+export interface CatalogValueLabels {
+  name: Record<string, string>; // 取值类型名称
+  values: Record<string, Record<string, string>>; // 原始数字 -> 名称
+}
+
+// 礼包目录
+export interface PackageCatalog {
+  locale: string;
+  packages: PackageCatalogEntry[];
+  fallback_name: Record<string, string>;
+  unknown_source_name: Record<string, string>;
+  value_labels: Record<string, CatalogValueLabels>; // 接口取值的展示名称，按取值类型分组
+  unknown_value_name: Record<string, string>; // 未收录取值的名称模板
+}
+
+// 礼包目录及覆盖文件路径
+export interface PackageCatalogView {
+  override_path: string;
+  catalog: PackageCatalog;
+}
+
+// 使用量快照
+export interface UsageSnapshot {
+  account_id: string;
+  captured_at: number;
+  plan_type: string;
+  reset_time: number;
+  fast_request_used: number;
+  fast_request_limit: number;
+  extra_fast_request_used: number;
+  extra_fast_request_limit: number;
+  slow_request_used: number;
+  slow_request_limit: number;
+  advanced_model_used: number;
+  advanced_model_limit: number;
+  autocomplete_used: number;
+  autocomplete_limit: number;
+}
+
+// 快照保留与降采样策略
+export interface RetentionPolicy {
+  raw_days: number;
+  hourly_days: number;
+  daily_days: number;
+}
+
+// 使用量历史设置
+export interface HistorySettings {
+  retention: RetentionPolicy;
+}
+
+// 执行保留策略的结果
+export interface RetentionReport {
+  downsampled: number;
+  expired: number;
+}
+
+// 使用事件同步状态
+export interface SyncState {
+  account_id: string;
+  synced_from: number;
+  synced_to: number;
+  last_synced_at: number;
+}
+
+// 使用事件同步进度（usage-sync-progress 事件）
+export interface SyncProgress {
+  account_id: string;
+  page_num: number;
+  fetched: number;
+  total: number;
+  inserted: number;
+}
+
+// 使用事件同步结果
+export interface SyncReport {
+  account_id: string;
+  start_time: number;
+  end_time: number;
+  pages: number;
+  fetched: number;
+  inserted: number;
+  state: SyncState;
+}
+
+// 使用事件分组方式
+export type GroupBy = "model" | "mode" | "max_mode" | "day" | "week" | "billing_cycle";
+
+// 使用事件来源
+export type EventSource = "stored" | "fetched";
+
+// 使用分析查询
+export interface UsageAnalyticsQuery {
+  account_id: string;
+  start_time: number;
+  end_time: number;
+  group_by: GroupBy;
+  timezone?: string | null;
+  source?: EventSource;
+}
+
+// 一组使用事件的统计
+export interface UsageGroup {
+  key: string;
+  start_time: number | null;
+  end_time: number | null;
+  request_count: number;
+  amount: number;
+  cost: number;
+  input_tokens: number;
+  output_tokens: number;
+  cache_read_tokens: number;
+  cache_write_tokens: number;
+}
+
+// 使用分析结果
+export interface UsageAnalytics {
+  account_id: string;
+  group_by: GroupBy;
+  timezone: string;
+  start_time: number;
+  end_time: number;
+  groups: UsageGroup[];
+  total: UsageGroup;
+}
+
+// 缓存 token 计费倍数
+export interface CachePricing {
+  read_multiplier: number;
+  write_multiplier: number;
+}
+
+// 缓存效率报告查询
+export interface CacheReportQuery {
+  account_id: string;
+  start_time: number;
+  end_time: number;
+  source?: EventSource;
+  pricing?: CachePricing;
+  worst_limit?: number | null;
+  min_prompt_tokens?: number | null;
+}
+
+// 缓存统计
+export interface CacheStats {
+  request_count: number;
+  input_tokens: number;
+  cache_read_tokens: number;
+  cache_write_tokens: number;
+  prompt_tokens: number;
+  hit_ratio: number;
+  write_share: number;
+  saved_tokens: number;
+  savings_ratio: number;
+}
+
+// 单个模型的缓存统计
+export interface ModelCacheStats {
+  model_name: string;
+  stats: CacheStats;
+}
+
+// 单个会话的缓存统计
+export interface SessionCacheStats {
+  session_id: string;
+  usage_time: number;
+  model_name: string;
+  mode: string;
+  stats: CacheStats;
+}
+
+// 缓存效率报告
+export interface CacheEfficiencyReport {
+  account_id: string;
+  start_time: number;
+  end_time: number;
+  pricing: CachePricing;
+  overall: CacheStats;
+  by_model: ModelCacheStats[];
+  worst_sessions: SessionCacheStats[];
+}
+
+// 计费周期范围
+export type CycleRange =
+  | { kind: "current" }
+  | { kind: "previous" }
+  | { kind: "last"; count: number };
+
+// 计费周期（index 为 0 表示当前周期）
+export interface BillingCycle {
+  index: number;
+  key: string;
+  start_time: number;
+  end_time: number;
+}
+
+// 计费周期对比查询
+export interface CycleComparisonQuery {
+  account_id: string;
+  count?: number; // 对比的周期数，默认 2
+  timezone?: string | null;
+  source?: EventSource;
+}
+
+// 单个周期的使用情况
+export interface CycleUsage {
+  cycle: BillingCycle;
+  total: UsageGroup;
+  by_model: UsageGroup[];
+  by_mode: UsageGroup[];
+}
+
+// 当前周期相对上一周期的变化
+export interface CycleChange {
+  key: string;
+  current_amount: number;
+  previous_amount: number;
+  amount_change: number;
+  amount_change_percent: number | null;
+  current_requests: number;
+  previous_requests: number;
+}
+
+// 计费周期对比结果
+export interface CycleComparison {
+  account_id: string;
+  timezone: string;
+  cycles: CycleUsage[]; // 从新到旧
+  total_change: CycleChange;
+  model_changes: CycleChange[];
+  mode_changes: CycleChange[];
+}
+
+// 活跃度热力图查询
+export interface HeatmapQuery {
+  account_ids?: string[]; // 为空时统计全部账号
+  start_time: number;
+  end_time: number;
+  timezone?: string | null;
+  source?: EventSource;
+}
+
+// 无法统计的账号
+export interface HeatmapFailure {
+  account_id: string;
+  error: string;
+}
+
+// 星期 × 小时的活跃度矩阵（行为星期，0 为周一；列为小时 0-23）
+export interface ActivityHeatmap {
+  account_ids: string[];
+  timezone: string;
+  start_time: number;
+  end_time: number;
+  requests: number[][];
+  tokens: number[][];
+  amount: number[][];
+  max_requests: number;
+  max_tokens: number;
+  total_requests: number;
+  total_tokens: number;
+  failures: HeatmapFailure[];
+}
+
+// 模型价格（每百万 token）
+export interface ModelPrice {
+  model: string;
+  mode?: string | null;
+  max_mode?: boolean | null;
+  input: number;
+  output: number;
+  cache_read: number;
+  cache_write: number;
+}
+
+// 模型价格表
+export interface PriceTable {
+  currency: string;
+  source: string; // 价格来源说明
+  updated_at: string; // 价格整理日期（YYYY-MM-DD）
+  prices: ModelPrice[];
+}
+
+// 模型价格表及导入文件路径
+export interface PriceTableView {
+  override_path: string;
+  table: PriceTable;
+}
+
+// 费用明细
+export interface CostBreakdown {
+  input: number;
+  output: number;
+  cache_read: number;
+  cache_write: number;
+  total: number;
+}
+
+// 费用估算查询
+export interface CostEstimateQuery {
+  account_id: string;
+  start_time: number;
+  end_time: number;
+  source?: EventSource;
+}
+
+// 单个模型的费用估算
+export interface ModelCostEstimate {
+  model_name: string;
+  request_count: number;
+  amount: number;
+  input_tokens: number;
+  output_tokens: number;
+  cache_read_tokens: number;
+  cache_write_tokens: number;
+  priced: boolean;
+  cost: CostBreakdown;
+}
+
+// 费用估算报告
+export interface CostEstimateReport {
+  account_id: string;
+  start_time: number;
+  end_time: number;
+  currency: string;
+  total: CostBreakdown;
+  by_model: ModelCostEstimate[];
+  unpriced_sessions: number;
+}
+
+// 额度类别
+export type QuotaCategory = "fast" | "slow" | "advanced_model" | "autocomplete" | "extra_pack";
+
+// 单个额度类别的预测
+export interface CategoryForecast {
+  category: QuotaCategory;
+  used: number;
+  limit: number;
+  left: number;
+  burn_rate_per_day: number;
+  deadline: number;
+  projected_depletion: number | null;
+  earliest_depletion: number | null;
+  latest_depletion: number | null;
+  depletes_before_deadline: boolean;
+}
+
+// 账号额度消耗预测
+export interface BurnRateForecast {
+  account_id: string;
+  generated_at: number;
+  timezone: string;
+  cycle_start: number;
+  reset_time: number;
+  elapsed_days: number;
+  event_count: number;
+  daily_amount_mean: number;
+  daily_amount_stddev: number;
+  confidence: number;
+  categories: CategoryForecast[];
+}
+
+// 账号列表中展示的预测摘要
+export interface ForecastBrief {
+  generated_at: number;
+  depletes_before_reset: boolean;
+  first_depleted_category: QuotaCategory | null;
+  first_depletion: number | null;
+}
+
+// 告警规则监控的指标
+export type AlertMetric =
+  | "fast_request_left"
+  | "fast_request_used"
+  | "fast_request_used_percent"
+  | "slow_request_left"
+  | "advanced_model_left"
+  | "autocomplete_left"
+  | "extra_fast_request_left"
+  | "extra_pack_expires_in_hours"
+  | "reset_in_hours"
+  | "token_expires_in_hours";
+
+// 比较方式
+export type Comparison = "<" | "<=" | ">" | ">=";
+
+// 告警规则
+export interface AlertRule {
+  id: string;
+  name: string;
+  metric: AlertMetric;
+  comparison: Comparison;
+  threshold: number;
+  enabled: boolean;
+  account_ids: string[]; // 为空表示所有账号
+}
+
+// 告警设置
+export interface AlertSettings {
+  rules: AlertRule[];
+  native_notifications: boolean;
+  history_limit: number;
+}
+
+// 告警（alert-raised 事件）
+export interface Alert {
+  id: string;
+  rule_id: string;
+  rule_name: string;
+  account_id: string;
+  account_name: string;
+  metric: AlertMetric;
+  comparison: Comparison;
+  threshold: number;
+  value: number;
+  message: string;
+  raised_at: number;
+  updated_at: number;
+  resolved_at: number | null;
+  snoozed_until: number | null;
+  acknowledged: boolean;
+}
+
+// Webhook 事件类型
+export type WebhookEventKind = "quota_threshold" | "token_expiry" | "credentials_invalid" | "refresh_failed" | "test";
+
+// Webhook 接收端
+export interface WebhookSink {
+  id: string;
+  name: string;
+  url: string;
+  events: WebhookEventKind[];
+  body_template: string; // 支持 {{event}}、{{account_id}}、{{account_name}}、{{message}}、{{occurred_at}}、{{data}}
+  secret: string | null; // 读取时为 "********"，原样保存表示不修改密钥
+  headers: Record<string, string>;
+  enabled: boolean;
+  max_retries: number; // 失败重试次数，最多 10 次
+  timeout_seconds: number; // 单次请求超时（秒），1～60
+}
+
+// Webhook 投递记录
+export interface DeliveryLog {
+  id: string;
+  sink_id: string;
+  sink_name: string;
+  event: WebhookEventKind;
+  account_id: string;
+  url: string;
+  attempts: number;
+  status_code: number | null;
+  success: boolean;
+  error: string | null;
+  request_body: string;
+  response_body: string;
+  delivered_at: number;
+  duration_ms: number;
+}
+
+// 账号月度预算
+export interface Budget {
+  account_id: string;
+  monthly_limit: number;
+  currency: string;
+  usd_rate: number | null; // 1 美元折合预算币种的金额，非 USD 预算必填
+  warning_percent: number;
+  critical_percent: number;
+  subscription: SubscriptionCharge | null; // 为空时不计入订阅费用
+  enabled: boolean;
+}
+
+// 订阅费用换算方式（接口未说明金额单位和扣费周期，需要对照账单填写）
+export interface SubscriptionCharge {
+  amount_divisor: number; // charge_amount 除以该值得到预算币种的金额
+  months_per_charge: number; // 每次扣费覆盖的月数，年付填 12
+}
+
+// 预算级别
+export type BudgetLevel = "ok" | "warning" | "critical";
+
+// 本月预算使用情况（budget-threshold 事件）
+export interface BudgetStatus {
+  account_id: string;
+  month: string;
+  period_start: number;
+  period_end: number;
+  limit: number;
+  currency: string;
+  spent: number;
+  event_spend: number;
+  subscription_spend: number;
+  event_count: number;
+  percent: number;
+  level: BudgetLevel;
+  computed_at: number;
+}
+
+// 异常检测粒度
+export type AnomalyGranularity = "hour" | "day";
+
+// 异常检测的用量指标
+export type AnomalyMetric = "amount" | "requests" | "tokens";
+
+// 异常检测设置
+export interface AnomalySettings {
+  enabled: boolean;
+  granularity: AnomalyGranularity;
+  metric: AnomalyMetric;
+  baseline_days: number;
+  detection_hours: number;
+  z_threshold: number;
+  min_ratio: number;
+  min_value: number;
+  min_active_buckets: number;
+  per_model: boolean;
+  native_notifications: boolean;
+  history_limit: number;
+}
+
+// 用量异常（usage-anomaly 事件）
+export interface UsageAnomaly {
+  id: string;
+  account_id: string;
+  account_name: string;
+  model_name: string | null; // 为空表示账号全部模型
+  granularity: AnomalyGranularity;
+  metric: AnomalyMetric;
+  bucket_key: string;
+  bucket_start: number;
+  bucket_end: number;
+  value: number;
+  request_count: number;
+  baseline_mean: number;
+  baseline_std: number;
+  ratio: number;
+  z_score: number;
+  session_ids: string[];
+  detected_at: number;
+  updated_at: number;
+  acknowledged: boolean;
+}
+
+// 批量请求的并发与频率限制
+export interface BatchLimits {
+  concurrency?: number; // 同时请求的账号数，默认 4
+  requests_per_second?: number; // 每秒最多发起的请求数，0 表示不限制，默认 2
+}
+
+// 团队看板查询
+export interface TeamDashboardQuery extends BatchLimits {
+  account_ids?: string[]; // 为空时包括全部账号
+  filter?: AccountFilter;
+}
+
+// 单个额度类别的使用情况
+export interface CategoryUsage {
+  category: QuotaCategory;
+  used: number;
+  limit: number;
+  left: number;
+}
+
+// 剩余额度最少的类别
+export interface LowestRemaining {
+  account_id: string;
+  account_name: string;
+  category: QuotaCategory;
+  left: number;
+  limit: number;
+}
+
+// 最早重置的账号
+export interface SoonestReset {
+  account_id: string;
+  account_name: string;
+  reset_time: number;
+}
+
+// 团队看板中的单个账号
+export interface TeamAccountRow {
+  account_id: string;
+  name: string;
+  email: string;
+  plan_type: string;
+  categories: CategoryUsage[];
+  reset_time: number;
+  lowest_remaining: LowestRemaining | null;
+  error: string | null; // 拉取失败的原因
+  stale: UsageStaleness | null; // 网络不可用时使用的缓存数据
+}
+
+// 团队汇总
+export interface TeamTotals {
+  account_count: number;
+  succeeded: number;
+  failed: number;
+  categories: CategoryUsage[];
+  soonest_reset: SoonestReset | null;
+  lowest_remaining: LowestRemaining | null;
+}
+
+// 团队看板
+export interface TeamDashboard {
+  rows: TeamAccountRow[];
+  totals: TeamTotals;
+  fetched_at: number;
+}
+
+// 团队看板部分结果（team-dashboard-progress 事件）
+export interface TeamDashboardProgress {
+  row: TeamAccountRow;
+  completed: number;
+  total: number;
+}
+
+// 批量刷新选项
+export interface BatchRefreshOptions extends BatchLimits {
+  account_ids?: string[]; // 为空时刷新全部账号
+  filter?: AccountFilter;
+}
+
+// 单个账号的刷新状态
+export type RefreshStatus = "succeeded" | "failed" | "cancelled";
+
+// 单个账号的刷新结果
+export interface RefreshOutcome {
+  account_id: string;
+  account_name: string;
+  account_email: string;
+  status: RefreshStatus;
+  summary: UsageSummary | null; // 网络不可用时为缓存的使用量
+  error: string | null;
+}
+
+// 批量刷新进度（batch-refresh-progress 事件）
+export interface BatchRefreshProgress {
+  outcome: RefreshOutcome;
+  completed: number;
+  total: number;
+}
+
+// 批量刷新结果
+export interface BatchRefreshReport {
+  total: number;
+  succeeded: number;
+  failed: number;
+  cancelled: number;
+  was_cancelled: boolean;
+  outcomes: RefreshOutcome[];
+  started_at: number;
+  finished_at: number;
+}
+
+// 账号领域事件（account-event 事件）
+export type AccountEvent =
+  | { type: "account_added"; account: AccountBrief }
+  | { type: "account_removed"; account_id: string }
+  | { type: "account_updated"; account: AccountBrief }
+  | { type: "usage_refreshed"; account_id: string; summary: UsageSummary }
+  | { type: "token_refreshed"; account_id: string; expired_at: string | null }
+  | { type: "switch_started"; account_id: string }
+  | { type: "switch_completed"; account_id: string }
+  | { type: "switch_failed"; account_id: string; error: string }
+  | { type: "store_reloaded"; account_count: number };
+
+// 后台刷新设置
+export interface SchedulerSettings extends BatchLimits {
+  enabled: boolean;
+  default_interval_minutes: number;
+  jitter_ratio: number;
+  max_backoff_minutes: number;
+}
+
+// 单个账号的刷新计划
+export interface AccountSchedule {
+  account_id: string;
+  interval_minutes: number | null;
+  last_run_at: number | null;
+  last_success_at: number | null;
+  next_run_at: number;
+  consecutive_failures: number;
+  last_error: string | null;
+}
+
+// 后台刷新状态
+export interface SchedulerStatus {
+  settings: SchedulerSettings;
+  running: boolean;
+  paused_offline: boolean;
+  last_online_check_at: number | null;
+  accounts: AccountSchedule[];
+}
+
 // API 错误
 export interface ApiError {
   message: string;